@@ -1,6 +1,7 @@
 "use client";
 
 import AboutModal from "@/components/AboutModal";
+import BatchModal from "@/components/BatchModal";
 import DraggableHeader from "@/components/DraggableHeader";
 import HelpModal from "@/components/HelpModal";
 import OnboardingModal from "@/components/OnboardingModal";
@@ -8,15 +9,20 @@ import ReviewPanel from "@/components/ReviewPanel";
 import SettingsModal from "@/components/SettingsModal";
 import UpdateModal from "@/components/UpdateModal";
 import UsageModal from "@/components/UsageModal";
-import { UnifiedCorrector, getProviderForModel } from "@/lib/llm";
+import { describeBackendError } from "@/lib/errors";
+import { UnifiedCorrector, getProviderForModel, verifyApiKey } from "@/lib/llm";
 import { MODELS, type ModelInfo, getAvailableModels, getModelById, getRecommendedModel } from "@/lib/models";
-import { deleteKey, getKey, getKeys, migrateFromLocalStorage, setKey } from "@/lib/secure-keys";
+import { runMigrations } from "@/lib/migrations";
+import { type AppPaths, loadAppPaths } from "@/lib/app-paths";
+import { type Policy, loadPolicy } from "@/lib/policy";
+import { type ApiKeyStatus, EMPTY_KEY_STATUS, type KeyDraft, getCredentialStatus, getKeyStatus, hasKey, keyAvailability, setKey } from "@/lib/secure-keys";
 import { CorrectionError, type CorrectionResponse, type Provider, type WritingStyle } from "@/lib/types";
 import { createReview, detectLanguage } from "@/lib/review";
-import { getPresets, savePreset } from "@/lib/presets";
+import { bindKeyProfiles, getBoundKeyProfile, getPresets, savePreset } from "@/lib/presets";
 import type { CorrectionReview, DetectedLanguage, LanguagePreference, Preset } from "@/lib/types";
 import { checkForUpdates, installUpdate, type UpdateInfo } from "@/lib/updater";
 import { trackUsage } from "@/lib/usage-tracker";
+import { type ShortcutProblem, getDefaultShortcut, getShortcutStatus } from "@/lib/shortcut";
 import { useLocale } from "@/lib/useLocale";
 import { useTheme } from "@/lib/useTheme";
 import { isMacOS, isTauri } from "@/lib/utils";
@@ -39,12 +45,9 @@ export default function HomePage() {
   const [presets, setPresets] = useState<Preset[]>([]);
   const [selectedPresetId, setSelectedPresetId] = useState("");
   const [isOnboardingOpen, setIsOnboardingOpen] = useState(false);
-  const [apiKeys, setApiKeys] = useState<Record<Provider, string>>({
-    openai: "",
-    anthropic: "",
-    mistral: "",
-    openrouter: "",
-  });
+  const [keyStatus, setKeyStatus] = useState<Record<Provider, ApiKeyStatus>>(EMPTY_KEY_STATUS);
+  const [policy, setPolicy] = useState<Policy | null>(null);
+  const [appPaths, setAppPaths] = useState<AppPaths | null>(null);
   const [autostartEnabled, setAutostartEnabled] = useState(false);
   const [soundEnabled, setSoundEnabled] = useState(true); // Default: enabled
   const [shortcutKey, setShortcutKey] = useState("]"); // Default: closing bracket
@@ -61,8 +64,10 @@ export default function HomePage() {
   const [showFallbackOption, setShowFallbackOption] = useState(false);
   const [fallbackModelId, setFallbackModelId] = useState<string | null>(null);
   const [isSettingsModalOpen, setIsSettingsModalOpen] = useState(false);
+  const [shortcutProblem, setShortcutProblem] = useState<ShortcutProblem | null>(null);
   const [isHelpModalOpen, setIsHelpModalOpen] = useState(false);
   const [isUsageModalOpen, setIsUsageModalOpen] = useState(false);
+  const [isBatchModalOpen, setIsBatchModalOpen] = useState(false);
   const [isAboutModalOpen, setIsAboutModalOpen] = useState(false);
   const [isCopied, setIsCopied] = useState(false);
   // Update modal state
@@ -75,6 +80,10 @@ export default function HomePage() {
   const textareaRef = useRef<HTMLTextAreaElement>(null);
   const modelDropdownRef = useRef<HTMLDivElement>(null);
   const styleDropdownRef = useRef<HTMLDivElement>(null);
+  // Lets listeners registered once at startup apply presets from the latest render
+  const presetChangeRef = useRef<(id: string) => void>(() => {});
+  // Aborting it cancels the streamed correction in flight
+  const correctionAbortRef = useRef<AbortController | null>(null);
   const detectedLanguage = useMemo(() => detectLanguage(inputText), [inputText]);
   const activeLanguage = languagePreference === "auto" ? detectedLanguage : languagePreference;
 
@@ -87,25 +96,27 @@ export default function HomePage() {
     let disposed = false;
     let unlistenShortcut: (() => void) | undefined;
     let unlistenSettings: (() => void) | undefined;
+    let unlistenPreset: (() => void) | undefined;
     const initializeApp = async () => {
-      // Migrate from localStorage to secure storage (one-time, Tauri only)
+      // Bring stored keys and settings up to the current schema (Tauri only)
       if (isTauri()) {
-        await migrateFromLocalStorage();
+        await runMigrations().catch((error) => console.error("Failed to run migrations:", error));
       }
 
-      // Load all API keys from secure storage
-      const loadedKeys = await getKeys();
+      // Administrator policy, which restricts models and locks settings
+      const loadedPolicy = await loadPolicy();
+      setPolicy(loadedPolicy);
+      setAppPaths(await loadAppPaths());
 
-      setApiKeys(loadedKeys);
+      // Load which API keys are stored; the keys themselves stay in the backend
+      const loadedStatus = await getKeyStatus();
+      const credentialStatus = isTauri() ? await getCredentialStatus().catch(() => null) : null;
+
+      setKeyStatus(loadedStatus);
       setPresets(getPresets());
 
       // Compute available models based on API keys
-      const hasKeys: Record<Provider, boolean> = {
-        openai: !!loadedKeys.openai,
-        anthropic: !!loadedKeys.anthropic,
-        mistral: !!loadedKeys.mistral,
-        openrouter: !!loadedKeys.openrouter,
-      };
+      const hasKeys = keyAvailability(loadedStatus);
       const available = getAvailableModels(hasKeys);
       setAvailableModels(available);
 
@@ -115,7 +126,8 @@ export default function HomePage() {
       if (savedModel && available.some((m) => m.id === savedModel)) {
         setModel(savedModel);
       } else if (available.length > 0) {
-        modelToSet = getRecommendedModel(hasKeys)?.id || available[0].id;
+        const policyDefault = available.find((m) => m.id === loadedPolicy?.defaults.model);
+        modelToSet = policyDefault?.id || getRecommendedModel(hasKeys)?.id || available[0].id;
         setModel(modelToSet);
         localStorage.setItem("selected-model", modelToSet);
       } else {
@@ -124,7 +136,7 @@ export default function HomePage() {
         localStorage.setItem("selected-model", modelToSet);
       }
 
-      const savedStyle = localStorage.getItem("writing-style") as WritingStyle | null;
+      const savedStyle = loadedPolicy?.locked.writingStyle ?? (localStorage.getItem("writing-style") as WritingStyle | null) ?? loadedPolicy?.defaults.writingStyle;
       let styleToSet: WritingStyle = "grammar";
       if (
         savedStyle &&
@@ -170,7 +182,9 @@ export default function HomePage() {
       }
 
       const savedSoundEnabled = localStorage.getItem("sound-enabled");
-      if (savedSoundEnabled !== null) {
+      if (loadedPolicy?.locked.soundEnabled != null) {
+        setSoundEnabled(loadedPolicy.locked.soundEnabled);
+      } else if (savedSoundEnabled !== null) {
         setSoundEnabled(savedSoundEnabled === "true");
       }
 
@@ -185,13 +199,18 @@ export default function HomePage() {
       }
 
       const savedAutoPasteEnabled = localStorage.getItem("auto-paste-enabled");
-      if (savedAutoPasteEnabled !== null) {
+      if (loadedPolicy?.locked.autoPasteEnabled != null) {
+        setAutoPasteEnabled(loadedPolicy.locked.autoPasteEnabled);
+      } else if (savedAutoPasteEnabled !== null) {
         setAutoPasteEnabled(savedAutoPasteEnabled === "true");
       }
 
       // Check if running in Tauri (client-side only)
       setShowGlobalShortcutInfo(isTauri());
-      if (isTauri() && !localStorage.getItem("correctify_onboarding_v1") && !Object.values(loadedKeys).some(Boolean)) {
+      if (credentialStatus?.locked) {
+        // Keys in the encrypted file stay unreadable until the passphrase is entered
+        setIsSettingsModalOpen(true);
+      } else if (isTauri() && !localStorage.getItem("correctify_onboarding_v1") && !Object.values(hasKeys).some(Boolean)) {
         setIsOnboardingOpen(true);
       }
 
@@ -203,14 +222,21 @@ export default function HomePage() {
         ]);
         const stopSettingsListener = await listen("open-settings", () => setIsSettingsModalOpen(true));
         if (disposed) stopSettingsListener(); else unlistenSettings = stopSettingsListener;
+        // correctify://preset/<id> links
+        const stopPresetListener = await listen<string>("apply-preset", (event) => presetChangeRef.current(event.payload));
+        if (disposed) stopPresetListener(); else unlistenPreset = stopPresetListener;
 
         console.log("Setting up global shortcut event listener...");
 
         // Initialize Rust settings from localStorage
-        const currentSoundEnabled = localStorage.getItem("sound-enabled") !== "false"; // Default: true
-        const currentShortcutKey = localStorage.getItem("shortcut-key") || "]";
-        const currentShortcutModifier = localStorage.getItem("shortcut-modifier") || "CmdOrCtrl+Shift";
-        const currentAutoPasteEnabled = localStorage.getItem("auto-paste-enabled") === "true"; // Default: false
+        const currentSoundEnabled = loadedPolicy?.locked.soundEnabled ?? localStorage.getItem("sound-enabled") !== "false"; // Default: true
+        // Without a saved shortcut, use the default for the UI locale's keyboard layouts
+        const defaultShortcut = await getDefaultShortcut().catch(() => ({ modifier: "CmdOrCtrl+Shift", key: "]" }));
+        const currentShortcutKey = localStorage.getItem("shortcut-key") || defaultShortcut.key;
+        const currentShortcutModifier = localStorage.getItem("shortcut-modifier") || defaultShortcut.modifier;
+        setShortcutKey(currentShortcutKey);
+        setShortcutModifier(currentShortcutModifier);
+        const currentAutoPasteEnabled = loadedPolicy?.locked.autoPasteEnabled ?? localStorage.getItem("auto-paste-enabled") === "true"; // Default: false
 
         try {
           await invoke("set_sound_enabled", { enabled: currentSoundEnabled });
@@ -218,22 +244,43 @@ export default function HomePage() {
 
           await invoke("set_auto_paste_enabled", { enabled: currentAutoPasteEnabled });
           console.log("Auto-paste enabled set to:", currentAutoPasteEnabled);
+        } catch (err) {
+          console.error("Failed to initialize settings:", err);
+        }
 
+        // A saved shortcut that is invalid or taken keeps the one startup fell back to;
+        // Settings opens on the shortcut tab so another one can be chosen
+        let shortcutError: unknown = null;
+        try {
           await invoke("update_shortcut", {
             newKey: currentShortcutKey,
             newModifier: currentShortcutModifier
           });
           console.log("Shortcut set to:", `${currentShortcutModifier}+${currentShortcutKey}`);
         } catch (err) {
-          console.error("Failed to initialize settings:", err);
+          console.error("Failed to register the saved shortcut:", err);
+          shortcutError = err;
+        }
+        const shortcutStatus = await getShortcutStatus().catch(() => null);
+        if (shortcutStatus && (shortcutError || !shortcutStatus.registered)) {
+          setShortcutKey(shortcutStatus.key);
+          setShortcutModifier(shortcutStatus.modifier);
+          setShortcutProblem({
+            requested: { key: currentShortcutKey, modifier: currentShortcutModifier },
+            status: shortcutStatus,
+            error: shortcutError,
+          });
+          setIsSettingsModalOpen(true);
+          const { getCurrentWindow } = await import("@tauri-apps/api/window");
+          await getCurrentWindow().show().catch((err) => console.error("Failed to show window:", err));
         }
 
         // Notification permission is intentionally requested only when a user enables notification feedback.
 
         const unlisten = await listen(
           "correct-clipboard-text",
-          async (event: { payload: string }) => {
-            const textToCorrect = event.payload;
+          async (event: { payload: { text: string; style: WritingStyle | null } }) => {
+            const textToCorrect = event.payload.text;
             console.log("=== Received text to correct from global shortcut ===");
             console.log("Text length:", textToCorrect.length);
             console.log("Text preview:", textToCorrect.substring(0, 100));
@@ -246,8 +293,7 @@ export default function HomePage() {
             const provider = getProviderForModel(currentModel);
 
             // Check if API key for this provider is configured
-            const currentApiKey = await getKey(`${provider}-api-key`);
-            if (!currentApiKey) {
+            if (!(await hasKey(provider))) {
               console.error("No API key available - please configure in settings");
               // Send error notification
               try {
@@ -261,6 +307,7 @@ export default function HomePage() {
               } catch (err) {
                 console.error("Failed to send error notification:", err);
               }
+              await invoke("clipboard_correction_failed").catch(() => {});
               return;
             }
 
@@ -269,14 +316,16 @@ export default function HomePage() {
               const correctionStartTime = Date.now();
 
               // Perform correction
+              // A correctify://correct?style= link overrides the selected style for this correction
               const currentStyle =
-                (localStorage.getItem("writing-style") as WritingStyle) || "grammar";
+                event.payload.style || (localStorage.getItem("writing-style") as WritingStyle) || "grammar";
               const customRules = localStorage.getItem("custom-rules") || "";
-              const corrector = new UnifiedCorrector(provider, currentApiKey, currentModel);
+              const corrector = new UnifiedCorrector(provider, currentModel);
               const result = await corrector.correct({
                 text: textToCorrect,
                 writingStyle: currentStyle,
                 customRules: customRules.trim() || undefined,
+                profile: getBoundKeyProfile(provider),
               });
 
               const correctionDuration = Date.now() - correctionStartTime;
@@ -296,6 +345,7 @@ export default function HomePage() {
               console.log("=== Correction completed successfully ===");
             } catch (err) {
               console.error("Failed to correct text:", err);
+              await invoke("clipboard_correction_failed").catch(() => {});
               // Send error notification
               try {
                 const { sendNotification } = await import("@tauri-apps/plugin-notification");
@@ -315,6 +365,8 @@ export default function HomePage() {
           unlisten();
         } else {
           unlistenShortcut = unlisten;
+          // Runs actions such as --correct-clipboard that were waiting for this listener
+          await invoke("frontend_ready").catch((err) => console.error("Failed to release launch actions:", err));
         }
       }
     };
@@ -343,6 +395,7 @@ export default function HomePage() {
       disposed = true;
       unlistenShortcut?.();
       unlistenSettings?.();
+      unlistenPreset?.();
     };
   }, []);
 
@@ -468,7 +521,7 @@ export default function HomePage() {
   };
 
   const handleSaveApiKey = async (
-    newApiKeys: Record<Provider, string>,
+    newApiKeys: Record<Provider, KeyDraft>,
     newAutostartEnabled: boolean,
     newSoundEnabled: boolean,
     newShortcutKey: string,
@@ -476,38 +529,25 @@ export default function HomePage() {
     newAutoPasteEnabled: boolean,
   ) => {
     // Update state
-    setApiKeys(newApiKeys);
     setAutostartEnabled(newAutostartEnabled);
     setSoundEnabled(newSoundEnabled);
     setShortcutKey(newShortcutKey);
     setShortcutModifier(newShortcutModifier);
     setAutoPasteEnabled(newAutoPasteEnabled);
 
-    // Save all API keys to secure storage
+    // Save newly entered API keys to secure storage; an empty field keeps the stored key
     try {
       for (const provider of Object.keys(newApiKeys) as Provider[]) {
-        const keyValue = newApiKeys[provider];
-        const keyName = `${provider}-api-key`;
-
-        if (keyValue && keyValue.trim().length > 0) {
-          await setKey(keyName, keyValue);
-        } else {
-          // Remove key if empty
-          try {
-            await deleteKey(keyName);
-          } catch (err) {
-            // Ignore if key doesn't exist
-          }
+        const { key, profile } = newApiKeys[provider];
+        if (key.trim().length > 0) {
+          await setKey(provider, key, profile);
         }
       }
 
       // Recompute available models
-      const hasKeys: Record<Provider, boolean> = {
-        openai: !!newApiKeys.openai,
-        anthropic: !!newApiKeys.anthropic,
-        mistral: !!newApiKeys.mistral,
-        openrouter: !!newApiKeys.openrouter,
-      };
+      const nextStatus = await getKeyStatus();
+      setKeyStatus(nextStatus);
+      const hasKeys = keyAvailability(nextStatus);
       const available = getAvailableModels(hasKeys);
       setAvailableModels(available);
 
@@ -532,7 +572,7 @@ export default function HomePage() {
       }
     } catch (error) {
       console.error("Failed to save API keys:", error);
-      alert("Failed to save API keys securely. Please try again.");
+      alert(describeBackendError(error, messages));
       return;
     }
 
@@ -564,19 +604,22 @@ export default function HomePage() {
           console.log(`Shortcut updated to: ${newShortcutModifier}+${newShortcutKey}`);
         }
 
-        // Handle autostart
-        const { enable, disable, isEnabled } = await import("@tauri-apps/plugin-autostart");
-        const currentlyEnabled = await isEnabled();
-
-        if (newAutostartEnabled && !currentlyEnabled) {
-          await enable();
-          console.log("Autostart enabled");
-        } else if (!newAutostartEnabled && currentlyEnabled) {
-          await disable();
-          console.log("Autostart disabled");
+        // Handle autostart; portable copies never register themselves
+        if (!appPaths?.portable) {
+          const { enable, disable, isEnabled } = await import("@tauri-apps/plugin-autostart");
+          const currentlyEnabled = await isEnabled();
+
+          if (newAutostartEnabled && !currentlyEnabled) {
+            await enable();
+            console.log("Autostart enabled");
+          } else if (!newAutostartEnabled && currentlyEnabled) {
+            await disable();
+            console.log("Autostart disabled");
+          }
         }
       } catch (err) {
         console.error("Failed to update settings:", err);
+        setError(describeBackendError(err, messages));
       }
     }
   };
@@ -596,11 +639,11 @@ export default function HomePage() {
       return;
     }
 
-    // Get provider and API key for selected model
+    // Check that the selected model's provider has a stored key
     const provider = getProviderForModel(model);
-    const modelApiKey = apiKeys[provider];
+    const boundProfile = getBoundKeyProfile(provider);
 
-    if (!modelApiKey || !modelApiKey.trim()) {
+    if (!keyStatus[provider].set) {
       const providerName = provider.charAt(0).toUpperCase() + provider.slice(1);
       setError(`Please add your ${providerName} API key in Settings`);
       setIsSettingsModalOpen(true);
@@ -622,6 +665,8 @@ export default function HomePage() {
     setMeta(null);
 
     const startTime = Date.now();
+    const abortController = new AbortController();
+    correctionAbortRef.current = abortController;
 
     try {
       {
@@ -635,12 +680,15 @@ export default function HomePage() {
         }
 
         const customRules = localStorage.getItem("custom-rules") || "";
-        const corrector = new UnifiedCorrector(provider, modelApiKey, model);
+        const corrector = new UnifiedCorrector(provider, model);
         const result = await corrector.correct({
           text: inputText,
           writingStyle: writingStyle,
           customRules: customRules.trim() || undefined,
           language: activeLanguage,
+          profile: boundProfile,
+          signal: abortController.signal,
+          onPartial: setOutputText,
         });
 
         const duration = Date.now() - startTime;
@@ -661,6 +709,7 @@ export default function HomePage() {
         trackUsage({
           timestamp: Date.now(),
           provider,
+          profile: result.profile,
           model,
           inputTokens: result.usage.inputTokens,
           outputTokens: result.usage.outputTokens,
@@ -670,6 +719,7 @@ export default function HomePage() {
           language: activeLanguage,
           detectedEdits: nextReview.edits.length,
           acceptedEdits: nextReview.edits.length,
+          cached: result.cached,
         });
 
         // Play completed sound
@@ -680,6 +730,11 @@ export default function HomePage() {
         }
       }
     } catch (err) {
+      // A cancelled correction is not a failure; the text so far is discarded
+      if (abortController.signal.aborted) {
+        setOutputText("");
+        return;
+      }
       const errorMsg = err instanceof Error ? err.message : "Failed to connect to the server";
       setError(errorMsg);
 
@@ -687,6 +742,7 @@ export default function HomePage() {
       trackUsage({
         timestamp: Date.now(),
         provider,
+        profile: boundProfile ?? keyStatus[provider].activeProfile ?? undefined,
         model,
         inputTokens: 0,
         outputTokens: 0,
@@ -705,6 +761,7 @@ export default function HomePage() {
         setShowFallbackOption(true);
       }
     } finally {
+      correctionAbortRef.current = null;
       setIsLoading(false);
     }
   };
@@ -753,7 +810,10 @@ export default function HomePage() {
     const name = window.prompt("Preset name");
     if (!name?.trim()) return;
     const now = Date.now();
-    const preset: Preset = { id: crypto.randomUUID(), name: name.trim(), writingStyle, customRules: localStorage.getItem("custom-rules") || "", language: languagePreference, createdAt: now, updatedAt: now };
+    // Pin the profiles active right now, so the preset keeps billing the same accounts
+    const keyProfiles = Object.fromEntries((Object.keys(keyStatus) as Provider[]).flatMap((provider) => { const active = keyStatus[provider].activeProfile; return active ? [[provider, active]] : []; }));
+    const preset: Preset = { id: crypto.randomUUID(), name: name.trim(), writingStyle, customRules: localStorage.getItem("custom-rules") || "", language: languagePreference, keyProfiles, createdAt: now, updatedAt: now };
+    bindKeyProfiles(keyProfiles);
     setPresets(savePreset(preset));
     setSelectedPresetId(preset.id);
   };
@@ -761,22 +821,41 @@ export default function HomePage() {
   const handlePresetChange = (id: string) => {
     setSelectedPresetId(id);
     const preset = presets.find((item) => item.id === id);
+    bindKeyProfiles(preset?.keyProfiles);
     if (!preset) return;
-    setWritingStyle(preset.writingStyle);
     setLanguagePreference(preset.language);
-    localStorage.setItem("writing-style", preset.writingStyle);
     localStorage.setItem("custom-rules", preset.customRules);
+    // A style locked by policy wins over the preset's
+    if (policy?.locked.writingStyle) return;
+    setWritingStyle(preset.writingStyle);
+    localStorage.setItem("writing-style", preset.writingStyle);
+  };
+
+  presetChangeRef.current = (id: string) => {
+    if (!presets.some((item) => item.id === id)) {
+      console.warn("Ignoring link to unknown preset:", id);
+      return;
+    }
+    handlePresetChange(id);
+  };
+
+  const handleCredentialsChanged = async () => {
+    // Steps that needed the locked credential file can run now
+    await runMigrations().catch((error) => console.error("Failed to run migrations:", error));
+    const loadedStatus = await getKeyStatus();
+    setKeyStatus(loadedStatus);
+    setAvailableModels(getAvailableModels(keyAvailability(loadedStatus)));
   };
 
   const handleOnboarding = async (provider: Provider, key: string) => {
     const modelForProvider = provider === "openai" ? getModelById("gpt-5.4-mini") : MODELS.find((item) => item.provider === provider);
     if (!modelForProvider) return false;
     try {
-      await new UnifiedCorrector(provider, key, modelForProvider.id).correct({ text: "Correctify test.", writingStyle: "grammar" });
-      await setKey(`${provider}-api-key`, key);
-      const nextKeys = { ...apiKeys, [provider]: key };
-      setApiKeys(nextKeys);
-      setAvailableModels(getAvailableModels({ openai: !!nextKeys.openai, anthropic: !!nextKeys.anthropic, mistral: !!nextKeys.mistral, openrouter: !!nextKeys.openrouter }));
+      if ((await verifyApiKey(provider, key)).status !== "valid") return false;
+      await setKey(provider, key);
+      const nextStatus = await getKeyStatus();
+      setKeyStatus(nextStatus);
+      setAvailableModels(getAvailableModels(keyAvailability(nextStatus)));
       setModel(modelForProvider.id);
       localStorage.setItem("selected-model", modelForProvider.id);
       localStorage.setItem("correctify_onboarding_v1", "complete");
@@ -791,6 +870,7 @@ export default function HomePage() {
         onSettingsClick={() => setIsSettingsModalOpen(true)}
         onHelpClick={() => setIsHelpModalOpen(true)}
         onUsageClick={() => setIsUsageModalOpen(true)}
+        onBatchClick={() => setIsBatchModalOpen(true)}
         onAboutClick={handleOpenAbout}
         onReloadClick={handleReload}
         onQuitClick={handleQuit}
@@ -804,19 +884,22 @@ export default function HomePage() {
 
       <SettingsModal
         isOpen={isSettingsModalOpen}
-        onClose={() => setIsSettingsModalOpen(false)}
+        onClose={() => {
+          setIsSettingsModalOpen(false);
+          setShortcutProblem(null);
+        }}
         onSave={handleSaveApiKey}
-        currentApiKeys={apiKeys}
+        keyStatus={keyStatus}
         currentAutostartEnabled={autostartEnabled}
         currentSoundEnabled={soundEnabled}
         currentShortcutKey={shortcutKey}
         currentShortcutModifier={shortcutModifier}
         currentAutoPasteEnabled={autoPasteEnabled}
-        onTestApiKey={async (provider, key) => {
-          const modelInfo = MODELS.find((item) => item.provider === provider);
-          if (!modelInfo) return false;
-          try { await new UnifiedCorrector(provider, key, modelInfo.id).correct({ text: "Validation test.", writingStyle: "grammar" }); return true; } catch { return false; }
-        }}
+        onCredentialsChanged={handleCredentialsChanged}
+        onTestApiKey={verifyApiKey}
+        policy={policy}
+        appPaths={appPaths}
+        shortcutProblem={shortcutProblem}
       />
 
       <HelpModal
@@ -861,6 +944,14 @@ export default function HomePage() {
 
       <UsageModal isOpen={isUsageModalOpen} onClose={() => setIsUsageModalOpen(false)} />
 
+      <BatchModal
+        isOpen={isBatchModalOpen}
+        onClose={() => setIsBatchModalOpen(false)}
+        model={model}
+        writingStyle={writingStyle}
+        language={languagePreference === "auto" ? undefined : languagePreference}
+      />
+
       <main className="min-h-screen flex justify-center p-6 bg-transparent pt-24 transition-colors overflow-auto">
         <div className="w-full max-w-4xl">
           <form onSubmit={handleSubmit} className="space-y-6">
@@ -886,7 +977,8 @@ export default function HomePage() {
                         type="button"
                         onClick={() => setIsStyleDropdownOpen(!isStyleDropdownOpen)}
                         className="flex items-center gap-2 px-3 py-1.5 text-xs font-medium text-foreground bg-background/40 hover:bg-background/80 hover:text-foreground  rounded-lg focus:outline-none focus:ring-2 focus:ring-primary cursor-pointer disabled:opacity-50 disabled:cursor-not-allowed transition-colors min-w-35"
-                        disabled={isLoading}
+                        disabled={isLoading || Boolean(policy?.locked.writingStyle)}
+                        title={policy?.locked.writingStyle ? messages.policy.managed : undefined}
                       >
                         <span className="flex-1 text-left">
                           {styleOptions.find((option) => option.value === writingStyle)?.label}
@@ -1064,6 +1156,15 @@ export default function HomePage() {
             >
               {isLoading ? messages.home.correctingButton : messages.home.correctButton}
             </button>
+            {isLoading && (
+              <button
+                type="button"
+                onClick={() => correctionAbortRef.current?.abort()}
+                className="w-full px-6 py-2 text-sm font-medium text-foreground hover:bg-foreground/5 rounded-lg transition-colors"
+              >
+                {messages.home.cancelButton}
+              </button>
+            )}
 
             <div className="flex items-center justify-center gap-4 text-sm text-foreground/60">
               <div className="flex items-center gap-1.5">
@@ -1236,6 +1337,13 @@ export default function HomePage() {
             </div>
           )}
 
+          {isLoading && outputText && (
+            <div className="mt-6 p-4 bg-card border border-border rounded-lg">
+              <p className="mb-2 text-xs font-medium text-foreground/60">{messages.home.partialLabel}</p>
+              <p className="whitespace-pre-wrap text-sm text-foreground">{outputText}</p>
+            </div>
+          )}
+
           {review && meta && <ReviewPanel review={review} model={meta.model || model} duration={meta.duration || 0} estimatedCost={(() => { const info = getModelById(model); return info?.costPer1MToken ? ((meta.usage?.inputTokens || 0) * info.costPer1MToken.input + (meta.usage?.outputTokens || 0) * info.costPer1MToken.output) / 1_000_000 : 0; })()} onChange={setReview} onCopy={handleCopyReview} onReplace={(text) => { setInputText(text); setReview(null); setOutputText(""); }} onRestore={() => { setInputText(review.original); setReview(null); setOutputText(""); }} />}
 
         </div>
@@ -0,0 +1,227 @@
+"use client";
+
+import { type BatchFileReport, type BatchOutput, type BatchSummary, correctFiles, pickBatchPaths } from "@/lib/batch";
+import { describeBackendError } from "@/lib/errors";
+import { getModelById } from "@/lib/models";
+import { getBoundKeyProfile } from "@/lib/presets";
+import type { DetectedLanguage, WritingStyle } from "@/lib/types";
+import { trackUsage } from "@/lib/usage-tracker";
+import { useLocale } from "@/lib/useLocale";
+import { FileText, X } from "lucide-react";
+import { useEffect, useState } from "react";
+
+const STYLES: WritingStyle[] = ["grammar", "formal", "informal", "collaborative", "concise"];
+
+interface BatchModalProps {
+  isOpen: boolean;
+  onClose: () => void;
+  model: string;
+  writingStyle: WritingStyle;
+  /** The language chosen in the main window; detection works per text, so files go without one otherwise. */
+  language?: DetectedLanguage;
+}
+
+export default function BatchModal({ isOpen, onClose, model, writingStyle, language }: BatchModalProps) {
+  const { messages } = useLocale();
+  const [paths, setPaths] = useState<string[]>([]);
+  const [style, setStyle] = useState<WritingStyle>(writingStyle);
+  const [output, setOutput] = useState<BatchOutput>("sibling");
+  const [isRunning, setIsRunning] = useState(false);
+  const [progress, setProgress] = useState({ completed: 0, total: 0 });
+  const [files, setFiles] = useState<Record<string, BatchFileReport>>({});
+  const [summary, setSummary] = useState<BatchSummary | null>(null);
+  const [error, setError] = useState("");
+
+  // Start from the style selected in the main window each time the dialog opens
+  useEffect(() => {
+    if (isOpen) setStyle(writingStyle);
+  }, [isOpen, writingStyle]);
+
+  const handlePick = async (folder: boolean) => {
+    const picked = await pickBatchPaths(folder);
+    if (picked.length === 0) return;
+    setPaths(picked);
+    setSummary(null);
+    setFiles({});
+    setError("");
+  };
+
+  const handleStart = async () => {
+    setIsRunning(true);
+    setSummary(null);
+    setFiles({});
+    setError("");
+    setProgress({ completed: 0, total: 0 });
+    const customRules = (localStorage.getItem("custom-rules") || "").trim();
+    const provider = getModelById(model)?.provider;
+    const profile = provider ? getBoundKeyProfile(provider) : undefined;
+    try {
+      const result = await correctFiles(
+        { paths, output, model, writingStyle: style, customRules: customRules || undefined, language, profile },
+        ({ completed, total, file }) => {
+          setProgress({ completed, total });
+          setFiles((previous) => ({ ...previous, [file.path]: file }));
+          if (file.status === "started" || !provider) return;
+          trackUsage({
+            timestamp: Date.now(),
+            provider,
+            profile: file.profile ?? profile,
+            model,
+            inputTokens: file.usage?.inputTokens ?? 0,
+            outputTokens: file.usage?.outputTokens ?? 0,
+            duration: file.durationMs,
+            success: file.status !== "failed",
+            error: file.error ? describeBackendError(file.error, messages) : undefined,
+            writingStyle: style,
+            language,
+            detectedEdits: file.edits,
+            acceptedEdits: file.edits,
+            cached: file.cached,
+          });
+        },
+      );
+      setSummary(result);
+    } catch (err) {
+      setError(describeBackendError(err, messages));
+    } finally {
+      setIsRunning(false);
+    }
+  };
+
+  if (!isOpen) return null;
+
+  const statusLabels: Record<BatchFileReport["status"], string> = {
+    started: messages.batchModal.statusStarted,
+    corrected: messages.batchModal.statusCorrected,
+    unchanged: messages.batchModal.statusUnchanged,
+    failed: messages.batchModal.statusFailed,
+  };
+  const statusColors: Record<BatchFileReport["status"], string> = {
+    started: "text-foreground/60",
+    corrected: "text-success-text",
+    unchanged: "text-foreground/60",
+    failed: "text-error-text",
+  };
+  const reports = summary ? summary.files : Object.values(files);
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center backdrop-blur-sm"
+      style={{ backgroundColor: "var(--color-modal-backdrop)" }}
+    >
+      <div className="relative w-full max-w-[550px] mx-4 bg-card-bg rounded-lg shadow-xl transition-colors" style={{ backgroundColor: "var(--card-bg-solid)" }}>
+        {/* Header */}
+        <div className="flex items-center justify-between p-6 border-b border-border">
+          <div className="flex items-center gap-3">
+            <div className="p-2 bg-primary/10 rounded-lg">
+              <FileText className="w-5 h-5 text-primary" />
+            </div>
+            <div>
+              <h2 className="text-xl font-semibold text-foreground">{messages.batchModal.title}</h2>
+              <p className="text-xs text-text-muted mt-0.5">{messages.batchModal.subtitle}</p>
+            </div>
+          </div>
+          <button
+            type="button"
+            onClick={onClose}
+            disabled={isRunning}
+            className="p-1 hover:bg-foreground/5 rounded-lg transition-colors disabled:opacity-50"
+            aria-label={messages.batchModal.close}
+          >
+            <X className="w-5 h-5 text-foreground" />
+          </button>
+        </div>
+
+        {/* Body */}
+        <div className="p-6 space-y-5 max-h-[60vh] overflow-y-auto">
+          <div className="space-y-2">
+            <div className="flex items-center gap-3">
+              <button type="button" onClick={() => handlePick(false)} disabled={isRunning} className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">
+                {messages.batchModal.chooseFiles}
+              </button>
+              <button type="button" onClick={() => handlePick(true)} disabled={isRunning} className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">
+                {messages.batchModal.chooseFolder}
+              </button>
+            </div>
+            <p className="text-xs text-foreground/60">
+              {paths.length === 0
+                ? messages.batchModal.nothingChosen
+                : paths.length === 1
+                  ? paths[0]
+                  : messages.batchModal.chosenCount.replace("{count}", paths.length.toString())}
+            </p>
+          </div>
+
+          <label className="flex items-center justify-between gap-3 text-sm text-foreground">
+            {messages.home.styleLabel}
+            <select value={style} onChange={(e) => setStyle(e.target.value as WritingStyle)} disabled={isRunning} className="rounded border border-border bg-background px-2 py-1 text-xs text-foreground">
+              {STYLES.map((value) => (
+                <option key={value} value={value}>{messages.home.styleOptions[value].label}</option>
+              ))}
+            </select>
+          </label>
+
+          <fieldset className="space-y-2" disabled={isRunning}>
+            <legend className="text-sm font-medium text-foreground">{messages.batchModal.outputLabel}</legend>
+            <label className="flex items-start gap-2 text-xs text-foreground/80">
+              <input type="radio" name="batch-output" checked={output === "sibling"} onChange={() => setOutput("sibling")} className="mt-0.5" />
+              {messages.batchModal.outputSibling}
+            </label>
+            <label className="flex items-start gap-2 text-xs text-foreground/80">
+              <input type="radio" name="batch-output" checked={output === "in-place"} onChange={() => setOutput("in-place")} className="mt-0.5" />
+              {messages.batchModal.outputInPlace}
+            </label>
+          </fieldset>
+
+          {(isRunning || summary) && progress.total > 0 && (
+            <div className="space-y-1">
+              <div className="h-2 w-full rounded bg-foreground/10">
+                <div className="h-2 rounded bg-primary transition-all" style={{ width: `${(progress.completed / progress.total) * 100}%` }} />
+              </div>
+              <p className="text-xs text-foreground/60">
+                {messages.batchModal.progress.replace("{completed}", progress.completed.toString()).replace("{total}", progress.total.toString())}
+              </p>
+            </div>
+          )}
+
+          {summary && (
+            <p className="text-sm text-foreground">
+              {messages.batchModal.summary
+                .replace("{corrected}", summary.corrected.toString())
+                .replace("{unchanged}", summary.unchanged.toString())
+                .replace("{failed}", summary.failed.toString())
+                .replace("{seconds}", (summary.durationMs / 1000).toFixed(1))}
+            </p>
+          )}
+
+          {reports.length > 0 && (
+            <ul className="space-y-1 text-xs">
+              {reports.map((file) => (
+                <li key={file.path} className="flex items-start justify-between gap-3">
+                  <span className="truncate text-foreground/80" title={file.output ?? file.path}>{file.path}</span>
+                  <span className={`shrink-0 ${statusColors[file.status]}`} title={file.error?.details}>
+                    {file.error ? describeBackendError(file.error, messages) : statusLabels[file.status]}
+                  </span>
+                </li>
+              ))}
+            </ul>
+          )}
+
+          {error && <p className="text-xs text-error-text">{error}</p>}
+        </div>
+
+        {/* Footer */}
+        <div className="px-6 py-4 border-t border-border">
+          <button
+            type="button"
+            onClick={handleStart}
+            disabled={isRunning || paths.length === 0}
+            className="w-full px-4 py-2 text-sm font-medium bg-primary text-button-text rounded-lg hover:bg-primary-hover transition-colors disabled:opacity-50"
+          >
+            {isRunning ? messages.batchModal.running : messages.batchModal.start}
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -4,6 +4,7 @@ import { useLocale } from "@/lib/useLocale";
 import type { Theme } from "@/lib/useTheme";
 import {
   BarChart3,
+  FileText,
   HelpCircle,
   Info,
   MoreVertical,
@@ -17,6 +18,7 @@ interface DraggableHeaderProps {
   onSettingsClick: () => void;
   onHelpClick: () => void;
   onUsageClick: () => void;
+  onBatchClick: () => void;
   onAboutClick: () => void;
   onReloadClick: () => void;
   onQuitClick: () => void;
@@ -30,6 +32,7 @@ export default function DraggableHeader({
   onSettingsClick,
   onHelpClick,
   onUsageClick,
+  onBatchClick,
   onAboutClick,
   onReloadClick,
   onQuitClick,
@@ -187,6 +190,18 @@ export default function DraggableHeader({
                 <span>{messages.header.usageStats}</span>
               </button>
 
+              <button
+                type="button"
+                onClick={() => {
+                  onBatchClick();
+                  setIsMenuOpen(false);
+                }}
+                className="w-full flex items-center gap-3 px-4 py-3 text-sm text-foreground hover:bg-foreground/5 transition-colors"
+              >
+                <FileText className="w-4 h-4" />
+                <span>{messages.header.correctFiles}</span>
+              </button>
+
               <div className="border-t border-border" />
 
               <button
@@ -1,6 +1,13 @@
 "use client";
 
-import { deleteKey } from "@/lib/secure-keys";
+import { BACKUP_EXTENSION, type BackupPreview, type ConflictResolution, exportBackup, previewBackup, restoreBackup, settingConflicts } from "@/lib/backup";
+import { describeBackendError } from "@/lib/errors";
+import { type CacheStatus, type KeyVerification, clearCorrectionCache, getCorrectionCache, setCorrectionCacheEnabled } from "@/lib/llm";
+import { type DictionaryTerms, dictionaryToText, getDictionary, importDictionary, parseDictionary, pickWordList, setDictionary } from "@/lib/dictionary";
+import type { AppPaths } from "@/lib/app-paths";
+import { type Policy, isProviderAllowed } from "@/lib/policy";
+import { type ApiKeyStatus, type CredentialStatus, DEFAULT_KEY_PROFILE, type KeyDraft, type KeyProfile, deleteKey, getCredentialStatus, migrateCredentials, setActiveKeyProfile, unlockCredentials } from "@/lib/secure-keys";
+import { type ShortcutCheck, type ShortcutProblem, captureShortcut, checkShortcut, formatShortcut } from "@/lib/shortcut";
 import type { Provider } from "@/lib/types";
 import { useLocale, type Locale } from "@/lib/useLocale";
 import { isMacOS, isTauri } from "@/lib/utils";
@@ -12,20 +19,35 @@ interface SettingsModalProps {
   isOpen: boolean;
   onClose: () => void;
   onSave: (
-    apiKeys: Record<Provider, string>,
+    apiKeys: Record<Provider, KeyDraft>,
     autostartEnabled: boolean,
     soundEnabled: boolean,
     shortcutKey: string,
     shortcutModifier: string,
     autoPasteEnabled: boolean,
   ) => void;
-  currentApiKeys: Record<Provider, string>;
+  /** Stored keys never reach the interface; only whether one is set and a masked preview. */
+  keyStatus: Record<Provider, ApiKeyStatus>;
   currentAutostartEnabled: boolean;
   currentSoundEnabled: boolean;
   currentShortcutKey: string;
   currentShortcutModifier: string;
   currentAutoPasteEnabled: boolean;
-  onTestApiKey: (provider: Provider, key: string) => Promise<boolean>;
+  onCredentialsChanged: () => void;
+  onTestApiKey: (provider: Provider, key: string) => Promise<KeyVerification>;
+  /** Administrator policy; locked settings and forbidden providers are disabled. */
+  policy: Policy | null;
+  /** Portable copies hide the autostart option and show where their data is kept. */
+  appPaths: AppPaths | null;
+  /** Set when the saved shortcut could not be registered; Settings opens on the shortcut tab. */
+  shortcutProblem: ShortcutProblem | null;
+}
+
+const NO_NEW_KEYS: Record<Provider, string> = { openai: "", anthropic: "", mistral: "", openrouter: "" };
+
+function activeProfileNames(keyStatus: Record<Provider, ApiKeyStatus>): Record<Provider, string> {
+  const name = (provider: Provider) => keyStatus[provider].activeProfile ?? DEFAULT_KEY_PROFILE;
+  return { openai: name("openai"), anthropic: name("anthropic"), mistral: name("mistral"), openrouter: name("openrouter") };
 }
 
 const API_KEY_CONFIG: Array<{
@@ -60,19 +82,57 @@ const API_KEY_CONFIG: Array<{
   },
 ];
 
+const DIAGNOSTIC_SETTINGS = [
+  "selected-model",
+  "writing-style",
+  "app-language",
+  "autostart-enabled",
+  "sound-enabled",
+  "shortcut-key",
+  "shortcut-modifier",
+  "auto-paste-enabled",
+];
+
+// A key whose quota ran out is still the right key, so it may be saved
+function isUsableKey(validation: "testing" | KeyVerification | undefined): boolean {
+  return typeof validation === "object" && (validation.status === "valid" || validation.status === "insufficient-quota");
+}
+
+function KeyVerificationStatus({ validation }: { validation: "testing" | KeyVerification | undefined }) {
+  const { messages } = useLocale();
+  if (typeof validation !== "object") return null;
+  switch (validation.status) {
+    case "valid":
+      return <span className="text-xs text-success-text">{validation.account ? messages.apiModal.keyValidAccount.replace("{account}", validation.account) : messages.apiModal.keyValid}</span>;
+    case "insufficient-quota":
+      return <span className="text-xs text-warning-text" title={validation.details ?? undefined}>{messages.apiModal.keyInsufficientQuota}</span>;
+    case "invalid":
+      return <span className="text-xs text-error-text" title={validation.details ?? undefined}>{messages.apiModal.keyInvalid}</span>;
+    case "network-error":
+      return <span className="text-xs text-error-text" title={validation.details ?? undefined}>{messages.apiModal.keyNetworkError}</span>;
+  }
+}
+
 export default function SettingsModal({
   isOpen,
   onClose,
   onSave,
-  currentApiKeys,
+  keyStatus,
   currentAutostartEnabled,
   currentSoundEnabled,
   currentShortcutKey,
   currentShortcutModifier,
   currentAutoPasteEnabled,
+  onCredentialsChanged,
   onTestApiKey,
+  policy,
+  appPaths,
+  shortcutProblem,
 }: SettingsModalProps) {
-  const [apiKeys, setApiKeys] = useState<Record<Provider, string>>(currentApiKeys);
+  // Newly entered keys; an empty field keeps the stored key
+  const [apiKeys, setApiKeys] = useState<Record<Provider, string>>(NO_NEW_KEYS);
+  // The profile each key field edits; a name that does not exist yet creates a profile
+  const [profileNames, setProfileNames] = useState<Record<Provider, string>>(() => activeProfileNames(keyStatus));
   const [autostartEnabled, setAutostartEnabled] = useState(currentAutostartEnabled);
   const [soundEnabled, setSoundEnabled] = useState(currentSoundEnabled);
   const [shortcutKey, setShortcutKey] = useState(currentShortcutKey);
@@ -92,8 +152,23 @@ export default function SettingsModal({
   const languageDropdownRef = useRef<HTMLDivElement>(null);
   const { messages, locale, changeLocale } = useLocale();
   const [isMac, setIsMac] = useState(false); // Default to false to avoid hydration mismatch
-  const [keyValidation, setKeyValidation] = useState<Partial<Record<Provider, "testing" | "valid" | "invalid">>>({});
+  const [keyValidation, setKeyValidation] = useState<Partial<Record<Provider, "testing" | KeyVerification>>>({});
   const [saveError, setSaveError] = useState("");
+  const [diagnosticsStatus, setDiagnosticsStatus] = useState("");
+  const [backupPassphrase, setBackupPassphrase] = useState("");
+  const [backupStatus, setBackupStatus] = useState("");
+  const [pendingRestore, setPendingRestore] = useState<{ path: string; preview: BackupPreview } | null>(null);
+  const [restoreResolution, setRestoreResolution] = useState<ConflictResolution>("keep-existing");
+  const [shortcutCheck, setShortcutCheck] = useState<ShortcutCheck | null>(null);
+  const [shortcutError, setShortcutError] = useState("");
+  const [isRecordingShortcut, setIsRecordingShortcut] = useState(false);
+  const [credentialStatus, setCredentialStatus] = useState<CredentialStatus | null>(null);
+  const [passphrase, setPassphrase] = useState("");
+  const [credentialMessage, setCredentialMessage] = useState("");
+  const [cacheStatus, setCacheStatus] = useState<CacheStatus | null>(null);
+  const [cacheError, setCacheError] = useState("");
+  const [dictionaryText, setDictionaryText] = useState("");
+  const [dictionaryError, setDictionaryError] = useState("");
 
   useEffect(() => {
     // Detect OS only on client side to avoid hydration mismatch
@@ -146,14 +221,15 @@ export default function SettingsModal({
   }, []);
 
   useEffect(() => {
-    setApiKeys(currentApiKeys);
+    setApiKeys(NO_NEW_KEYS);
+    setProfileNames(activeProfileNames(keyStatus));
     setAutostartEnabled(currentAutostartEnabled);
     setSoundEnabled(currentSoundEnabled);
     setShortcutKey(currentShortcutKey);
     setShortcutModifier(currentShortcutModifier);
     setAutoPasteEnabled(currentAutoPasteEnabled);
   }, [
-    currentApiKeys,
+    keyStatus,
     currentAutostartEnabled,
     currentSoundEnabled,
     currentShortcutKey,
@@ -161,15 +237,126 @@ export default function SettingsModal({
     currentAutoPasteEnabled,
   ]);
 
+  useEffect(() => {
+    if (isOpen && shortcutProblem) setActiveTab("global-shortcut");
+  }, [isOpen, shortcutProblem]);
+
+  useEffect(() => {
+    if (!isOpen || !isTauri()) return;
+    getCredentialStatus()
+      .then((status) => {
+        setCredentialStatus(status);
+        if (status.locked) setActiveTab("api-keys");
+      })
+      .catch((error) => console.error("Failed to read credential status:", error));
+    getCorrectionCache()
+      .then(setCacheStatus)
+      .catch((error) => console.error("Failed to read the correction cache:", error));
+    getDictionary()
+      .then((terms) => setDictionaryText(dictionaryToText(terms)))
+      .catch((error) => console.error("Failed to read the dictionary:", error));
+  }, [isOpen]);
+
+  // The cache is changed right away rather than on Save, like backups and diagnostics
+  const handleCacheChange = async (change: () => Promise<CacheStatus>) => {
+    try {
+      setCacheStatus(await change());
+      setCacheError("");
+    } catch (error) {
+      setCacheError(describeBackendError(error, messages));
+    }
+  };
+
+  // The dictionary is stored by the backend as soon as it is edited or imported
+  const handleDictionaryChange = async (change: () => Promise<DictionaryTerms>) => {
+    try {
+      setDictionaryText(dictionaryToText(await change()));
+      setDictionaryError("");
+    } catch (error) {
+      setDictionaryError(describeBackendError(error, messages));
+    }
+  };
+
+  const handleImportWordList = async () => {
+    const path = await pickWordList();
+    if (path) await handleDictionaryChange(() => importDictionary(path));
+  };
+
+  const refreshCredentials = async (message: string) => {
+    setPassphrase("");
+    setCredentialMessage(message);
+    setCredentialStatus(await getCredentialStatus());
+    onCredentialsChanged();
+  };
+
+  const handleUnlockCredentials = async () => {
+    try {
+      await unlockCredentials(passphrase);
+      await refreshCredentials(messages.apiModal.credentialUnlocked);
+    } catch (error) {
+      setCredentialMessage(describeBackendError(error, messages));
+    }
+  };
+
+  const handleMigrateCredentials = async () => {
+    if (!credentialStatus) return;
+    const target = credentialStatus.backend === "keyring" ? "encrypted-file" : "keyring";
+    try {
+      await migrateCredentials(target, passphrase);
+      await refreshCredentials(messages.apiModal.credentialMigrated);
+    } catch (error) {
+      setCredentialMessage(describeBackendError(error, messages));
+    }
+  };
+
+  // Validate the shortcut as it is edited so conflicts show up before saving
+  useEffect(() => {
+    if (!isOpen || !isTauriApp || activeTab !== "global-shortcut" || !shortcutKey) return;
+    let cancelled = false;
+    checkShortcut({ modifier: shortcutModifier, key: shortcutKey })
+      .then((check) => {
+        if (cancelled) return;
+        setShortcutCheck(check);
+        setShortcutError("");
+      })
+      .catch((error) => {
+        if (cancelled) return;
+        setShortcutCheck(null);
+        setShortcutError(describeBackendError(error, messages));
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [isOpen, isTauriApp, activeTab, shortcutKey, shortcutModifier, messages]);
+
+  const handleRecordShortcut = async () => {
+    setIsRecordingShortcut(true);
+    setShortcutError("");
+    try {
+      const check = await captureShortcut();
+      if (check) {
+        setShortcutModifier(check.modifier);
+        setShortcutKey(check.key);
+        setShortcutCheck(check);
+      }
+    } catch (error) {
+      setShortcutCheck(null);
+      setShortcutError(describeBackendError(error, messages));
+    } finally {
+      setIsRecordingShortcut(false);
+    }
+  };
+
   const handleSubmit = (e: FormEvent) => {
     e.preventDefault();
-    const untestedChange = (Object.keys(apiKeys) as Provider[]).find((provider) => apiKeys[provider].trim() && apiKeys[provider] !== currentApiKeys[provider] && keyValidation[provider] !== "valid");
+    const untestedChange = (Object.keys(apiKeys) as Provider[]).find((provider) => apiKeys[provider].trim() && !isUsableKey(keyValidation[provider]));
     if (untestedChange) { setSaveError(`Test the ${untestedChange} key before saving it.`); return; }
     // Save custom rules to localStorage
     if (typeof window !== "undefined") {
       localStorage.setItem("custom-rules", customRules);
     }
-    onSave(apiKeys, autostartEnabled, soundEnabled, shortcutKey, shortcutModifier, autoPasteEnabled);
+    const drafts = Object.fromEntries((Object.keys(apiKeys) as Provider[]).map((provider) => [provider, { key: apiKeys[provider], profile: profileNames[provider].trim() || DEFAULT_KEY_PROFILE }])) as Record<Provider, KeyDraft>;
+    onSave(drafts, autostartEnabled, soundEnabled, shortcutKey, shortcutModifier, autoPasteEnabled);
     onClose();
   };
 
@@ -181,33 +368,119 @@ export default function SettingsModal({
   const testKey = async (provider: Provider) => {
     if (!apiKeys[provider].trim()) return;
     setKeyValidation((previous) => ({ ...previous, [provider]: "testing" }));
-    const valid = await onTestApiKey(provider, apiKeys[provider]);
-    setKeyValidation((previous) => ({ ...previous, [provider]: valid ? "valid" : "invalid" }));
+    try {
+      const verification = await onTestApiKey(provider, apiKeys[provider]);
+      setKeyValidation((previous) => ({ ...previous, [provider]: verification }));
+    } catch (error) {
+      const details = describeBackendError(error, messages);
+      setKeyValidation((previous) => ({ ...previous, [provider]: { status: "network-error", account: null, details } }));
+    }
   };
 
   const handleClearApiKey = (provider: Provider) => {
     setApiKeys((prev) => ({ ...prev, [provider]: "" }));
   };
 
+  const editedProfile = (provider: Provider): KeyProfile | undefined =>
+    keyStatus[provider].profiles.find((profile) => profile.name.toLowerCase() === profileNames[provider].trim().toLowerCase());
+
+  const handleActivateProfile = async (provider: Provider, profile: string) => {
+    try {
+      await setActiveKeyProfile(provider, profile);
+      onCredentialsChanged();
+    } catch (error) {
+      alert(describeBackendError(error, messages));
+    }
+  };
+
   const handleRemoveStoredKey = async (provider: Provider) => {
     if (!isTauri()) return;
+    const profile = editedProfile(provider);
+    if (!profile) return;
 
     if (
       confirm(
-        `Are you sure you want to remove the stored ${provider} API key? You will need to re-enter it.`,
+        `Are you sure you want to remove the stored ${provider} API key "${profile.name}"? You will need to re-enter it.`,
       )
     ) {
       try {
-        await deleteKey(`${provider}-api-key`);
+        await deleteKey(provider, profile.name);
         setApiKeys((prev) => ({ ...prev, [provider]: "" }));
+        onCredentialsChanged();
         alert("API key removed successfully from secure storage.");
       } catch (error) {
         console.error("Failed to remove API key:", error);
-        alert("Failed to remove API key. Please try again.");
+        alert(describeBackendError(error, messages));
       }
     }
   };
 
+  const handleExportDiagnostics = async () => {
+    const [{ save }, { invoke }] = await Promise.all([
+      import("@tauri-apps/plugin-dialog"),
+      import("@tauri-apps/api/core"),
+    ]);
+    const path = await save({
+      defaultPath: `correctify-diagnostics-${new Date().toISOString().slice(0, 10)}.zip`,
+      filters: [{ name: "Zip", extensions: ["zip"] }],
+    });
+    if (!path) return;
+    // Preferences only; custom rules may contain private text, so only their length is shared.
+    const settings = {
+      ...Object.fromEntries(DIAGNOSTIC_SETTINGS.map((key) => [key, localStorage.getItem(key)])),
+      customRulesLength: (localStorage.getItem("custom-rules") || "").length,
+    };
+    try {
+      await invoke("export_diagnostics", { path, settings });
+      setDiagnosticsStatus(messages.apiModal.diagnosticsExported);
+    } catch (error) {
+      console.error("Failed to export diagnostics:", error);
+      setDiagnosticsStatus(describeBackendError(error, messages));
+    }
+  };
+
+  const handleExportBackup = async () => {
+    const { save } = await import("@tauri-apps/plugin-dialog");
+    const path = await save({
+      defaultPath: `correctify-${new Date().toISOString().slice(0, 10)}.${BACKUP_EXTENSION}`,
+      filters: [{ name: "Correctify backup", extensions: [BACKUP_EXTENSION] }],
+    });
+    if (!path) return;
+    try {
+      await exportBackup(path, backupPassphrase);
+      setBackupStatus(messages.apiModal.backupExported);
+    } catch (error) {
+      setBackupStatus(describeBackendError(error, messages));
+    }
+  };
+
+  const handleChooseBackup = async () => {
+    const { open: openFile } = await import("@tauri-apps/plugin-dialog");
+    const path = await openFile({ multiple: false, directory: false, filters: [{ name: "Correctify backup", extensions: [BACKUP_EXTENSION] }] });
+    if (typeof path !== "string") return;
+    try {
+      setPendingRestore({ path, preview: await previewBackup(path, backupPassphrase) });
+      setBackupStatus("");
+    } catch (error) {
+      setBackupStatus(describeBackendError(error, messages));
+    }
+  };
+
+  const handleRestoreBackup = async () => {
+    if (!pendingRestore) return;
+    try {
+      const restored = await restoreBackup(pendingRestore.path, backupPassphrase, pendingRestore.preview, restoreResolution);
+      setPendingRestore(null);
+      setBackupPassphrase("");
+      setBackupStatus(messages.apiModal.backupRestored.replace("{count}", restored.toString()));
+      onCredentialsChanged();
+      // Settings are read at startup; reload so the restored ones take effect
+      setTimeout(() => window.location.reload(), 1500);
+    } catch (error) {
+      setBackupStatus(describeBackendError(error, messages));
+    }
+  };
+
   const handleOpenAPIKey = async (url: string) => {
     try {
       await open(url);
@@ -297,6 +570,75 @@ export default function SettingsModal({
           {/* API Keys Tab */}
           {activeTab === "api-keys" && (
             <div className="space-y-4">
+              {policy?.error && <p className="text-sm text-error-text">{messages.policy.invalid}</p>}
+              {isTauriApp && credentialStatus && (
+                <div className="space-y-2 pb-4 border-b border-border">
+                  <p className="text-sm font-medium text-foreground">
+                    {messages.apiModal.credentialStorageLabel}:{" "}
+                    <span className="font-normal text-foreground/80">
+                      {credentialStatus.backend === "keyring"
+                        ? messages.apiModal.credentialBackendKeyring
+                        : messages.apiModal.credentialBackendFile}
+                    </span>
+                  </p>
+                  {credentialStatus.portable && (
+                    <p className="text-xs text-foreground/60">{messages.apiModal.credentialPortable}</p>
+                  )}
+                  {credentialStatus.fallbackReason && (
+                    <p className="text-xs text-foreground/60" title={credentialStatus.fallbackReason}>
+                      {messages.apiModal.credentialFallback}
+                    </p>
+                  )}
+                  {(credentialStatus.locked || credentialStatus.backend === "keyring") && (
+                    <input
+                      type="password"
+                      value={passphrase}
+                      onChange={(e) => setPassphrase(e.target.value)}
+                      placeholder={messages.apiModal.credentialPassphrase}
+                      aria-label={messages.apiModal.credentialPassphrase}
+                      className="w-full px-4 py-2.5 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground transition-colors text-sm placeholder:text-muted-foreground"
+                    />
+                  )}
+                  {credentialStatus.locked && (
+                    <p className="text-xs text-foreground/60">
+                      {credentialStatus.vaultExists ? messages.apiModal.credentialUnlockHint : messages.apiModal.credentialCreateHint}
+                    </p>
+                  )}
+                  <div className="flex items-center gap-3">
+                    {credentialStatus.locked && (
+                      <button
+                        type="button"
+                        onClick={handleUnlockCredentials}
+                        disabled={!passphrase}
+                        className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50"
+                      >
+                        {credentialStatus.vaultExists ? messages.apiModal.credentialUnlock : messages.apiModal.credentialCreate}
+                      </button>
+                    )}
+                    {credentialStatus.backend === "keyring" ? (
+                      <button
+                        type="button"
+                        onClick={handleMigrateCredentials}
+                        disabled={!passphrase}
+                        className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50"
+                      >
+                        {messages.apiModal.credentialMoveToFile}
+                      </button>
+                    ) : (
+                      credentialStatus.keyringAvailable && !credentialStatus.locked && (
+                        <button
+                          type="button"
+                          onClick={handleMigrateCredentials}
+                          className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10"
+                        >
+                          {messages.apiModal.credentialMoveToKeyring}
+                        </button>
+                      )
+                    )}
+                  </div>
+                  {credentialMessage && <p className="text-xs text-foreground/60" role="status">{credentialMessage}</p>}
+                </div>
+              )}
               {API_KEY_CONFIG.map((config) => (
                 <div
                   key={config.provider}
@@ -324,14 +666,34 @@ export default function SettingsModal({
                       </button>
                     )}
                   </div>
+                  {!isProviderAllowed(config.provider) && <p className="text-xs text-foreground/60">{messages.policy.providerBlocked}</p>}
                   <input
                     id={`apiKey-${config.provider}`}
                     type="password"
+                    disabled={!isProviderAllowed(config.provider)}
                     value={apiKeys[config.provider]}
                     onChange={(e) => handleApiKeyChange(config.provider, e.target.value)}
-                    placeholder={`Enter your ${config.label}`}
+                    placeholder={editedProfile(config.provider) ? `Stored: ${editedProfile(config.provider)?.preview}` : `Enter your ${config.label}`}
                     className="w-full px-4 py-2.5 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground transition-colors text-sm placeholder:text-muted-foreground"
                   />
+                  <div className="flex items-center gap-2">
+                    <label htmlFor={`keyProfile-${config.provider}`} className="text-xs text-foreground/70">{messages.apiModal.keyProfile}</label>
+                    <input
+                      id={`keyProfile-${config.provider}`}
+                      list={`keyProfiles-${config.provider}`}
+                      value={profileNames[config.provider]}
+                      onChange={(e) => setProfileNames((prev) => ({ ...prev, [config.provider]: e.target.value }))}
+                      className="min-w-0 flex-1 rounded border border-border bg-background px-2 py-1 text-xs text-foreground outline-none focus:ring-2 focus:ring-primary"
+                    />
+                    <datalist id={`keyProfiles-${config.provider}`}>
+                      {keyStatus[config.provider].profiles.map((profile) => <option key={profile.name} value={profile.name} />)}
+                    </datalist>
+                    {editedProfile(config.provider)?.name === keyStatus[config.provider].activeProfile ? (
+                      <span className="text-xs text-success-text">{messages.apiModal.keyProfileActive}</span>
+                    ) : editedProfile(config.provider) && (
+                      <button type="button" onClick={() => handleActivateProfile(config.provider, editedProfile(config.provider)!.name)} className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10">{messages.apiModal.keyProfileActivate}</button>
+                    )}
+                  </div>
                   <div className="flex items-center justify-between">
                     <button
                       type="button"
@@ -340,7 +702,7 @@ export default function SettingsModal({
                     >
                       {config.description} →
                     </button>
-                    {isTauriApp && apiKeys[config.provider] && (
+                    {isTauriApp && editedProfile(config.provider) && (
                       <button
                         type="button"
                         onClick={() => handleRemoveStoredKey(config.provider)}
@@ -351,9 +713,8 @@ export default function SettingsModal({
                     )}
                   </div>
                   <div className="flex items-center gap-2">
-                    <button type="button" onClick={() => testKey(config.provider)} disabled={!apiKeys[config.provider] || keyValidation[config.provider] === "testing"} className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">{keyValidation[config.provider] === "testing" ? "Testing…" : "Test key"}</button>
-                    {keyValidation[config.provider] === "valid" && <span className="text-xs text-success-text">Validated</span>}
-                    {keyValidation[config.provider] === "invalid" && <span className="text-xs text-error-text">Could not validate</span>}
+                    <button type="button" onClick={() => testKey(config.provider)} disabled={!apiKeys[config.provider] || !isProviderAllowed(config.provider) || keyValidation[config.provider] === "testing"} className="rounded px-2 py-1 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">{keyValidation[config.provider] === "testing" ? "Testing…" : "Test key"}</button>
+                    <KeyVerificationStatus validation={keyValidation[config.provider]} />
                   </div>
                 </div>
               ))}
@@ -469,7 +830,15 @@ export default function SettingsModal({
               {/* Autostart and Sound Notifications Side by Side (Desktop Only) */}
               {isTauriApp && (
                 <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
-                  {/* Autostart Option */}
+                  {/* Autostart Option; portable copies show where their data lives instead */}
+                  {appPaths?.portable ? (
+                    <div className="space-y-2">
+                      <p className="text-sm font-medium text-foreground">{messages.apiModal.portableLabel}</p>
+                      <p className="text-xs text-foreground/60 mt-1 break-all">
+                        {messages.apiModal.portableDescription.replace("{path}", appPaths.root ?? appPaths.dataDir)}
+                      </p>
+                    </div>
+                  ) : (
                   <div className="space-y-2">
                     <div className="flex items-start gap-3">
                       <input
@@ -492,6 +861,7 @@ export default function SettingsModal({
                       </div>
                     </div>
                   </div>
+                  )}
 
                   {/* Sound Notifications */}
                   <div className="space-y-2">
@@ -501,6 +871,7 @@ export default function SettingsModal({
                         type="checkbox"
                         checked={soundEnabled}
                         onChange={(e) => setSoundEnabled(e.target.checked)}
+                        disabled={policy?.locked.soundEnabled != null}
                         className="mt-1 w-4 h-4 accent-primary bg-background border-border rounded focus:ring-2 focus:ring-primary"
                       />
                       <div className="flex-1">
@@ -513,6 +884,7 @@ export default function SettingsModal({
                         <p className="text-xs text-foreground/60 mt-1">
                           {messages.apiModal.soundDescription}
                         </p>
+                        {policy?.locked.soundEnabled != null && <p className="text-xs text-foreground/60 mt-1">{messages.policy.managed}</p>}
                       </div>
                     </div>
                   </div>
@@ -529,6 +901,7 @@ export default function SettingsModal({
                       type="checkbox"
                       checked={autoPasteEnabled}
                       onChange={(e) => setAutoPasteEnabled(e.target.checked)}
+                      disabled={policy?.locked.autoPasteEnabled != null}
                       className="mt-1 w-4 h-4 accent-primary bg-background border-border rounded focus:ring-2 focus:ring-primary"
                     />
                     <div className="flex-1">
@@ -541,10 +914,121 @@ export default function SettingsModal({
                       <p className="text-xs text-foreground/60 mt-1">
                         {messages.apiModal.autoPasteDescription}
                       </p>
+                      {policy?.locked.autoPasteEnabled != null && <p className="text-xs text-foreground/60 mt-1">{messages.policy.managed}</p>}
+                    </div>
+                  </div>
+                </div>
+              )}
+
+              {/* Correction Cache (Desktop Only) */}
+              {isTauriApp && cacheStatus && (
+                <div className="space-y-2">
+                  <div className="flex items-start gap-3">
+                    <input
+                      id="correctionCache"
+                      type="checkbox"
+                      checked={cacheStatus.enabled}
+                      onChange={(e) => handleCacheChange(() => setCorrectionCacheEnabled(e.target.checked))}
+                      className="mt-1 w-4 h-4 accent-primary bg-background border-border rounded focus:ring-2 focus:ring-primary"
+                    />
+                    <div className="flex-1">
+                      <label
+                        htmlFor="correctionCache"
+                        className="block text-sm font-medium text-foreground cursor-pointer"
+                      >
+                        {messages.apiModal.cacheLabel}
+                      </label>
+                      <p className="text-xs text-foreground/60 mt-1">
+                        {messages.apiModal.cacheDescription}
+                      </p>
+                      {cacheStatus.enabled && (
+                        <div className="flex items-center gap-3 mt-1">
+                          <span className="text-xs text-foreground/60">
+                            {messages.apiModal.cacheEntries.replace("{count}", cacheStatus.entries.toString())}
+                          </span>
+                          <button
+                            type="button"
+                            onClick={() => handleCacheChange(clearCorrectionCache)}
+                            disabled={cacheStatus.entries === 0}
+                            className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50"
+                          >
+                            {messages.apiModal.cacheClear}
+                          </button>
+                        </div>
+                      )}
+                      {cacheError && <p className="text-xs text-error-text mt-1">{cacheError}</p>}
                     </div>
                   </div>
                 </div>
               )}
+
+              {/* Diagnostics Export (Desktop Only) */}
+              {isTauriApp && (
+                <div className="space-y-2">
+                  <p className="block text-sm font-medium text-foreground">{messages.apiModal.diagnosticsLabel}</p>
+                  <p className="text-xs text-foreground/60">{messages.apiModal.diagnosticsDescription}</p>
+                  <div className="flex items-center gap-3">
+                    <button
+                      type="button"
+                      onClick={handleExportDiagnostics}
+                      className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10"
+                    >
+                      {messages.apiModal.exportDiagnostics}
+                    </button>
+                    {diagnosticsStatus && <span className="text-xs text-foreground/60">{diagnosticsStatus}</span>}
+                  </div>
+                </div>
+              )}
+
+              {/* Encrypted Backup (Desktop Only) */}
+              {isTauriApp && (
+                <div className="space-y-2">
+                  <p className="block text-sm font-medium text-foreground">{messages.apiModal.backupLabel}</p>
+                  <p className="text-xs text-foreground/60">{messages.apiModal.backupDescription}</p>
+                  <input
+                    type="password"
+                    aria-label={messages.apiModal.backupPassphrase}
+                    placeholder={messages.apiModal.backupPassphrase}
+                    value={backupPassphrase}
+                    onChange={(e) => { setBackupPassphrase(e.target.value); setPendingRestore(null); }}
+                    className="w-full px-3 py-2 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground text-sm"
+                  />
+                  <div className="flex items-center gap-3">
+                    <button type="button" onClick={handleExportBackup} disabled={!backupPassphrase} className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">
+                      {messages.apiModal.backupExport}
+                    </button>
+                    <button type="button" onClick={handleChooseBackup} disabled={!backupPassphrase} className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10 disabled:opacity-50">
+                      {messages.apiModal.backupImport}
+                    </button>
+                  </div>
+                  {pendingRestore && (
+                    <div className="space-y-2 rounded-lg border border-border p-3 text-xs text-foreground/80">
+                      <p>{messages.apiModal.backupPreview.replace("{date}", new Date(pendingRestore.preview.createdAt).toLocaleString(locale)).replace("{version}", pendingRestore.preview.appVersion)}</p>
+                      <ul className="list-disc pl-4">
+                        {pendingRestore.preview.profiles.map((profile) => (
+                          <li key={`${profile.provider}-${profile.name}`}>
+                            {profile.provider} · {profile.name}{profile.conflict && <span className="text-warning-text"> — {messages.apiModal.backupConflict}</span>}
+                          </li>
+                        ))}
+                        <li>{messages.apiModal.backupSettings.replace("{count}", Object.keys(pendingRestore.preview.settings).length.toString()).replace("{conflicts}", settingConflicts(pendingRestore.preview).length.toString())}</li>
+                      </ul>
+                      <label className="flex items-center gap-2">
+                        {messages.apiModal.backupResolution}
+                        <select value={restoreResolution} onChange={(e) => setRestoreResolution(e.target.value as ConflictResolution)} className="rounded border border-border bg-background px-2 py-1 text-xs text-foreground">
+                          <option value="keep-existing">{messages.apiModal.backupKeepExisting}</option>
+                          <option value="use-backup">{messages.apiModal.backupUseBackup}</option>
+                          <option value="keep-both">{messages.apiModal.backupKeepBoth}</option>
+                        </select>
+                      </label>
+                      <div className="flex gap-2">
+                        <button type="button" onClick={handleRestoreBackup} className="rounded bg-primary px-3 py-1.5 text-xs font-medium text-button-text">{messages.apiModal.backupRestore}</button>
+                        <button type="button" onClick={() => setPendingRestore(null)} className="rounded px-3 py-1.5 text-xs font-medium text-foreground/70 hover:bg-foreground/5">{messages.apiModal.backupCancel}</button>
+                      </div>
+                    </div>
+                  )}
+                  {backupStatus && <p className="text-xs text-foreground/60">{backupStatus}</p>}
+                </div>
+              )}
             </div>
           )}
 
@@ -580,12 +1064,58 @@ export default function SettingsModal({
                   {messages.apiModal.rulesTab.info}
                 </p>
               </div>
+
+              {/* Personal Dictionary (Desktop Only) */}
+              {isTauriApp && (
+                <div className="space-y-2">
+                  <div className="flex items-center justify-between gap-3">
+                    <label
+                      htmlFor="dictionary"
+                      className="block text-sm font-medium text-foreground"
+                    >
+                      {messages.apiModal.rulesTab.dictionaryLabel}
+                    </label>
+                    <button
+                      type="button"
+                      onClick={handleImportWordList}
+                      className="rounded px-3 py-1.5 text-xs font-medium text-primary hover:bg-primary/10"
+                    >
+                      {messages.apiModal.rulesTab.dictionaryImport}
+                    </button>
+                  </div>
+                  <textarea
+                    id="dictionary"
+                    value={dictionaryText}
+                    onChange={(e) => setDictionaryText(e.target.value)}
+                    onBlur={() => handleDictionaryChange(() => setDictionary(parseDictionary(dictionaryText)))}
+                    placeholder={messages.apiModal.rulesTab.dictionaryPlaceholder}
+                    rows={6}
+                    className="w-full px-4 py-3 bg-background border border-border rounded-lg focus:outline-none focus:ring-2 focus:ring-primary text-foreground transition-colors text-sm placeholder:text-muted-foreground resize-none font-mono"
+                  />
+                  <p className="text-xs text-foreground/60">
+                    {messages.apiModal.rulesTab.dictionaryDescription}
+                  </p>
+                  {dictionaryError && <p className="text-xs text-error-text">{dictionaryError}</p>}
+                </div>
+              )}
             </div>
           )}
 
           {/* Global Shortcut Tab */}
           {activeTab === "global-shortcut" && isTauriApp && (
             <div className="space-y-6">
+              {shortcutProblem && (
+                <div className="space-y-1 rounded-lg border border-border bg-foreground/5 p-3" role="alert">
+                  <p className="text-sm text-error-text">
+                    {(shortcutProblem.status.registered ? messages.apiModal.shortcutFallback : messages.apiModal.shortcutNone)
+                      .replace("{requested}", formatShortcut(shortcutProblem.requested, isMac))
+                      .replace("{fallback}", formatShortcut(shortcutProblem.status, isMac))}
+                  </p>
+                  {shortcutProblem.error != null && (
+                    <p className="text-xs text-foreground/60">{describeBackendError(shortcutProblem.error, messages)}</p>
+                  )}
+                </div>
+              )}
               <div className="space-y-3">
                 <label
                   htmlFor="shortcutModifier"
@@ -744,26 +1274,36 @@ export default function SettingsModal({
                   <div className="space-y-2">
                     <p className="text-xs text-foreground/60">{messages.apiModal.shortcutPreview}</p>
                     <div className="px-3 py-2 bg-foreground/5 border border-border rounded-lg text-sm font-mono text-foreground flex items-center justify-center h-[42px]">
-                      {shortcutModifier === "CmdOrCtrl+Shift"
-                        ? (isMac
-                            ? `⌘ + ⇧ + ${shortcutKey}`
-                            : `Ctrl + Shift + ${shortcutKey}`)
-                        : shortcutModifier === "CmdOrCtrl+Alt"
-                        ? (isMac
-                            ? `⌘ + ⌥ + ${shortcutKey}`
-                            : `Ctrl + Alt + ${shortcutKey}`)
-                        : shortcutModifier === "AltOrOption+Shift"
-                        ? (isMac
-                            ? `⌥ + ⇧ + ${shortcutKey}`
-                            : `Alt + Shift + ${shortcutKey}`)
-                        : (isMac
-                            ? `⌘ + ⌥ + ⇧ + ${shortcutKey}`
-                            : `Ctrl + Alt + Shift + ${shortcutKey}`)
-                      }
+                      {formatShortcut({ modifier: shortcutModifier, key: shortcutKey }, isMac)}
                     </div>
                   </div>
                 </div>
 
+                {/* Record and availability */}
+                <div className="flex items-center gap-3">
+                  <button
+                    type="button"
+                    onClick={handleRecordShortcut}
+                    disabled={isRecordingShortcut}
+                    className="px-3 py-2 text-sm font-medium text-foreground bg-foreground/10 hover:bg-foreground/15 border border-border rounded-lg transition-colors disabled:opacity-60"
+                  >
+                    {isRecordingShortcut ? messages.apiModal.shortcutRecording : messages.apiModal.shortcutRecord}
+                  </button>
+                  <p
+                    className={`text-xs ${shortcutError || shortcutCheck?.availability === "taken" ? "text-red-500" : "text-foreground/60"}`}
+                    role="status"
+                  >
+                    {shortcutError ||
+                      (shortcutCheck?.availability === "taken"
+                        ? messages.apiModal.shortcutTaken
+                        : shortcutCheck?.availability === "available"
+                        ? messages.apiModal.shortcutAvailable
+                        : shortcutCheck?.availability === "current"
+                        ? messages.apiModal.shortcutCurrent
+                        : "")}
+                  </p>
+                </div>
+
                 <p className="text-xs text-foreground/60">
                   {messages.apiModal.shortcutDescription}
                 </p>
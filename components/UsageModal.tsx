@@ -95,6 +95,9 @@ export default function UsageModal({ isOpen, onClose }: UsageModalProps) {
                 <span>{messages.usageModal.totalRequests}</span>
               </div>
               <p className="text-2xl font-bold text-foreground">{stats.totalRequests}</p>
+              {stats.cachedRequests > 0 && (
+                <p className="text-xs text-foreground/60 mt-1">{messages.usageModal.fromCache.replace("{count}", stats.cachedRequests.toString())}</p>
+              )}
             </div>
 
             <div className="p-4 bg-foreground/5 rounded-lg">
@@ -186,7 +189,7 @@ export default function UsageModal({ isOpen, onClose }: UsageModalProps) {
           <div className="pt-4 border-t border-border">
             <div className="mb-4 space-y-2">
               <h3 className="text-sm font-semibold text-foreground">Recent activity</h3>
-              {getUsageHistory().slice(-5).reverse().map((entry) => <div key={entry.timestamp} className="flex items-center justify-between text-xs text-foreground/65"><span>{new Date(entry.timestamp).toLocaleString()} · {entry.model} · {entry.language ?? "unknown"}</span><span className={entry.success ? "text-success-text" : "text-error-text"}>{entry.success ? "Completed" : entry.error ?? "Failed"}</span></div>)}
+              {getUsageHistory().slice(-5).reverse().map((entry) => <div key={entry.timestamp} className="flex items-center justify-between text-xs text-foreground/65"><span>{new Date(entry.timestamp).toLocaleString()} · {entry.model} · {entry.language ?? "unknown"}</span><span className={entry.success ? "text-success-text" : "text-error-text"}>{entry.success ? (entry.cached ? "Cached" : "Completed") : entry.error ?? "Failed"}</span></div>)}
             </div>
             <button type="button" onClick={handleExport} className="mb-2 w-full flex items-center justify-center gap-2 px-4 py-2 text-sm font-medium text-foreground hover:bg-foreground/5 rounded-lg transition-colors"><Download className="w-4 h-4" />Export metadata CSV</button>
             <button
@@ -0,0 +1,28 @@
+import { invoke } from "@tauri-apps/api/core";
+import { isTauri } from "./utils";
+
+/**
+ * Where the backend keeps data. A copy with a `correctify.portable` file next
+ * to its executable keeps everything in a `CorrectifyData` directory beside it
+ * and does not register itself to start at login.
+ */
+export interface AppPaths {
+  portable: boolean;
+  /** The directory holding everything a portable copy writes. */
+  root: string | null;
+  dataDir: string;
+  logDir: string;
+}
+
+/**
+ * Ask the backend where data is kept. Null outside the desktop app.
+ */
+export async function loadAppPaths(): Promise<AppPaths | null> {
+  if (!isTauri()) return null;
+  try {
+    return await invoke<AppPaths>("get_app_paths");
+  } catch (error) {
+    console.error("Failed to load app paths:", error);
+    return null;
+  }
+}
@@ -0,0 +1,89 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Preset, Provider } from "./types";
+
+/** Interface settings carried by a backup, by localStorage key. Usage history stays on this machine. */
+const BACKUP_SETTINGS = [
+  "selected-model",
+  "writing-style",
+  "custom-rules",
+  "app-language",
+  "autostart-enabled",
+  "sound-enabled",
+  "shortcut-key",
+  "shortcut-modifier",
+  "auto-paste-enabled",
+  "correctify_presets_v1",
+  "correctify_bound_key_profiles_v1",
+];
+const PRESETS_KEY = "correctify_presets_v1";
+
+export const BACKUP_EXTENSION = "correctify-backup";
+
+export type ConflictResolution = "keep-existing" | "use-backup" | "keep-both";
+
+export interface ImportedProfile {
+  provider: Provider;
+  name: string;
+  /** A profile with this name already holds a different key. */
+  conflict: boolean;
+}
+
+export interface BackupPreview {
+  createdAt: number;
+  appVersion: string;
+  profiles: ImportedProfile[];
+  settings: Record<string, string>;
+}
+
+export async function exportBackup(path: string, passphrase: string): Promise<void> {
+  const settings = Object.fromEntries(BACKUP_SETTINGS.flatMap((key) => {
+    const value = localStorage.getItem(key);
+    return value === null ? [] : [[key, value]];
+  }));
+  await invoke("export_backup", { path, passphrase, settings });
+}
+
+export function previewBackup(path: string, passphrase: string): Promise<BackupPreview> {
+  return invoke<BackupPreview>("preview_backup", { path, passphrase });
+}
+
+/** Settings in the backup whose value differs from this machine's. */
+export function settingConflicts(preview: BackupPreview): string[] {
+  return Object.entries(preview.settings)
+    .filter(([key, value]) => BACKUP_SETTINGS.includes(key) && localStorage.getItem(key) !== null && localStorage.getItem(key) !== value)
+    .map(([key]) => key);
+}
+
+function mergePresets(current: Preset[], backup: Preset[], resolution: ConflictResolution): Preset[] {
+  const merged = [...current];
+  for (const preset of backup) {
+    const index = merged.findIndex((item) => item.id === preset.id);
+    if (index === -1) merged.push(preset);
+    else if (resolution === "use-backup") merged[index] = preset;
+    else if (resolution === "keep-both" && JSON.stringify(merged[index]) !== JSON.stringify(preset)) merged.push({ ...preset, id: crypto.randomUUID(), name: `${preset.name} (backup)` });
+  }
+  return merged;
+}
+
+/**
+ * Restores key profiles in the backend, then applies the previewed settings.
+ * Presets are merged; other conflicting settings follow the resolution, with
+ * "keep both" keeping this machine's value. Returns the number of key profiles restored.
+ */
+export async function restoreBackup(path: string, passphrase: string, preview: BackupPreview, resolution: ConflictResolution): Promise<number> {
+  const restored = await invoke<number>("restore_backup", { path, passphrase, resolution });
+  for (const [key, value] of Object.entries(preview.settings)) {
+    if (!BACKUP_SETTINGS.includes(key)) continue;
+    const current = localStorage.getItem(key);
+    if (key === PRESETS_KEY && current !== null) {
+      try {
+        localStorage.setItem(key, JSON.stringify(mergePresets(JSON.parse(current) as Preset[], JSON.parse(value) as Preset[], resolution)));
+      } catch (error) {
+        console.error("Failed to merge presets from backup:", error);
+      }
+    } else if (current === null || resolution === "use-backup") {
+      localStorage.setItem(key, value);
+    }
+  }
+  return restored;
+}
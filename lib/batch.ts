@@ -0,0 +1,68 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { BackendError } from "./errors";
+import type { DetectedLanguage, WritingStyle } from "./types";
+
+/** Where corrected files go: a `.corrected` sibling, or the file itself after a `.bak` copy. */
+export type BatchOutput = "sibling" | "in-place";
+
+export type BatchFileStatus = "started" | "corrected" | "unchanged" | "failed";
+
+export const BATCH_EXTENSIONS = ["md", "markdown", "txt"];
+
+export interface BatchFileReport {
+  path: string;
+  status: BatchFileStatus;
+  output: string | null;
+  backup: string | null;
+  edits: number;
+  usage: { inputTokens?: number | null; outputTokens?: number | null; totalTokens?: number | null } | null;
+  cached: boolean;
+  profile: string | null;
+  durationMs: number;
+  error: BackendError | null;
+}
+
+export interface BatchProgress {
+  completed: number;
+  total: number;
+  file: BatchFileReport;
+}
+
+export interface BatchSummary {
+  corrected: number;
+  unchanged: number;
+  failed: number;
+  durationMs: number;
+  files: BatchFileReport[];
+}
+
+export interface BatchRequest {
+  paths: string[];
+  output: BatchOutput;
+  model: string;
+  writingStyle: WritingStyle;
+  customRules?: string;
+  language?: DetectedLanguage;
+  profile?: string;
+}
+
+/** Asks for Markdown and text files, or for one folder. Returns no paths when cancelled. */
+export async function pickBatchPaths(folder: boolean): Promise<string[]> {
+  const { open } = await import("@tauri-apps/plugin-dialog");
+  const picked = folder
+    ? await open({ directory: true, multiple: false })
+    : await open({ directory: false, multiple: true, filters: [{ name: "Markdown and text", extensions: BATCH_EXTENSIONS }] });
+  if (!picked) return [];
+  return Array.isArray(picked) ? picked : [picked];
+}
+
+/** Corrects the files in the backend, calling `onProgress` as each file starts and finishes. */
+export async function correctFiles(request: BatchRequest, onProgress: (progress: BatchProgress) => void): Promise<BatchSummary> {
+  const { listen } = await import("@tauri-apps/api/event");
+  const unlisten = await listen<BatchProgress>("batch-progress", (event) => onProgress(event.payload));
+  try {
+    return await invoke<BatchSummary>("correct_files", { request });
+  } finally {
+    unlisten();
+  }
+}
@@ -0,0 +1,55 @@
+import { invoke } from "@tauri-apps/api/core";
+
+/** Write `preferred` wherever the text has `variant`. */
+export interface Spelling {
+  variant: string;
+  preferred: string;
+}
+
+/** The personal dictionary: terms a correction must never change, and preferred spellings. */
+export interface DictionaryTerms {
+  protected: string[];
+  spellings: Spelling[];
+}
+
+export function getDictionary(): Promise<DictionaryTerms> {
+  return invoke<DictionaryTerms>("get_dictionary");
+}
+
+/** Replaces the dictionary; the backend drops blank and repeated terms. */
+export function setDictionary(terms: DictionaryTerms): Promise<DictionaryTerms> {
+  return invoke<DictionaryTerms>("set_dictionary", { terms });
+}
+
+/** Adds the terms of a plain word list file to the dictionary. */
+export function importDictionary(path: string): Promise<DictionaryTerms> {
+  return invoke<DictionaryTerms>("import_dictionary", { path });
+}
+
+/** Asks for a word list. Returns null when cancelled. */
+export async function pickWordList(): Promise<string | null> {
+  const { open } = await import("@tauri-apps/plugin-dialog");
+  const picked = await open({ directory: false, multiple: false, filters: [{ name: "Word list", extensions: ["txt", "dic"] }] });
+  return typeof picked === "string" ? picked : null;
+}
+
+/** The dictionary as a word list: one term per line, `variant -> preferred` for a spelling. */
+export function dictionaryToText(terms: DictionaryTerms): string {
+  return [...terms.protected, ...terms.spellings.map(({ variant, preferred }) => `${variant} -> ${preferred}`)].join("\n");
+}
+
+/** Reads a word list the way the backend imports one; blank lines and `#` comments are skipped. */
+export function parseDictionary(text: string): DictionaryTerms {
+  const terms: DictionaryTerms = { protected: [], spellings: [] };
+  for (const line of text.split("\n").map((line) => line.trim())) {
+    if (!line || line.startsWith("#")) continue;
+    const arrow = line.includes("->") ? "->" : line.includes("→") ? "→" : null;
+    if (arrow) {
+      const [variant, ...rest] = line.split(arrow);
+      terms.spellings.push({ variant: variant.trim(), preferred: rest.join(arrow).trim() });
+    } else {
+      terms.protected.push(line);
+    }
+  }
+  return terms;
+}
@@ -0,0 +1,44 @@
+import type { Messages } from "./useLocale";
+
+export type BackendErrorCode =
+  | "keyring-unavailable"
+  | "not-found"
+  | "invalid-shortcut"
+  | "shortcut-unavailable"
+  | "shortcut-reserved"
+  | "clipboard-failed"
+  | "credentials-locked"
+  | "wrong-passphrase"
+  | "invalid-argument"
+  | "storage-failed"
+  | "missing-api-key"
+  | "provider-authentication"
+  | "provider-rejected"
+  | "provider-capacity"
+  | "provider-unavailable"
+  | "correction-failed"
+  | "blocked-by-policy"
+  | "cancelled";
+
+/** The serialized `AppError` every Tauri command rejects with. */
+export interface BackendError {
+  code: BackendErrorCode;
+  messageKey: string;
+  details: string;
+}
+
+export function isBackendError(error: unknown): error is BackendError {
+  return typeof error === "object" && error !== null && "code" in error && "messageKey" in error;
+}
+
+export function hasErrorCode(error: unknown, code: BackendErrorCode): boolean {
+  return isBackendError(error) && error.code === code;
+}
+
+/** Resolves a backend error to translated text, falling back to a generic message. */
+export function describeBackendError(error: unknown, messages: Messages): string {
+  if (!isBackendError(error)) return error instanceof Error ? error.message : messages.errors.unknown;
+  const [section, key] = error.messageKey.split(".");
+  const translated = section === "errors" ? messages.errors[key as keyof Messages["errors"]] : undefined;
+  return translated ?? messages.errors.unknown;
+}
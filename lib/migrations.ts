@@ -0,0 +1,48 @@
+import { invoke } from "@tauri-apps/api/core";
+import { type BackendError, hasErrorCode } from "./errors";
+import { refreshKeyStatus } from "./secure-keys";
+import { isTauri } from "./utils";
+
+const VERSION_KEY = "correctify_schema_version";
+
+interface MigrationReport {
+  version: number;
+  applied: string[];
+  /** localStorage writes to make; null removes the key. */
+  localChanges: Record<string, string | null>;
+  /** The step that was rolled back, retried on the next run. */
+  failed: { name: string; error: BackendError } | null;
+}
+
+function readLocalStorage(): Record<string, string> {
+  const entries: Record<string, string> = {};
+  for (let index = 0; index < localStorage.length; index++) {
+    const key = localStorage.key(index);
+    const value = key === null ? null : localStorage.getItem(key);
+    if (key !== null && value !== null) entries[key] = value;
+  }
+  return entries;
+}
+
+/**
+ * Bring stored keys, settings, presets and usage history up to the current schema
+ * The backend runs each step as a transaction and hands back the localStorage
+ * changes; a step that fails is rolled back and retried on the next run.
+ */
+export async function runMigrations(): Promise<void> {
+  if (!isTauri()) return;
+
+  const report = await invoke<MigrationReport>("run_migrations", { local: readLocalStorage() });
+  for (const [key, value] of Object.entries(report.localChanges)) {
+    if (value === null) localStorage.removeItem(key);
+    else localStorage.setItem(key, value);
+  }
+  // Written last, so changes interrupted halfway are replayed on the next start
+  localStorage.setItem(VERSION_KEY, String(report.version));
+  if (report.applied.length > 0) refreshKeyStatus();
+
+  // A locked credential file is retried once it has been unlocked
+  if (report.failed && !hasErrorCode(report.failed.error, "credentials-locked")) {
+    console.error(`Migration "${report.failed.name}" was rolled back:`, report.failed.error);
+  }
+}
@@ -1,3 +1,4 @@
+import { isModelAllowed } from "./policy";
 import type { Provider } from "./types";
 
 export interface ModelInfo {
@@ -47,5 +48,5 @@ export function getFallbackModel(): ModelInfo {
 }
 
 export function getAvailableModels(hasKeys: Record<Provider, boolean>): ModelInfo[] {
-  return MODELS.filter((model) => hasKeys[model.provider]);
+  return MODELS.filter((model) => hasKeys[model.provider] && isModelAllowed(model));
 }
@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/core";
+import type { Provider, WritingStyle } from "./types";
+import { isTauri } from "./utils";
+
+/**
+ * Administrator policy read by the backend from a system-wide file
+ * - Linux: /etc/correctify/policy.json
+ * - macOS: /Library/Application Support/Correctify/policy.json
+ * - Windows: %ProgramData%\Correctify\policy.json
+ *
+ * The backend enforces it; the interface only hides what is not allowed and
+ * disables locked settings.
+ */
+export interface Policy {
+  /** Providers keys may be stored for. null allows all. */
+  allowedProviders: Provider[] | null;
+  allowedModels: string[] | null;
+  /** Blocked model ids; a trailing `*` matches any suffix. */
+  blockedModels: string[];
+  endpoints: Partial<Record<Provider, string>>;
+  locked: { autoPasteEnabled: boolean | null; soundEnabled: boolean | null; writingStyle: WritingStyle | null };
+  defaults: { writingStyle: WritingStyle | null; model: string | null };
+  /** The policy file's path. */
+  source: string;
+  /** Why the policy file could not be used; every provider is blocked until it is fixed. */
+  error: string | null;
+}
+
+let current: Policy | null = null;
+
+/**
+ * Load the policy from the backend. Null when the install is not managed.
+ */
+export async function loadPolicy(): Promise<Policy | null> {
+  if (!isTauri()) return null;
+  try {
+    current = await invoke<Policy | null>("get_policy");
+  } catch (error) {
+    console.error("Failed to load the administrator policy:", error);
+  }
+  return current;
+}
+
+/** The policy from the last loadPolicy call. */
+export function getPolicy(): Policy | null {
+  return current;
+}
+
+export function isProviderAllowed(provider: Provider): boolean {
+  return !current?.allowedProviders || current.allowedProviders.includes(provider);
+}
+
+export function isModelAllowed(model: { id: string; provider: Provider }): boolean {
+  if (!isProviderAllowed(model.provider)) return false;
+  if (current?.allowedModels && !current.allowedModels.includes(model.id)) return false;
+  return !current?.blockedModels.some((pattern) => pattern.endsWith("*") ? model.id.startsWith(pattern.slice(0, -1)) : pattern === model.id);
+}
@@ -1,6 +1,7 @@
-import type { Preset } from "./types";
+import type { Preset, Provider } from "./types";
 
 const STORAGE_KEY = "correctify_presets_v1";
+const BOUND_PROFILES_KEY = "correctify_bound_key_profiles_v1";
 
 export function getPresets(): Preset[] {
   if (typeof window === "undefined") return [];
@@ -20,3 +21,15 @@ export function deletePreset(id: string): Preset[] {
   localStorage.setItem(STORAGE_KEY, JSON.stringify(presets));
   return presets;
 }
+
+/** Binds the selected preset's key profiles, or clears them for custom settings. */
+export function bindKeyProfiles(profiles?: Partial<Record<Provider, string>>): void {
+  if (profiles && Object.keys(profiles).length > 0) localStorage.setItem(BOUND_PROFILES_KEY, JSON.stringify(profiles));
+  else localStorage.removeItem(BOUND_PROFILES_KEY);
+}
+
+/** The profile the selected preset signs with for this provider; undefined means the active profile. */
+export function getBoundKeyProfile(provider: Provider): string | undefined {
+  if (typeof window === "undefined") return undefined;
+  try { return (JSON.parse(localStorage.getItem(BOUND_PROFILES_KEY) ?? "{}") as Partial<Record<Provider, string>>)[provider]; } catch { return undefined; }
+}
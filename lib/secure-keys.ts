@@ -1,4 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
+import { hasErrorCode, isBackendError } from "./errors";
 import type { Provider } from "./types";
 import { isTauri } from "./utils";
 
@@ -8,130 +9,151 @@ import { isTauri } from "./utils";
  * - Windows: DPAPI
  * - Linux: System keyring
  *
+ * When the OS vault is unavailable, the backend keeps keys in a
+ * passphrase-encrypted file instead; see getCredentialStatus.
+ *
+ * Keys are write-only from here: the backend signs provider requests itself
+ * and reports only whether a key is set.
+ *
  * Correctify is desktop-only. Browser builds never retain API credentials.
  */
 
-const KEY_PREFIX = "correctify_";
-const KEYRING_RECORD = `${KEY_PREFIX}api-keys-v2`;
-const EMPTY_KEYS: Record<Provider, string> = { openai: "", anthropic: "", mistral: "", openrouter: "" };
-let keysPromise: Promise<Record<Provider, string>> | null = null;
+export interface KeyProfile {
+  name: string;
+  /** The key with its middle elided. The key itself never leaves the backend. */
+  preview: string;
+}
 
-function providerFromKey(key: string): Provider | null {
-  const provider = key.replace(/-api-key$/, "");
-  return provider in EMPTY_KEYS ? provider as Provider : null;
+export interface ApiKeyStatus {
+  set: boolean;
+  /** Preview of the active profile's key. */
+  preview: string | null;
+  activeProfile: string | null;
+  profiles: KeyProfile[];
 }
 
-async function loadKeys(): Promise<Record<Provider, string>> {
-  if (!isTauri()) return { ...EMPTY_KEYS };
-  try {
-    const value = await invoke<string>("secure_storage_get", { key: KEYRING_RECORD });
-    return { ...EMPTY_KEYS, ...JSON.parse(value) };
-  } catch {
-    return { ...EMPTY_KEYS };
-  }
+/** A key typed into Settings and the profile it should be stored in. */
+export interface KeyDraft {
+  key: string;
+  profile: string;
 }
 
-export function getKeys(): Promise<Record<Provider, string>> {
-  keysPromise ??= loadKeys();
-  return keysPromise;
+export const DEFAULT_KEY_PROFILE = "Default";
+const UNSET: ApiKeyStatus = { set: false, preview: null, activeProfile: null, profiles: [] };
+export const EMPTY_KEY_STATUS: Record<Provider, ApiKeyStatus> = { openai: UNSET, anthropic: UNSET, mistral: UNSET, openrouter: UNSET };
+let statusPromise: Promise<Record<Provider, ApiKeyStatus>> | null = null;
+
+export type CredentialBackend = "keyring" | "encrypted-file";
+
+export interface CredentialStatus {
+  backend: CredentialBackend;
+  /** Why the OS vault was skipped, when the encrypted file was chosen automatically. */
+  fallbackReason: string | null;
+  locked: boolean;
+  vaultExists: boolean;
+  keyringAvailable: boolean;
+  /** Portable copies always keep keys in the encrypted file. */
+  portable: boolean;
 }
 
-async function saveKeys(keys: Record<Provider, string>): Promise<void> {
-  await invoke("secure_storage_set", { key: KEYRING_RECORD, value: JSON.stringify(keys) });
-  keysPromise = Promise.resolve(keys);
+async function loadKeyStatus(): Promise<Record<Provider, ApiKeyStatus>> {
+  if (!isTauri()) return { ...EMPTY_KEY_STATUS };
+  try {
+    return { ...EMPTY_KEY_STATUS, ...await invoke<Record<Provider, ApiKeyStatus>>("get_api_key_status") };
+  } catch (error) {
+    if (!hasErrorCode(error, "credentials-locked")) {
+      console.error("Failed to load API key status:", error);
+    }
+    return { ...EMPTY_KEY_STATUS };
+  }
 }
 
 /**
- * Get a key from secure storage (or localStorage in web mode)
+ * Which providers have a stored key, with a masked preview of each
  */
-export async function getKey(key: string): Promise<string | null> {
-  const provider = providerFromKey(key);
-  if (!provider) return null;
-  return (await getKeys())[provider] || null;
+export function getKeyStatus(): Promise<Record<Provider, ApiKeyStatus>> {
+  statusPromise ??= loadKeyStatus();
+  return statusPromise;
+}
+
+export function keyAvailability(status: Record<Provider, ApiKeyStatus>): Record<Provider, boolean> {
+  return { openai: status.openai.set, anthropic: status.anthropic.set, mistral: status.mistral.set, openrouter: status.openrouter.set };
 }
 
 /**
- * Set a key in secure storage (or localStorage in web mode)
+ * Store a provider key in secure storage, in the named profile or the active one
  */
-export async function setKey(key: string, value: string): Promise<void> {
+export async function setKey(provider: Provider, value: string, profile?: string): Promise<void> {
   if (!isTauri()) throw new Error("API keys are available only in the Correctify desktop app");
 
   try {
-    const provider = providerFromKey(key);
-    if (!provider) throw new Error("Unknown API key provider");
-    await saveKeys({ ...(await getKeys()), [provider]: value });
+    await invoke("set_api_key", { provider, key: value, profile: profile || null });
   } catch (error) {
-    console.error(`Failed to save ${key} to secure storage:`, error);
-    throw new Error(`Failed to save ${key} to secure storage`);
+    console.error(`Failed to save the ${provider} key to secure storage:`, error);
+    throw isBackendError(error) ? error : new Error(`Failed to save the ${provider} key to secure storage`);
+  } finally {
+    statusPromise = null;
   }
 }
 
 /**
- * Delete a key from secure storage (or localStorage in web mode)
+ * Delete a provider key profile from secure storage, the active one by default
  */
-export async function deleteKey(key: string): Promise<void> {
+export async function deleteKey(provider: Provider, profile?: string): Promise<void> {
   if (!isTauri()) return;
 
   try {
-    const provider = providerFromKey(key);
-    if (!provider) return;
-    await saveKeys({ ...(await getKeys()), [provider]: "" });
+    await invoke("remove_api_key", { provider, profile: profile || null });
   } catch (error) {
-    console.error(`Failed to delete key "${key}" from secure storage:`, error);
-    throw new Error(`Failed to remove ${key} from secure storage`);
+    console.error(`Failed to delete the ${provider} key from secure storage:`, error);
+    throw isBackendError(error) ? error : new Error(`Failed to remove the ${provider} key from secure storage`);
+  } finally {
+    statusPromise = null;
   }
 }
 
 /**
- * Check if a key exists in secure storage
+ * Choose which key profile signs requests for a provider
  */
-export async function hasKey(key: string): Promise<boolean> {
+export async function setActiveKeyProfile(provider: Provider, profile: string): Promise<void> {
   try {
-    const value = await getKey(key);
-    return value !== null && value.trim().length > 0;
-  } catch (error) {
-    console.error(`Failed to check if key "${key}" exists:`, error);
-    return false;
+    await invoke("set_active_key_profile", { provider, profile });
+  } finally {
+    statusPromise = null;
   }
 }
 
 /**
- * Migrate API keys from localStorage to secure storage
- * This is a one-time migration that runs on app startup
+ * Forget the cached key status, after keys changed outside this module
  */
-export async function migrateFromLocalStorage(): Promise<void> {
-  const MIGRATION_FLAG = "MIGRATION_COMPLETE_V1_1_0";
+export function refreshKeyStatus(): void {
+  statusPromise = null;
+}
 
-  if (!isTauri()) return;
-  await invoke("migrate_legacy_key_files");
-  await invoke("migrate_legacy_keyring_entries", { key: KEYRING_RECORD });
-  keysPromise = null;
+/**
+ * Check if a provider has a stored key
+ */
+export async function hasKey(provider: Provider): Promise<boolean> {
+  return (await getKeyStatus())[provider].set;
+}
 
-  // Check if migration has already been completed
-  if (localStorage.getItem(MIGRATION_FLAG) === "true") {
-    return;
-  }
+export function getCredentialStatus(): Promise<CredentialStatus> {
+  return invoke<CredentialStatus>("get_credential_status");
+}
 
-  const keysToMigrate = [
-    "openai-api-key",
-    "anthropic-api-key",
-    "openrouter-api-key",
-    "mistral-api-key",
-  ];
-
-  await Promise.all(keysToMigrate.map(async (key) => {
-    try {
-      const value = localStorage.getItem(key);
-      if (value && value.trim().length > 0) {
-        await setKey(key, value);
-        localStorage.removeItem(key);
-      }
-    } catch (error) {
-      console.error(`Failed to migrate ${key}:`, error);
-      // Continue with other keys even if one fails
-    }
-  }));
+/**
+ * Unlock the encrypted credential file, or create it with this passphrase
+ */
+export async function unlockCredentials(passphrase: string): Promise<void> {
+  await invoke("unlock_credential_store", { passphrase });
+  statusPromise = null;
+}
 
-  // Mark migration as complete
-  localStorage.setItem(MIGRATION_FLAG, "true");
+/**
+ * Move stored keys to another backend and keep using it. Returns the number of records moved.
+ */
+export async function migrateCredentials(target: CredentialBackend, passphrase?: string): Promise<number> {
+  const migrated = await invoke<number>("migrate_credentials", { target, passphrase: passphrase || null });
+  statusPromise = null;
+  return migrated;
 }
@@ -0,0 +1,86 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type ShortcutAvailability = "current" | "available" | "taken";
+
+export interface ShortcutCombination {
+  modifier: string;
+  key: string;
+}
+
+export interface ShortcutCheck extends ShortcutCombination {
+  availability: ShortcutAvailability;
+}
+
+export interface ShortcutStatus extends ShortcutCombination {
+  /** False when no combination could be registered, so the shortcut does nothing. */
+  registered: boolean;
+}
+
+/** Why the saved shortcut is not the live one, shown on the shortcut tab. */
+export interface ShortcutProblem {
+  /** The combination that could not be registered. */
+  requested: ShortcutCombination;
+  status: ShortcutStatus;
+  error: unknown;
+}
+
+const MODIFIER_CODES = new Set([
+  "ShiftLeft", "ShiftRight", "ControlLeft", "ControlRight",
+  "AltLeft", "AltRight", "MetaLeft", "MetaRight", "CapsLock", "Fn",
+]);
+
+const MODIFIER_LABELS: Record<string, { mac: string; other: string }> = {
+  "CmdOrCtrl+Shift": { mac: "⌘ + ⇧", other: "Ctrl + Shift" },
+  "CmdOrCtrl+Alt": { mac: "⌘ + ⌥", other: "Ctrl + Alt" },
+  "AltOrOption+Shift": { mac: "⌥ + ⇧", other: "Alt + Shift" },
+  "CmdOrCtrl+Alt+Shift": { mac: "⌘ + ⌥ + ⇧", other: "Ctrl + Alt + Shift" },
+};
+
+/** A combination as the platform writes it, such as `⌘ + ⇧ + ]` or `Ctrl + Shift + ]`. */
+export function formatShortcut(combination: ShortcutCombination, isMac: boolean): string {
+  const labels = MODIFIER_LABELS[combination.modifier] ?? MODIFIER_LABELS["CmdOrCtrl+Alt+Shift"];
+  return `${isMac ? labels.mac : labels.other} + ${combination.key}`;
+}
+
+/** Validates a combination and reports whether it is free, without saving it. */
+export function checkShortcut(combination: ShortcutCombination): Promise<ShortcutCheck> {
+  return invoke<ShortcutCheck>("check_shortcut", { ...combination });
+}
+
+export function getDefaultShortcut(): Promise<ShortcutCombination> {
+  return invoke<ShortcutCombination>("get_default_shortcut");
+}
+
+/** The live shortcut; startup falls back to a free combination when the saved one is taken. */
+export function getShortcutStatus(): Promise<ShortcutStatus> {
+  return invoke<ShortcutStatus>("get_shortcut_status");
+}
+
+/**
+ * Records the next key combination pressed in this window. Resolves to null
+ * when the user presses Escape on its own; rejects with a backend error when
+ * the combination is invalid or reserved.
+ */
+export async function captureShortcut(): Promise<ShortcutCheck | null> {
+  await invoke("begin_shortcut_capture");
+  const event = await new Promise<KeyboardEvent | null>((resolve) => {
+    const onKeyDown = (keyEvent: KeyboardEvent) => {
+      if (MODIFIER_CODES.has(keyEvent.code)) return;
+      keyEvent.preventDefault();
+      keyEvent.stopPropagation();
+      window.removeEventListener("keydown", onKeyDown, true);
+      const bareEscape = keyEvent.code === "Escape" && !keyEvent.ctrlKey && !keyEvent.altKey && !keyEvent.shiftKey && !keyEvent.metaKey;
+      resolve(bareEscape ? null : keyEvent);
+    };
+    window.addEventListener("keydown", onKeyDown, true);
+  });
+  return invoke<ShortcutCheck | null>("finish_shortcut_capture", {
+    event: event && {
+      code: event.code,
+      ctrlKey: event.ctrlKey,
+      altKey: event.altKey,
+      shiftKey: event.shiftKey,
+      metaKey: event.metaKey,
+    },
+  });
+}
@@ -26,13 +26,23 @@ export interface Preset {
   writingStyle: WritingStyle;
   customRules: string;
   language: LanguagePreference;
+  /** Key profile to sign with for each provider while this preset is selected. */
+  keyProfiles?: Partial<Record<Provider, string>>;
   createdAt: number;
   updatedAt: number;
 }
 
-export interface CorrectionInput { text: string; model?: string; temperature?: number; writingStyle?: WritingStyle; customRules?: string; language?: DetectedLanguage; signal?: AbortSignal; }
+export interface CorrectionInput {
+  text: string; model?: string; temperature?: number; writingStyle?: WritingStyle; customRules?: string; language?: DetectedLanguage; profile?: string; signal?: AbortSignal;
+  /** Streams the response, called with the corrected text so far. Aborting `signal` then stops the provider request. */
+  onPartial?: (text: string) => void;
+}
 export interface CorrectionUsage { inputTokens?: number; outputTokens?: number; totalTokens?: number; }
-export interface CorrectionResult { result: string; usage: CorrectionUsage; finishReason?: string; requestId?: string; }
+export interface CorrectionResult {
+  result: string; usage: CorrectionUsage; finishReason?: string; requestId?: string; profile?: string;
+  /** Answered from the backend's correction cache without a provider request. */
+  cached?: boolean;
+}
 export interface Corrector { correct(input: CorrectionInput): Promise<CorrectionResult>; }
 
 export class CorrectionError extends Error {
@@ -5,6 +5,8 @@ import type { DetectedLanguage, WritingStyle } from "./types";
 export interface UsageEntry {
   timestamp: number;
   provider: Provider;
+  /** The key profile that paid for the request. */
+  profile?: string;
   model: string;
   inputTokens?: number;
   outputTokens?: number;
@@ -15,12 +17,15 @@ export interface UsageEntry {
   language?: DetectedLanguage;
   detectedEdits?: number;
   acceptedEdits?: number;
+  /** Answered from the correction cache, which cost no tokens. */
+  cached?: boolean;
 }
 
 export interface UsageStats {
   totalRequests: number;
   successfulRequests: number;
   failedRequests: number;
+  cachedRequests: number;
   totalDuration: number; // milliseconds
   totalTokens: number;
   estimatedCost: number; // in USD
@@ -56,8 +61,8 @@ export function getUsageHistory(): UsageEntry[] {
 }
 
 export function exportUsageHistory(): string {
-  const headers = ["timestamp", "provider", "model", "style", "language", "duration_ms", "input_tokens", "output_tokens", "estimated_edits", "accepted_edits", "success", "error"];
-  const rows = getUsageHistory().map((entry) => [new Date(entry.timestamp).toISOString(), entry.provider, entry.model, entry.writingStyle ?? "grammar", entry.language ?? "unknown", entry.duration, entry.inputTokens ?? 0, entry.outputTokens ?? 0, entry.detectedEdits ?? 0, entry.acceptedEdits ?? 0, entry.success, entry.error ?? ""].map((value) => `"${String(value).replaceAll('"', '""')}"`).join(","));
+  const headers = ["timestamp", "provider", "profile", "model", "style", "language", "duration_ms", "input_tokens", "output_tokens", "estimated_edits", "accepted_edits", "success", "cached", "error"];
+  const rows = getUsageHistory().map((entry) => [new Date(entry.timestamp).toISOString(), entry.provider, entry.profile ?? "", entry.model, entry.writingStyle ?? "grammar", entry.language ?? "unknown", entry.duration, entry.inputTokens ?? 0, entry.outputTokens ?? 0, entry.detectedEdits ?? 0, entry.acceptedEdits ?? 0, entry.success, entry.cached ?? false, entry.error ?? ""].map((value) => `"${String(value).replaceAll('"', '""')}"`).join(","));
   return [headers.join(","), ...rows].join("\n");
 }
 
@@ -103,6 +108,7 @@ export function calculateUsageStats(entries: UsageEntry[] = getUsageHistory()):
     totalRequests: entries.length,
     successfulRequests: entries.filter((e) => e.success).length,
     failedRequests: entries.filter((e) => !e.success).length,
+    cachedRequests: entries.filter((e) => e.cached).length,
     totalDuration: 0,
     totalTokens: 0,
     estimatedCost: 0,
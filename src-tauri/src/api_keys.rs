@@ -0,0 +1,494 @@
+//! Credential broker for provider API keys.
+//!
+//! Keys live in one record of the active credential store and are read only
+//! by the backend when it signs a provider request. The webview learns
+//! whether a key is set and sees a masked preview, never the key itself.
+//!
+//! Each provider can hold several named profiles (say "Work" and
+//! "Personal"); one of them is active and signs requests unless a request
+//! names another.
+
+use crate::credentials::Credentials;
+use crate::error::AppError;
+use crate::llm::Provider;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The consolidated record holding every provider key profile.
+pub const API_KEYS_RECORD: &str = "correctify_api-keys-v2";
+/// The profile that keys saved before profiles existed are imported into.
+pub const DEFAULT_PROFILE: &str = "Default";
+const PREVIEW_EDGE: usize = 4;
+const MAX_PROFILE_NAME_CHARACTERS: usize = 40;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Profile {
+    name: String,
+    provider: Provider,
+    key: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeyRecord {
+    profiles: Vec<Profile>,
+    /// The active profile name for each provider with at least one profile.
+    active: BTreeMap<Provider, String>,
+}
+
+impl KeyRecord {
+    fn find(&self, provider: Provider, name: &str) -> Option<&Profile> {
+        self.profiles
+            .iter()
+            .find(|profile| profile.provider == provider && profile.name.eq_ignore_ascii_case(name))
+    }
+
+    // Keeps every provider with profiles pointing at one that exists
+    fn repair_active(&mut self) {
+        for provider in Provider::ALL {
+            let current = self.active.get(&provider).cloned();
+            let valid = current.as_deref().is_some_and(|name| self.find(provider, name).is_some());
+            if valid {
+                continue;
+            }
+            match self.profiles.iter().find(|profile| profile.provider == provider) {
+                Some(profile) => {
+                    self.active.insert(provider, profile.name.clone());
+                }
+                None => {
+                    self.active.remove(&provider);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileSummary {
+    pub name: String,
+    /// The first and last characters with the middle elided, such as `sk-p…x9Qa`.
+    pub preview: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyStatus {
+    pub set: bool,
+    /// The preview of the active profile's key.
+    pub preview: Option<String>,
+    pub active_profile: Option<String>,
+    pub profiles: Vec<ProfileSummary>,
+}
+
+/// A key picked for one request, and the profile it came from.
+pub struct SigningKey {
+    pub key: String,
+    pub profile: String,
+}
+
+/// A key profile found in a backup, compared with what is stored now.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedProfile {
+    pub provider: Provider,
+    pub name: String,
+    /// A profile with this name already holds a different key.
+    pub conflict: bool,
+}
+
+/// What to do with a backed-up profile whose name is already taken by a different key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictResolution {
+    KeepExisting,
+    UseBackup,
+    /// Imports the backed-up key under a new name such as "Work (backup)".
+    KeepBoth,
+}
+
+fn load(credentials: &Credentials) -> Result<KeyRecord, AppError> {
+    match credentials.get(API_KEYS_RECORD) {
+        Ok(value) => parse(&value),
+        Err(AppError::NotFound(_)) => Ok(KeyRecord::default()),
+        Err(e) => Err(e),
+    }
+}
+
+fn parse(value: &str) -> Result<KeyRecord, AppError> {
+    let damaged = |e: serde_json::Error| AppError::StorageFailed(format!("API key record is damaged: {}", e));
+    let mut record = match serde_json::from_str::<KeyRecord>(value) {
+        Ok(record) => record,
+        Err(_) => {
+            // Records written before profiles are a flat {provider: key} map; tolerate
+            // unknown providers and empty values from older builds
+            let raw: BTreeMap<String, String> = serde_json::from_str(value).map_err(damaged)?;
+            let profiles = Provider::ALL
+                .into_iter()
+                .filter_map(|provider| {
+                    let key = raw.get(provider.id())?.trim();
+                    (!key.is_empty()).then(|| Profile {
+                        name: DEFAULT_PROFILE.to_string(),
+                        provider,
+                        key: key.to_string(),
+                    })
+                })
+                .collect();
+            KeyRecord { profiles, active: BTreeMap::new() }
+        }
+    };
+    record.repair_active();
+    Ok(record)
+}
+
+fn encode(record: &KeyRecord) -> Result<String, AppError> {
+    serde_json::to_string(record).map_err(|e| AppError::StorageFailed(format!("Failed to encode API keys: {}", e)))
+}
+
+fn save(credentials: &Credentials, record: &KeyRecord) -> Result<(), AppError> {
+    credentials.set(API_KEYS_RECORD, &encode(record)?)
+}
+
+fn profile_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidArgument("The profile name is empty".to_string()));
+    }
+    if name.chars().count() > MAX_PROFILE_NAME_CHARACTERS || name.chars().any(char::is_control) {
+        return Err(AppError::InvalidArgument(format!(
+            "Profile names are limited to {} printable characters",
+            MAX_PROFILE_NAME_CHARACTERS
+        )));
+    }
+    Ok(name.to_string())
+}
+
+/// The key for `provider`, for signing a request inside the backend only.
+/// Uses the named profile when given, otherwise the active one.
+pub fn get(credentials: &Credentials, provider: Provider, profile: Option<&str>) -> Result<SigningKey, AppError> {
+    let record = load(credentials)?;
+    let name = match profile {
+        Some(name) => name,
+        None => record.active.get(&provider).map(String::as_str).unwrap_or(DEFAULT_PROFILE),
+    };
+    match record.find(provider, name) {
+        Some(found) => Ok(SigningKey { key: found.key.clone(), profile: found.name.clone() }),
+        None if profile.is_some() => Err(AppError::MissingApiKey(format!(
+            "No {} API key is stored in the profile '{}'",
+            provider.label(),
+            name
+        ))),
+        None => Err(AppError::MissingApiKey(format!("No {} API key is configured", provider.label()))),
+    }
+}
+
+/// Stores `key` in the named profile, or the active one, creating it when
+/// needed. The first profile of a provider becomes its active profile.
+pub fn set(credentials: &Credentials, provider: Provider, profile: Option<&str>, key: &str) -> Result<(), AppError> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(AppError::InvalidArgument("The API key is empty".to_string()));
+    }
+    let mut record = load(credentials)?;
+    let name = match profile {
+        Some(name) => profile_name(name)?,
+        None => record.active.get(&provider).cloned().unwrap_or_else(|| DEFAULT_PROFILE.to_string()),
+    };
+    match record
+        .profiles
+        .iter_mut()
+        .find(|existing| existing.provider == provider && existing.name.eq_ignore_ascii_case(&name))
+    {
+        Some(existing) => existing.key = key.to_string(),
+        None => record.profiles.push(Profile { name, provider, key: key.to_string() }),
+    }
+    record.repair_active();
+    save(credentials, &record)
+}
+
+/// Forgets the named profile, or the active one. Another profile of the same
+/// provider takes over as active.
+pub fn remove(credentials: &Credentials, provider: Provider, profile: Option<&str>) -> Result<(), AppError> {
+    let mut record = load(credentials)?;
+    let Some(name) = profile.map(str::to_string).or_else(|| record.active.get(&provider).cloned()) else {
+        return Ok(());
+    };
+    let before = record.profiles.len();
+    record
+        .profiles
+        .retain(|existing| !(existing.provider == provider && existing.name.eq_ignore_ascii_case(&name)));
+    if record.profiles.len() != before {
+        record.repair_active();
+        save(credentials, &record)?;
+    }
+    Ok(())
+}
+
+/// Makes `profile` the one that signs requests for `provider`.
+pub fn set_active(credentials: &Credentials, provider: Provider, profile: &str) -> Result<(), AppError> {
+    let mut record = load(credentials)?;
+    let name = record
+        .find(provider, profile)
+        .map(|found| found.name.clone())
+        .ok_or_else(|| AppError::NotFound(format!("No {} key profile named '{}'", provider.label(), profile)))?;
+    record.active.insert(provider, name);
+    save(credentials, &record)
+}
+
+pub fn status(credentials: &Credentials) -> Result<BTreeMap<Provider, ApiKeyStatus>, AppError> {
+    let record = load(credentials)?;
+    Ok(Provider::ALL
+        .into_iter()
+        .map(|provider| {
+            let active_profile = record.active.get(&provider).cloned();
+            let preview = active_profile
+                .as_deref()
+                .and_then(|name| record.find(provider, name))
+                .map(|profile| mask(&profile.key));
+            let profiles = record
+                .profiles
+                .iter()
+                .filter(|profile| profile.provider == provider)
+                .map(|profile| ProfileSummary { name: profile.name.clone(), preview: mask(&profile.key) })
+                .collect();
+            (provider, ApiKeyStatus { set: preview.is_some(), preview, active_profile, profiles })
+        })
+        .collect())
+}
+
+/// The stored record for a backup, or None when no key is stored.
+pub fn export(credentials: &Credentials) -> Result<Option<String>, AppError> {
+    let record = load(credentials)?;
+    if record.profiles.is_empty() {
+        return Ok(None);
+    }
+    encode(&record).map(Some)
+}
+
+/// Re-encodes a record written before profiles in the current layout.
+/// Returns None when the record is already current.
+pub fn upgrade(value: &str) -> Result<Option<String>, AppError> {
+    if serde_json::from_str::<KeyRecord>(value).is_ok() {
+        return Ok(None);
+    }
+    encode(&parse(value)?).map(Some)
+}
+
+/// Adds keys kept by older releases as "Default" profiles of providers that
+/// have no key yet. Returns the record to store, or None when nothing was added.
+pub fn adopt_legacy(value: Option<&str>, keys: &BTreeMap<Provider, String>) -> Result<Option<String>, AppError> {
+    let mut record = match value {
+        Some(value) => parse(value)?,
+        None => KeyRecord::default(),
+    };
+    let mut adopted = false;
+    for (&provider, key) in keys {
+        let key = key.trim();
+        if key.is_empty() || record.profiles.iter().any(|profile| profile.provider == provider) {
+            continue;
+        }
+        record.profiles.push(Profile { name: DEFAULT_PROFILE.to_string(), provider, key: key.to_string() });
+        adopted = true;
+    }
+    if !adopted {
+        return Ok(None);
+    }
+    record.repair_active();
+    encode(&record).map(Some)
+}
+
+/// Lists the profiles in a backed-up record without revealing their keys.
+pub fn preview_import(credentials: &Credentials, value: &str) -> Result<Vec<ImportedProfile>, AppError> {
+    let current = load(credentials)?;
+    Ok(parse_backup(value)?
+        .profiles
+        .into_iter()
+        .map(|profile| {
+            let conflict = current
+                .find(profile.provider, &profile.name)
+                .is_some_and(|existing| existing.key != profile.key);
+            ImportedProfile { provider: profile.provider, name: profile.name, conflict }
+        })
+        .collect())
+}
+
+// A backup comes from outside the store, so its names get the checks typed names get
+fn parse_backup(value: &str) -> Result<KeyRecord, AppError> {
+    let mut record = parse(value)?;
+    for profile in &mut record.profiles {
+        profile.name = profile_name(&profile.name).map_err(|e| {
+            AppError::InvalidArgument(format!("The backup holds an unusable profile name: {}", e.details()))
+        })?;
+    }
+    Ok(record)
+}
+
+/// Merges a backed-up record into the store, keeping only providers `allowed`
+/// accepts. Returns how many profiles were added or replaced; identical
+/// profiles are skipped.
+pub fn import(
+    credentials: &Credentials,
+    value: &str,
+    resolution: ConflictResolution,
+    allowed: impl Fn(Provider) -> bool,
+) -> Result<u32, AppError> {
+    let mut record = load(credentials)?;
+    let mut imported = 0;
+    for profile in parse_backup(value)?.profiles.into_iter().filter(|profile| allowed(profile.provider)) {
+        let existing = record
+            .profiles
+            .iter()
+            .position(|existing| existing.provider == profile.provider && existing.name.eq_ignore_ascii_case(&profile.name));
+        match existing {
+            None => record.profiles.push(profile),
+            Some(index) if record.profiles[index].key == profile.key => continue,
+            Some(_) if resolution == ConflictResolution::KeepExisting => continue,
+            Some(index) if resolution == ConflictResolution::UseBackup => record.profiles[index].key = profile.key,
+            Some(_) => {
+                let name = (1..)
+                    .map(|attempt| backup_name(&profile.name, attempt))
+                    .find(|name| record.find(profile.provider, name).is_none())
+                    .unwrap_or_default();
+                record.profiles.push(Profile { name, ..profile });
+            }
+        }
+        imported += 1;
+    }
+    if imported > 0 {
+        record.repair_active();
+        save(credentials, &record)?;
+    }
+    Ok(imported)
+}
+
+// "Work (backup)", "Work (backup 2)", ...; a long name is shortened so the
+// suffix still fits the profile name limit
+fn backup_name(name: &str, attempt: u32) -> String {
+    let suffix = match attempt {
+        1 => " (backup)".to_string(),
+        _ => format!(" (backup {})", attempt),
+    };
+    let base: String = name.chars().take(MAX_PROFILE_NAME_CHARACTERS - suffix.chars().count()).collect();
+    format!("{}{}", base.trim_end(), suffix)
+}
+
+fn mask(key: &str) -> String {
+    let characters: Vec<char> = key.chars().collect();
+    // Short keys would be mostly revealed by their edges
+    if characters.len() < PREVIEW_EDGE * 4 {
+        return "…".to_string();
+    }
+    let start: String = characters[..PREVIEW_EDGE].iter().collect();
+    let end: String = characters[characters.len() - PREVIEW_EDGE..].iter().collect();
+    format!("{}…{}", start, end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn active(credentials: &Credentials, provider: Provider) -> String {
+        get(credentials, provider, None).unwrap().profile
+    }
+
+    #[test]
+    fn the_first_profile_is_active_until_another_is_chosen() {
+        let (_data, credentials) = testing::credentials();
+        set(&credentials, Provider::OpenAI, Some("Work"), "sk-work-key-0001").unwrap();
+        set(&credentials, Provider::OpenAI, Some("Personal"), "sk-personal-key-0002").unwrap();
+        assert_eq!(active(&credentials, Provider::OpenAI), "Work");
+
+        set_active(&credentials, Provider::OpenAI, "personal").unwrap();
+        let signing = get(&credentials, Provider::OpenAI, None).unwrap();
+        assert_eq!((signing.profile.as_str(), signing.key.as_str()), ("Personal", "sk-personal-key-0002"));
+        assert!(matches!(set_active(&credentials, Provider::OpenAI, "Missing"), Err(AppError::NotFound(_))));
+
+        // Saving without a profile name updates the active one
+        set(&credentials, Provider::OpenAI, None, " sk-personal-key-0003 ").unwrap();
+        assert_eq!(get(&credentials, Provider::OpenAI, Some("Personal")).unwrap().key, "sk-personal-key-0003");
+        let status = &status(&credentials).unwrap()[&Provider::OpenAI];
+        assert_eq!(status.active_profile.as_deref(), Some("Personal"));
+        assert_eq!(status.preview.as_deref(), Some("sk-p…0003"));
+    }
+
+    #[test]
+    fn removing_the_active_profile_hands_over_to_another() {
+        let (_data, credentials) = testing::credentials();
+        set(&credentials, Provider::Mistral, Some("Work"), "mistral-work").unwrap();
+        set(&credentials, Provider::Mistral, Some("Personal"), "mistral-personal").unwrap();
+
+        remove(&credentials, Provider::Mistral, None).unwrap();
+        assert_eq!(active(&credentials, Provider::Mistral), "Personal");
+
+        remove(&credentials, Provider::Mistral, Some("PERSONAL")).unwrap();
+        assert!(matches!(get(&credentials, Provider::Mistral, None), Err(AppError::MissingApiKey(_))));
+        assert!(!status(&credentials).unwrap()[&Provider::Mistral].set);
+        // Nothing left to remove is not an error
+        remove(&credentials, Provider::Mistral, None).unwrap();
+    }
+
+    #[test]
+    fn a_request_can_name_its_profile() {
+        let (_data, credentials) = testing::credentials();
+        set(&credentials, Provider::Anthropic, Some("Work"), "sk-ant-work").unwrap();
+        set(&credentials, Provider::Anthropic, Some("Personal"), "sk-ant-personal").unwrap();
+
+        let signing = get(&credentials, Provider::Anthropic, Some("personal")).unwrap();
+        assert_eq!((signing.profile.as_str(), signing.key.as_str()), ("Personal", "sk-ant-personal"));
+        assert_eq!(active(&credentials, Provider::Anthropic), "Work");
+
+        let missing = get(&credentials, Provider::Anthropic, Some("Team")).err().unwrap();
+        assert!(matches!(&missing, AppError::MissingApiKey(details) if details.contains("'Team'")));
+        // Profiles belong to one provider
+        assert!(matches!(get(&credentials, Provider::OpenAI, Some("Work")), Err(AppError::MissingApiKey(_))));
+    }
+
+    #[test]
+    fn upgrades_the_flat_provider_map() {
+        let legacy = r#"{"openai":" sk-legacy ","anthropic":"","gemini":"unused"}"#;
+        let record = parse(legacy).unwrap();
+        assert_eq!(record.profiles.len(), 1);
+        assert_eq!(record.find(Provider::OpenAI, "default").map(|profile| profile.key.as_str()), Some("sk-legacy"));
+        assert_eq!(record.active.get(&Provider::OpenAI).map(String::as_str), Some(DEFAULT_PROFILE));
+        assert!(!record.active.contains_key(&Provider::Anthropic));
+
+        let upgraded = upgrade(legacy).unwrap().unwrap();
+        assert!(upgrade(&upgraded).unwrap().is_none());
+        assert!(matches!(parse("[1, 2]"), Err(AppError::StorageFailed(_))));
+    }
+
+    #[test]
+    fn repairs_active_profiles_that_no_longer_exist() {
+        let profile = |provider, name: &str| Profile { name: name.to_string(), provider, key: "key".to_string() };
+        let mut record = KeyRecord {
+            profiles: vec![profile(Provider::OpenAI, "Work"), profile(Provider::OpenAI, "Personal")],
+            active: [(Provider::OpenAI, "Gone".to_string()), (Provider::Mistral, "Work".to_string())].into_iter().collect(),
+        };
+        record.repair_active();
+        assert_eq!(record.active.get(&Provider::OpenAI).map(String::as_str), Some("Work"));
+        assert!(!record.active.contains_key(&Provider::Mistral));
+
+        record.active.insert(Provider::OpenAI, "personal".to_string());
+        record.repair_active();
+        assert_eq!(record.active.get(&Provider::OpenAI).map(String::as_str), Some("personal"));
+    }
+
+    #[test]
+    fn backup_names_keep_short_names_whole() {
+        assert_eq!(backup_name("Work", 1), "Work (backup)");
+        assert_eq!(backup_name("Work", 3), "Work (backup 3)");
+    }
+
+    #[test]
+    fn backup_names_of_long_names_fit_the_limit() {
+        let long = "Ä".repeat(MAX_PROFILE_NAME_CHARACTERS);
+        for attempt in [1, 2, 10, 100] {
+            let name = backup_name(&long, attempt);
+            assert_eq!(profile_name(&name).unwrap(), name);
+            assert!(name.starts_with("ÄÄÄ"));
+        }
+        // Shortening never leaves a space before the suffix
+        let spaced = format!("{} tail", "a".repeat(30));
+        assert_eq!(backup_name(&spaced, 1), format!("{} (backup)", "a".repeat(30)));
+    }
+}
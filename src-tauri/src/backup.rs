@@ -0,0 +1,249 @@
+//! Passphrase-encrypted backups of API keys, presets and settings.
+//!
+//! A backup holds the API key record from the active credential store and
+//! the interface settings the webview hands over, encrypted like the
+//! credential file. Restoring never returns keys to the webview: the
+//! preview names the key profiles and only settings come back as values.
+
+use crate::api_keys::{self, ConflictResolution, ImportedProfile};
+use crate::credentials::{self, Credentials};
+use crate::error::AppError;
+use crate::policy::Policy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const BACKUP_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Contents {
+    version: u32,
+    /// Milliseconds since the Unix epoch.
+    created_at: u64,
+    app_version: String,
+    api_keys: Option<String>,
+    settings: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupPreview {
+    pub created_at: u64,
+    pub app_version: String,
+    pub profiles: Vec<ImportedProfile>,
+    /// Interface settings by storage key, for the webview to compare and apply.
+    pub settings: BTreeMap<String, String>,
+}
+
+/// Writes a backup of the stored keys and `settings` to `path`.
+pub fn export(
+    path: &Path,
+    passphrase: &str,
+    app_version: &str,
+    settings: BTreeMap<String, String>,
+    credentials: &Credentials,
+) -> Result<(), AppError> {
+    let contents = Contents {
+        version: BACKUP_VERSION,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default(),
+        app_version: app_version.to_string(),
+        api_keys: api_keys::export(credentials)?,
+        settings,
+    };
+    let plaintext = serde_json::to_vec(&contents)
+        .map_err(|e| AppError::StorageFailed(format!("Failed to encode backup: {}", e)))?;
+    let encrypted = credentials::encrypt_with_passphrase(passphrase, &plaintext)?;
+    fs::write(path, encrypted).map_err(|e| AppError::StorageFailed(format!("Failed to write backup: {}", e)))?;
+    tracing::info!(settings = contents.settings.len(), "Backup exported");
+    Ok(())
+}
+
+fn read(path: &Path, passphrase: &str) -> Result<Contents, AppError> {
+    let encrypted =
+        fs::read_to_string(path).map_err(|e| AppError::StorageFailed(format!("Failed to read backup: {}", e)))?;
+    let plaintext = credentials::decrypt_with_passphrase(&encrypted, passphrase)?;
+    let contents: Contents = serde_json::from_slice(&plaintext)
+        .map_err(|e| AppError::StorageFailed(format!("Backup contents are damaged: {}", e)))?;
+    if contents.version > BACKUP_VERSION {
+        return Err(AppError::InvalidArgument(format!(
+            "This backup was made by a newer Correctify ({}). Update before restoring it.",
+            contents.app_version
+        )));
+    }
+    Ok(contents)
+}
+
+/// Decrypts a backup and compares its key profiles with the stored ones.
+pub fn preview(path: &Path, passphrase: &str, credentials: &Credentials) -> Result<BackupPreview, AppError> {
+    let contents = read(path, passphrase)?;
+    let profiles = match &contents.api_keys {
+        Some(record) => api_keys::preview_import(credentials, record)?,
+        None => Vec::new(),
+    };
+    Ok(BackupPreview {
+        created_at: contents.created_at,
+        app_version: contents.app_version,
+        profiles,
+        settings: contents.settings,
+    })
+}
+
+/// Restores the key profiles from a backup, skipping providers the policy
+/// forbids. Settings are applied by the webview from the preview. Returns how
+/// many profiles were added or replaced.
+pub fn restore(
+    path: &Path,
+    passphrase: &str,
+    resolution: ConflictResolution,
+    credentials: &Credentials,
+    policy: &Policy,
+) -> Result<u32, AppError> {
+    let contents = read(path, passphrase)?;
+    let restored = match &contents.api_keys {
+        Some(record) => api_keys::import(credentials, record, resolution, |provider| policy.allows_provider(provider))?,
+        None => 0,
+    };
+    tracing::info!(restored, ?resolution, "Backup restored");
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::Provider;
+    use crate::testing;
+
+    const PASSPHRASE: &str = "backup passphrase";
+
+    fn write(path: &Path, contents: &Contents) {
+        let encrypted = credentials::encrypt_with_passphrase(PASSPHRASE, &serde_json::to_vec(contents).unwrap()).unwrap();
+        fs::write(path, encrypted).unwrap();
+    }
+
+    fn backup_of(path: &Path, api_keys: &str) {
+        let contents = Contents {
+            version: BACKUP_VERSION,
+            created_at: 0,
+            app_version: "1.0.0".to_string(),
+            api_keys: Some(api_keys.to_string()),
+            settings: BTreeMap::new(),
+        };
+        write(path, &contents);
+    }
+
+    #[test]
+    fn restores_keys_and_settings_into_another_store() {
+        let (data, source) = testing::credentials();
+        api_keys::set(&source, Provider::OpenAI, Some("Work"), "sk-work-key-0001").unwrap();
+        let path = data.path().join("keys.correctify-backup");
+        let settings = BTreeMap::from([("writingStyle".to_string(), "\"formal\"".to_string())]);
+        export(&path, PASSPHRASE, "1.2.0", settings.clone(), &source).unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("sk-work-key-0001"));
+
+        let (_target_data, target) = testing::credentials();
+        let preview = preview(&path, PASSPHRASE, &target).unwrap();
+        assert_eq!(preview.app_version, "1.2.0");
+        assert_eq!(preview.settings, settings);
+        assert_eq!(preview.profiles.len(), 1);
+        assert_eq!((preview.profiles[0].name.as_str(), preview.profiles[0].conflict), ("Work", false));
+
+        let restored = restore(&path, PASSPHRASE, ConflictResolution::KeepExisting, &target, &Policy::default()).unwrap();
+        assert_eq!(restored, 1);
+        assert_eq!(api_keys::get(&target, Provider::OpenAI, None).unwrap().key, "sk-work-key-0001");
+        // Restoring the same backup again changes nothing
+        assert_eq!(restore(&path, PASSPHRASE, ConflictResolution::UseBackup, &target, &Policy::default()).unwrap(), 0);
+    }
+
+    #[test]
+    fn refuses_a_wrong_passphrase() {
+        let (data, credentials) = testing::credentials();
+        let path = data.path().join("empty.correctify-backup");
+        export(&path, PASSPHRASE, "1.2.0", BTreeMap::new(), &credentials).unwrap();
+        assert!(matches!(preview(&path, "another passphrase", &credentials), Err(AppError::WrongPassphrase(_))));
+        assert!(preview(&path, PASSPHRASE, &credentials).unwrap().profiles.is_empty());
+    }
+
+    #[test]
+    fn refuses_a_backup_from_a_newer_version() {
+        let (data, credentials) = testing::credentials();
+        let path = data.path().join("newer.correctify-backup");
+        let contents = Contents {
+            version: BACKUP_VERSION + 1,
+            created_at: 0,
+            app_version: "9.0.0".to_string(),
+            api_keys: None,
+            settings: BTreeMap::new(),
+        };
+        write(&path, &contents);
+        let error = preview(&path, PASSPHRASE, &credentials).err().unwrap();
+        assert!(matches!(&error, AppError::InvalidArgument(details) if details.contains("9.0.0")));
+    }
+
+    #[test]
+    fn resolves_conflicting_profiles_as_asked() {
+        let (data, credentials) = testing::credentials();
+        let path = data.path().join("conflict.correctify-backup");
+        let (_backed_up_data, backed_up) = testing::credentials();
+        api_keys::set(&backed_up, Provider::OpenAI, Some("Work"), "sk-backed-up").unwrap();
+        backup_of(&path, &api_keys::export(&backed_up).unwrap().unwrap());
+
+        let key = |name| api_keys::get(&credentials, Provider::OpenAI, Some(name)).map(|signing| signing.key);
+        let cases = [
+            (ConflictResolution::KeepExisting, 0, "sk-current", None),
+            (ConflictResolution::UseBackup, 1, "sk-backed-up", None),
+            (ConflictResolution::KeepBoth, 1, "sk-current", Some("sk-backed-up")),
+        ];
+        for (resolution, expected, work, backup) in cases {
+            api_keys::remove(&credentials, Provider::OpenAI, Some("Work (backup)")).unwrap();
+            api_keys::set(&credentials, Provider::OpenAI, Some("Work"), "sk-current").unwrap();
+            assert!(preview(&path, PASSPHRASE, &credentials).unwrap().profiles[0].conflict);
+
+            let restored = restore(&path, PASSPHRASE, resolution, &credentials, &Policy::default()).unwrap();
+            assert_eq!(restored, expected, "{:?}", resolution);
+            assert_eq!(key("Work").unwrap(), work, "{:?}", resolution);
+            assert_eq!(key("Work (backup)").ok().as_deref(), backup, "{:?}", resolution);
+        }
+    }
+
+    #[test]
+    fn skips_providers_the_policy_forbids() {
+        let (data, credentials) = testing::credentials();
+        let path = data.path().join("forbidden.correctify-backup");
+        backup_of(&path, r#"{"openai":"sk-openai","mistral":"mistral-key"}"#);
+        let policy = Policy { allowed_providers: Some(vec![Provider::Mistral]), ..Policy::default() };
+
+        assert_eq!(restore(&path, PASSPHRASE, ConflictResolution::UseBackup, &credentials, &policy).unwrap(), 1);
+        assert!(api_keys::get(&credentials, Provider::OpenAI, None).is_err());
+        assert_eq!(api_keys::get(&credentials, Provider::Mistral, None).unwrap().key, "mistral-key");
+    }
+
+    #[test]
+    fn refuses_profile_names_that_could_not_be_typed() {
+        let (data, credentials) = testing::credentials();
+        let path = data.path().join("names.correctify-backup");
+        let long = "x".repeat(41);
+        for name in ["   ", "Work\u{7}", long.as_str()] {
+            let record = serde_json::json!({
+                "profiles": [{ "name": name, "provider": "openai", "key": "sk-openai" }],
+                "active": {},
+            });
+            backup_of(&path, &record.to_string());
+            assert!(matches!(preview(&path, PASSPHRASE, &credentials), Err(AppError::InvalidArgument(_))));
+            let restored = restore(&path, PASSPHRASE, ConflictResolution::UseBackup, &credentials, &Policy::default());
+            assert!(matches!(restored, Err(AppError::InvalidArgument(_))), "{:?}", name);
+        }
+        assert!(api_keys::status(&credentials).unwrap().values().all(|status| !status.set));
+
+        // Surrounding spaces are trimmed like typed names
+        let record = r#"{"profiles":[{"name":" Work ","provider":"openai","key":"sk-openai"}],"active":{}}"#;
+        backup_of(&path, record);
+        assert_eq!(restore(&path, PASSPHRASE, ConflictResolution::UseBackup, &credentials, &Policy::default()).unwrap(), 1);
+        assert_eq!(api_keys::get(&credentials, Provider::OpenAI, None).unwrap().profile, "Work");
+    }
+}
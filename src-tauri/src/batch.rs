@@ -0,0 +1,288 @@
+//! Batch correction of Markdown and text files.
+//!
+//! Files are corrected a few at a time, and the client keeps the requests
+//! for them within the provider's rate limits. Each result goes to a `.corrected` sibling (`notes.md` becomes
+//! `notes.corrected.md`) or replaces the file once a copy is kept as
+//! `notes.md.bak`.
+
+use crate::dictionary::Terms;
+use crate::error::AppError;
+use crate::llm::{CorrectionRequest, CorrectionResult, Usage};
+use crate::review;
+use futures_util::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+pub const EXTENSIONS: [&str; 3] = ["md", "markdown", "txt"];
+const CORRECTED_SUFFIX: &str = ".corrected";
+const MAX_FILES: usize = 500;
+// Files read and waiting for their corrections at once
+const FILES_IN_FLIGHT: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputMode {
+    /// Writes `notes.corrected.md` next to `notes.md`.
+    Sibling,
+    /// Overwrites the file after copying it to `notes.md.bak`.
+    InPlace,
+}
+
+/// What to correct and how; each file is corrected with the same settings.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRequest {
+    /// Files and folders.
+    pub paths: Vec<PathBuf>,
+    pub output: OutputMode,
+    pub model: String,
+    pub writing_style: Option<String>,
+    pub custom_rules: Option<String>,
+    pub language: Option<String>,
+    pub profile: Option<String>,
+    /// The personal dictionary, filled in by the backend.
+    #[serde(skip)]
+    pub dictionary: Arc<Terms>,
+}
+
+impl BatchRequest {
+    pub fn correction(&self, text: String) -> CorrectionRequest {
+        CorrectionRequest {
+            text,
+            model: self.model.clone(),
+            writing_style: self.writing_style.clone(),
+            custom_rules: self.custom_rules.clone(),
+            language: self.language.clone(),
+            temperature: None,
+            profile: self.profile.clone(),
+            dictionary: self.dictionary.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileStatus {
+    Started,
+    Corrected,
+    Unchanged,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReport {
+    pub path: String,
+    pub status: FileStatus,
+    /// Where the corrected text was written; `None` when nothing was.
+    pub output: Option<String>,
+    pub backup: Option<String>,
+    pub edits: usize,
+    pub usage: Option<Usage>,
+    /// Answered from the correction cache, at no cost.
+    pub cached: bool,
+    /// The key profile that paid for the correction.
+    pub profile: Option<String>,
+    pub duration_ms: u64,
+    pub error: Option<AppError>,
+}
+
+/// Sent when a file starts and when it finishes.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchProgress<'a> {
+    pub completed: usize,
+    pub total: usize,
+    pub file: &'a FileReport,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSummary {
+    pub corrected: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+    pub files: Vec<FileReport>,
+}
+
+/// The files to correct: the chosen files, and the Markdown and text files
+/// anywhere under the chosen folders. Hidden entries, symlinks and earlier
+/// `.corrected` outputs inside folders are skipped.
+pub fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, AppError> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk(path, &mut files)?;
+        } else if !path.is_file() {
+            return Err(AppError::NotFound(format!("No such file or folder: {}", path.display())));
+        } else if is_correctable(path) {
+            files.push(path.clone());
+        } else {
+            return Err(AppError::InvalidArgument(format!("Only .md, .markdown and .txt files can be corrected: {}", path.display())));
+        }
+    }
+    files.sort();
+    files.dedup();
+    if files.is_empty() {
+        return Err(AppError::NotFound("No Markdown or text files were found".to_string()));
+    }
+    if files.len() > MAX_FILES {
+        return Err(AppError::InvalidArgument(format!("Found {} files; correct at most {} at once", files.len(), MAX_FILES)));
+    }
+    Ok(files)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), AppError> {
+    let entries = fs::read_dir(dir).map_err(|e| AppError::StorageFailed(format!("Failed to read {}: {}", dir.display(), e)))?;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            walk(&path, files)?;
+        } else if file_type.is_file() && is_correctable(&path) && !is_output(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn is_correctable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()))
+}
+
+fn is_output(path: &Path) -> bool {
+    path.file_stem().and_then(|stem| stem.to_str()).is_some_and(|stem| stem.ends_with(CORRECTED_SUFFIX))
+}
+
+fn sibling_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}{}.{}", stem, CORRECTED_SUFFIX, extension))
+}
+
+// Never overwrites an earlier backup: notes.md.bak, then notes.md.1.bak and so on
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (0..)
+        .map(|n| if n == 0 { format!("{}.bak", name) } else { format!("{}.{}.bak", name, n) })
+        .map(|backup| path.with_file_name(backup))
+        .find(|backup| !backup.exists())
+        .unwrap_or_else(|| path.with_file_name(format!("{}.bak", name)))
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), AppError> {
+    fs::write(path, contents).map_err(|e| AppError::StorageFailed(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Corrects `files` a few at a time and calls `progress` as each file starts
+/// and finishes. A failed file does not stop the others.
+pub async fn run<F, Fut, P>(files: Vec<PathBuf>, mode: OutputMode, correct: F, progress: P) -> BatchSummary
+where
+    F: Fn(String) -> Fut + Sync,
+    Fut: Future<Output = Result<CorrectionResult, AppError>> + Send,
+    P: Fn(BatchProgress<'_>) + Sync,
+{
+    let started = Instant::now();
+    let total = files.len();
+    let completed = AtomicUsize::new(0);
+    let (correct, progress, completed) = (&correct, &progress, &completed);
+
+    let mut files: Vec<FileReport> = stream::iter(files)
+        .map(|path| async move {
+            let mut report = FileReport {
+                path: path.display().to_string(),
+                status: FileStatus::Started,
+                output: None,
+                backup: None,
+                edits: 0,
+                usage: None,
+                cached: false,
+                profile: None,
+                duration_ms: 0,
+                error: None,
+            };
+            progress(BatchProgress { completed: completed.load(Ordering::SeqCst), total, file: &report });
+            let file_started = Instant::now();
+            if let Err(e) = correct_file(&path, mode, correct, &mut report).await {
+                tracing::warn!(code = e.code(), "Failed to correct a file: {}", e.details());
+                report.status = FileStatus::Failed;
+                report.error = Some(e);
+            }
+            report.duration_ms = file_started.elapsed().as_millis() as u64;
+            let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+            progress(BatchProgress { completed: done, total, file: &report });
+            report
+        })
+        .buffer_unordered(FILES_IN_FLIGHT)
+        .collect()
+        .await;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let count = |status| files.iter().filter(|file| file.status == status).count();
+    let summary = BatchSummary {
+        corrected: count(FileStatus::Corrected),
+        unchanged: count(FileStatus::Unchanged),
+        failed: count(FileStatus::Failed),
+        duration_ms: started.elapsed().as_millis() as u64,
+        files,
+    };
+    tracing::info!(corrected = summary.corrected, unchanged = summary.unchanged, failed = summary.failed, "Batch correction finished");
+    summary
+}
+
+async fn correct_file<F, Fut>(path: &Path, mode: OutputMode, correct: &F, report: &mut FileReport) -> Result<(), AppError>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<CorrectionResult, AppError>>,
+{
+    let original = fs::read_to_string(path).map_err(|e| AppError::StorageFailed(format!("Failed to read {}: {}", path.display(), e)))?;
+    let body = original.trim();
+    if body.is_empty() {
+        report.status = FileStatus::Unchanged;
+        return Ok(());
+    }
+
+    let result = correct(body.to_string()).await?;
+    // The model trims its answer; keep the file's own surrounding whitespace
+    let leading = &original[..original.len() - original.trim_start().len()];
+    let trailing = &original[original.trim_end().len()..];
+    let corrected = format!("{}{}{}", leading, result.result, trailing);
+    report.usage = Some(result.usage);
+    report.cached = result.cached;
+    report.profile = result.profile;
+    report.edits = review::edits(&original, &corrected).len();
+    if corrected == original {
+        report.status = FileStatus::Unchanged;
+        return Ok(());
+    }
+
+    match mode {
+        OutputMode::Sibling => {
+            let output = sibling_path(path);
+            write(&output, &corrected)?;
+            report.output = Some(output.display().to_string());
+        }
+        OutputMode::InPlace => {
+            let backup = backup_path(path);
+            fs::copy(path, &backup).map_err(|e| AppError::StorageFailed(format!("Failed to back up {}: {}", path.display(), e)))?;
+            write(path, &corrected)?;
+            report.backup = Some(backup.display().to_string());
+            report.output = Some(report.path.clone());
+        }
+    }
+    report.status = FileStatus::Corrected;
+    Ok(())
+}
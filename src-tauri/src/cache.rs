@@ -0,0 +1,292 @@
+//! Local cache of corrections.
+//!
+//! A repeated request, the same text with the same style, custom rules,
+//! language and model, is answered from disk without a provider request.
+//! Entries are keyed by a SHA-256 hash of those inputs and expire after a
+//! week; the least recently used go first once the cache is full.
+
+use crate::error::AppError;
+use crate::llm::CorrectionRequest;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CACHE_FILE: &str = "correction-cache.json";
+const MAX_ENTRIES: usize = 5_000;
+const MAX_BYTES: usize = 4 * 1024 * 1024;
+const TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    result: String,
+    created_at: u64,
+    used_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Stored {
+    enabled: bool,
+    entries: HashMap<String, Entry>,
+}
+
+impl Default for Stored {
+    fn default() -> Self {
+        Stored { enabled: true, entries: HashMap::new() }
+    }
+}
+
+/// What the settings show about the cache.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStatus {
+    pub enabled: bool,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+pub struct CorrectionCache {
+    path: PathBuf,
+    stored: Mutex<Stored>,
+}
+
+impl CorrectionCache {
+    /// Opens the cache in `data_dir`, starting empty when it is missing or unreadable.
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(CACHE_FILE);
+        let mut stored: Stored = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        let now = now();
+        stored.entries.retain(|_, entry| now.saturating_sub(entry.created_at) < TTL_SECS);
+        CorrectionCache { path, stored: Mutex::new(stored) }
+    }
+
+    pub fn status(&self) -> CacheStatus {
+        let stored = self.stored.lock().unwrap();
+        CacheStatus {
+            enabled: stored.enabled,
+            entries: stored.entries.len(),
+            bytes: stored.entries.values().map(|entry| entry.result.len()).sum(),
+        }
+    }
+
+    /// Turning the cache off also forgets every stored correction.
+    pub fn set_enabled(&self, enabled: bool) -> Result<(), AppError> {
+        let mut stored = self.stored.lock().unwrap();
+        stored.enabled = enabled;
+        if !enabled {
+            stored.entries.clear();
+        }
+        self.save(&stored)
+    }
+
+    pub fn clear(&self) -> Result<(), AppError> {
+        let mut stored = self.stored.lock().unwrap();
+        stored.entries.clear();
+        self.save(&stored)
+    }
+
+    /// The cached correction for `request`, if the cache is on and has a fresh one.
+    pub fn get(&self, request: &CorrectionRequest) -> Option<String> {
+        let mut stored = self.stored.lock().unwrap();
+        if !stored.enabled {
+            return None;
+        }
+        let now = now();
+        let entry = stored.entries.get_mut(&key(request))?;
+        if now.saturating_sub(entry.created_at) >= TTL_SECS {
+            return None;
+        }
+        entry.used_at = now;
+        Some(entry.result.clone())
+    }
+
+    /// Remembers `result` as the correction for `request`.
+    pub fn insert(&self, request: &CorrectionRequest, result: &str) -> Result<(), AppError> {
+        self.insert_all([(request, result)])
+    }
+
+    /// Remembers each correction and saves once, evicting the least recently
+    /// used entries past the limits.
+    pub fn insert_all<'a>(&self, corrections: impl IntoIterator<Item = (&'a CorrectionRequest, &'a str)>) -> Result<(), AppError> {
+        let mut stored = self.stored.lock().unwrap();
+        if !stored.enabled {
+            return Ok(());
+        }
+        let now = now();
+        stored.entries.retain(|_, entry| now.saturating_sub(entry.created_at) < TTL_SECS);
+        for (request, result) in corrections {
+            if result.len() <= MAX_BYTES / 4 {
+                stored.entries.insert(key(request), Entry { result: result.to_string(), created_at: now, used_at: now });
+            }
+        }
+
+        let mut bytes: usize = stored.entries.values().map(|entry| entry.result.len()).sum();
+        while stored.entries.len() > MAX_ENTRIES || bytes > MAX_BYTES {
+            let Some(oldest) = stored.entries.iter().min_by_key(|(_, entry)| entry.used_at).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            if let Some(entry) = stored.entries.remove(&oldest) {
+                bytes -= entry.result.len();
+            }
+        }
+        self.save(&stored)
+    }
+
+    fn save(&self, stored: &Stored) -> Result<(), AppError> {
+        let contents = serde_json::to_string(stored).map_err(|e| AppError::StorageFailed(e.to_string()))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| AppError::StorageFailed(format!("Failed to save the correction cache: {}", e)))?;
+        }
+        // Written aside and renamed over the cache, so a crash mid-write never leaves half a file
+        let temporary = self.path.with_extension("tmp");
+        fs::write(&temporary, contents)
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|e| AppError::StorageFailed(format!("Failed to save the correction cache: {}", e)))
+    }
+}
+
+// Every input that changes the correction, the dictionary terms the text
+// mentions included, each prefixed with its length so that no two requests run
+// together into the same bytes
+fn key(request: &CorrectionRequest) -> String {
+    let mut hasher = Sha256::new();
+    let dictionary = request.dictionary.prompt_rules(&request.text);
+    for part in [
+        request.model.as_str(),
+        request.writing_style.as_deref().unwrap_or("grammar"),
+        request.custom_rules.as_deref().unwrap_or_default(),
+        request.language.as_deref().unwrap_or_default(),
+        dictionary.as_deref().unwrap_or_default(),
+        request.text.as_str(),
+    ] {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part.as_bytes());
+    }
+    hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_secs()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dictionary::Terms;
+    use crate::testing;
+    use std::sync::Arc;
+
+    fn request(text: &str) -> CorrectionRequest {
+        CorrectionRequest {
+            text: text.to_string(),
+            model: "gpt-5.4-mini".to_string(),
+            writing_style: None,
+            custom_rules: None,
+            language: None,
+            temperature: None,
+            profile: None,
+            dictionary: Default::default(),
+        }
+    }
+
+    fn age(cache: &CorrectionCache, request: &CorrectionRequest, created: u64, used: u64) {
+        let mut stored = cache.stored.lock().unwrap();
+        let entry = stored.entries.get_mut(&key(request)).unwrap();
+        entry.created_at = now() - created;
+        entry.used_at = now() - used;
+    }
+
+    #[test]
+    fn every_correction_input_changes_the_key() {
+        let base = key(&request("Teh Acme text"));
+        let protected = |term: &str| Arc::new(Terms { protected: vec![term.to_string()], spellings: Vec::new() });
+        let variations = [
+            CorrectionRequest { model: "gpt-5.4".to_string(), ..request("Teh Acme text") },
+            CorrectionRequest { writing_style: Some("formal".to_string()), ..request("Teh Acme text") },
+            CorrectionRequest { custom_rules: Some("Use British spelling".to_string()), ..request("Teh Acme text") },
+            CorrectionRequest { language: Some("de".to_string()), ..request("Teh Acme text") },
+            CorrectionRequest { dictionary: protected("Acme"), ..request("Teh Acme text") },
+            request("Teh Acme text."),
+        ];
+        for variation in &variations {
+            assert_ne!(key(variation), base, "{:?}", variation);
+        }
+
+        // Settings that cannot change the answer keep the key
+        let same = [
+            CorrectionRequest { writing_style: Some("grammar".to_string()), ..request("Teh Acme text") },
+            CorrectionRequest { temperature: Some(0.7), profile: Some("Work".to_string()), ..request("Teh Acme text") },
+            CorrectionRequest { dictionary: protected("Globex"), ..request("Teh Acme text") },
+        ];
+        for request in &same {
+            assert_eq!(key(request), base, "{:?}", request);
+        }
+    }
+
+    #[test]
+    fn entries_expire_after_a_week() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        let cache = CorrectionCache::open(directory);
+        let (fresh, stale) = (request("Fresh"), request("Stale"));
+        cache.insert(&fresh, "Fresh.").unwrap();
+        cache.insert(&stale, "Stale.").unwrap();
+        age(&cache, &fresh, TTL_SECS - 60, 0);
+        age(&cache, &stale, TTL_SECS, 0);
+
+        assert_eq!(cache.get(&fresh).as_deref(), Some("Fresh."));
+        // Using an entry does not extend its life
+        assert!(cache.get(&stale).is_none());
+
+        cache.insert(&request("Other"), "Other.").unwrap();
+        let reopened = CorrectionCache::open(directory);
+        assert_eq!(reopened.status().entries, 2);
+        assert!(reopened.get(&stale).is_none());
+        assert!(!directory.join(CACHE_FILE).with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entries() {
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+        let requests: Vec<CorrectionRequest> = (0..MAX_ENTRIES).map(|index| request(&format!("Text {}", index))).collect();
+        cache.insert_all(requests.iter().map(|request| (request, "Corrected"))).unwrap();
+        for request in &requests {
+            age(&cache, request, 60, 30);
+        }
+        age(&cache, &requests[0], 60, 50);
+        age(&cache, &requests[1], 60, 40);
+        // Reading the oldest entry makes the next one the least recently used
+        assert!(cache.get(&requests[0]).is_some());
+
+        cache.insert(&request("New"), "New.").unwrap();
+        assert_eq!(cache.status().entries, MAX_ENTRIES);
+        assert!(cache.get(&requests[0]).is_some());
+        assert!(cache.get(&requests[1]).is_none());
+        assert_eq!(cache.get(&request("New")).as_deref(), Some("New."));
+    }
+
+    #[test]
+    fn evicts_past_the_size_limit_and_skips_huge_results() {
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+        let large = "x".repeat(MAX_BYTES / 4);
+        for index in 0..5 {
+            cache.insert(&request(&index.to_string()), &large).unwrap();
+            age(&cache, &request(&index.to_string()), 0, 10 - index);
+        }
+        assert_eq!(cache.status().entries, 4);
+        assert!(cache.get(&request("0")).is_none());
+
+        cache.insert(&request("huge"), &format!("{}x", large)).unwrap();
+        assert!(cache.get(&request("huge")).is_none());
+    }
+}
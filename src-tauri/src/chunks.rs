@@ -0,0 +1,241 @@
+//! Markdown-aware splitting of long documents.
+//!
+//! A long document is cut at block boundaries (blank lines, headings, list
+//! items), never inside a fenced code block, so each chunk fits a single
+//! request and any model's context. The whitespace around chunks is never
+//! sent, so putting the corrected chunks back between the original
+//! separators rebuilds the document byte for byte.
+
+/// Longest chunk sent in one request. Well inside the smallest context window
+/// (32k tokens) and the output limit, with room for the prompt.
+pub const MAX_CHUNK_CHARACTERS: usize = 12_000;
+
+/// A byte range of the document, without surrounding whitespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk {
+    pub start: usize,
+    pub end: usize,
+    /// False when the chunk is nothing but fenced code, which is kept as is.
+    pub prose: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Block {
+    start: usize,
+    end: usize,
+    code: bool,
+}
+
+/// Splits `text` into chunks of at most `max_characters` where the blocks
+/// allow it. A single paragraph over the limit is split between lines, and a
+/// code block or a line over the limit becomes a chunk of its own.
+pub fn split(text: &str, max_characters: usize) -> Vec<Chunk> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    // The open chunk and its length in characters, separators included
+    let mut current: Option<(Chunk, usize)> = None;
+    for block in blocks(text).into_iter().flat_map(|block| pieces(text, block, max_characters)) {
+        let piece = Chunk { start: block.start, end: block.end, prose: !block.code };
+        current = match current {
+            Some((chunk, length)) => {
+                let joined = length + text[chunk.end..piece.end].chars().count();
+                if joined <= max_characters {
+                    Some((Chunk { end: piece.end, prose: chunk.prose || piece.prose, ..chunk }, joined))
+                } else {
+                    chunks.push(chunk);
+                    Some((piece, text[piece.start..piece.end].chars().count()))
+                }
+            }
+            None => Some((piece, text[piece.start..piece.end].chars().count())),
+        };
+    }
+    chunks.extend(current.map(|(chunk, _)| chunk));
+    trimmed(text, chunks)
+}
+
+/// The blocks of `text` one by one: the units whose corrections are worth
+/// remembering on their own.
+pub fn paragraphs(text: &str) -> Vec<Chunk> {
+    let paragraphs = blocks(text).into_iter().map(|block| Chunk { start: block.start, end: block.end, prose: !block.code });
+    trimmed(text, paragraphs.collect())
+}
+
+// The model trims its answer, so leading indentation must stay outside the chunk
+fn trimmed(text: &str, mut chunks: Vec<Chunk>) -> Vec<Chunk> {
+    for chunk in &mut chunks {
+        let slice = &text[chunk.start..chunk.end];
+        chunk.start += slice.len() - slice.trim_start().len();
+        chunk.end = chunk.start + slice.trim().len();
+    }
+    chunks.retain(|chunk| chunk.start < chunk.end);
+    chunks
+}
+
+/// Top-level Markdown blocks. `end` excludes the line break after the block.
+fn blocks(text: &str) -> Vec<Block> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Block> = None;
+    let mut fence: Option<(char, usize)> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let end = start + content.len();
+        let trimmed = content.trim_start();
+
+        if let Some(open) = fence {
+            if let Some(block) = current.as_mut() {
+                block.end = end;
+            }
+            if closes_fence(trimmed, open) {
+                fence = None;
+                blocks.extend(current.take());
+            }
+            continue;
+        }
+        if let Some(open) = fence_marker(trimmed) {
+            blocks.extend(current.take());
+            current = Some(Block { start, end, code: true });
+            fence = Some(open);
+            continue;
+        }
+        if trimmed.is_empty() {
+            blocks.extend(current.take());
+            continue;
+        }
+        if is_heading(trimmed) {
+            blocks.extend(current.take());
+            blocks.push(Block { start, end, code: false });
+            continue;
+        }
+        match current.as_mut() {
+            Some(block) if !is_list_item(trimmed) => block.end = end,
+            _ => {
+                blocks.extend(current.take());
+                current = Some(Block { start, end, code: false });
+            }
+        }
+    }
+    // An unclosed fence runs to the end of the document
+    blocks.extend(current);
+    blocks
+}
+
+// A prose block over the limit, split between its lines
+fn pieces(text: &str, block: Block, max_characters: usize) -> Vec<Block> {
+    let slice = &text[block.start..block.end];
+    if block.code || slice.chars().count() <= max_characters {
+        return vec![block];
+    }
+    let mut pieces = Vec::new();
+    let mut offset = block.start;
+    for line in slice.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\n', '\r']);
+        pieces.push(Block { start: offset, end: offset + content.len(), code: false });
+        offset += line.len();
+    }
+    pieces
+}
+
+fn fence_marker(line: &str) -> Option<(char, usize)> {
+    let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let length = line.chars().take_while(|c| *c == marker).count();
+    (length >= 3).then_some((marker, length))
+}
+
+fn closes_fence(line: &str, (marker, length): (char, usize)) -> bool {
+    let run = line.chars().take_while(|c| *c == marker).count();
+    run >= length && line[run * marker.len_utf8()..].trim().is_empty()
+}
+
+fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level) && line[level..].chars().next().is_none_or(char::is_whitespace)
+}
+
+fn is_list_item(line: &str) -> bool {
+    if ["- ", "* ", "+ "].iter().any(|marker| line.starts_with(marker)) {
+        return true;
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    digits > 0 && (line[digits..].starts_with(". ") || line[digits..].starts_with(") "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks that the chunks are in order, trimmed, cut on character
+    // boundaries and separated by whitespace only, then returns their texts
+    fn round_trip(text: &str, chunks: &[Chunk]) -> Vec<String> {
+        let mut rebuilt = String::new();
+        let mut position = 0;
+        for chunk in chunks {
+            assert!(position <= chunk.start && chunk.start < chunk.end);
+            assert!(text.is_char_boundary(chunk.start) && text.is_char_boundary(chunk.end));
+            assert!(text[position..chunk.start].trim().is_empty(), "text lost before {:?}", chunk);
+            let slice = &text[chunk.start..chunk.end];
+            assert_eq!(slice, slice.trim());
+            rebuilt.push_str(&text[position..chunk.start]);
+            rebuilt.push_str(slice);
+            position = chunk.end;
+        }
+        assert!(text[position..].trim().is_empty());
+        rebuilt.push_str(&text[position..]);
+        assert_eq!(rebuilt, text);
+        chunks.iter().map(|chunk| text[chunk.start..chunk.end].to_string()).collect()
+    }
+
+    #[test]
+    fn never_splits_backtick_or_tilde_fences() {
+        let backticks = "```rust\nlet a = 1;\n\n# not a heading\n- not a list\n```";
+        let tildes = "~~~\n```\n\nstill code\n~~~";
+        let text = format!("Intro text.\n\n{}\n\nMiddle text.\n\n{}\n\nLast text.\n", backticks, tildes);
+
+        for chunks in [split(&text, 20), paragraphs(&text)] {
+            let texts = round_trip(&text, &chunks);
+            assert_eq!(texts, ["Intro text.", backticks, "Middle text.", tildes, "Last text."]);
+            assert_eq!(chunks.iter().map(|chunk| chunk.prose).collect::<Vec<_>>(), [true, false, true, false, true]);
+        }
+        // Under a generous limit code and prose share a chunk, which is prose
+        let chunks = split(&text, MAX_CHUNK_CHARACTERS);
+        assert_eq!(round_trip(&text, &chunks), [text.trim_end()]);
+        assert!(chunks[0].prose);
+    }
+
+    #[test]
+    fn keeps_crlf_line_breaks_outside_chunks() {
+        let text = "First line\r\nsecond line\r\n\r\nNext paragraph\r\n";
+        assert_eq!(round_trip(text, &paragraphs(text)), ["First line\r\nsecond line", "Next paragraph"]);
+        assert_eq!(round_trip(text, &split(text, 12)), ["First line", "second line", "Next paragraph"]);
+    }
+
+    #[test]
+    fn a_trailing_newline_stays_outside_the_last_chunk() {
+        for text in ["One.\n\nTwo.", "One.\n\nTwo.\n", "\n  One.\n\nTwo.\n\n"] {
+            assert_eq!(round_trip(text, &paragraphs(text)), ["One.", "Two."]);
+            assert_eq!(round_trip(text, &split(text, 4)), ["One.", "Two."]);
+        }
+        assert!(split("", 10).is_empty());
+        assert!(paragraphs(" \n\n ").is_empty());
+    }
+
+    #[test]
+    fn a_long_paragraph_is_split_between_lines() {
+        let text = "aaaaaaaaa\nbbbbbbbbb\nccccccccc\nddddddddd\nexceeds the limit alone";
+        let chunks = split(text, 19);
+        assert_eq!(
+            round_trip(text, &chunks),
+            ["aaaaaaaaa\nbbbbbbbbb", "ccccccccc\nddddddddd", "exceeds the limit alone"]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.prose));
+    }
+
+    #[test]
+    fn limits_count_characters_not_bytes() {
+        let text = "ééééé\nüüüüü\n日本語日本語\n\n🙂🙂🙂";
+        let chunks = split(text, 11);
+        assert_eq!(round_trip(text, &chunks), ["ééééé\nüüüüü", "日本語日本語\n\n🙂🙂🙂"]);
+        assert_eq!(round_trip(text, &split(text, 10)), ["ééééé", "üüüüü", "日本語日本語", "🙂🙂🙂"]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Command-line and deep-link actions.
+//!
+//! Only one Correctify runs at a time. A second launch hands its arguments to
+//! the running instance and exits, so launchers and scripts can trigger
+//! actions such as `correctify --correct-clipboard`. Links with the
+//! `correctify://` scheme reach the same actions:
+//!
+//! - `correctify://correct?style=formal` corrects the clipboard, optionally in another style
+//! - `correctify://open-settings` opens Settings
+//! - `correctify://preset/<id>` applies a saved preset
+
+use crate::error::AppError;
+use crate::prompts::WRITING_STYLES;
+use percent_encoding::percent_decode_str;
+use std::sync::Mutex;
+use url::Url;
+
+pub const SCHEME: &str = "correctify";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Bring the main window to the front.
+    Show,
+    /// Bring the main window to the front with Settings open.
+    OpenSettings,
+    /// Correct the clipboard as if the global shortcut had been pressed, in
+    /// `style` instead of the selected writing style when one is given.
+    CorrectClipboard { style: Option<String> },
+    /// Apply the saved preset with this id.
+    ApplyPreset(String),
+}
+
+/// Reads the actions from a command line, skipping the executable name.
+/// On Linux and Windows an opened `correctify://` link arrives this way too.
+/// Unknown arguments and links are logged and ignored.
+pub fn parse(argv: &[String]) -> Vec<Action> {
+    let mut actions = Vec::new();
+    for argument in argv.iter().skip(1) {
+        match argument.as_str() {
+            "--show" => actions.push(Action::Show),
+            "--correct-clipboard" => actions.push(Action::CorrectClipboard { style: None }),
+            link if link.starts_with("correctify:") => match parse_link(link) {
+                Ok(action) => actions.push(action),
+                Err(e) => tracing::warn!("Ignoring link: {}", e),
+            },
+            other => tracing::warn!(argument = other, "Ignoring unknown command-line argument"),
+        }
+    }
+    actions
+}
+
+/// Reads the action from a `correctify://` link.
+pub fn parse_link(link: &str) -> Result<Action, AppError> {
+    let invalid = |reason: &str| AppError::InvalidArgument(format!("{}: {}", reason, link));
+    let url = Url::parse(link).map_err(|_| invalid("Malformed link"))?;
+    if url.scheme() != SCHEME {
+        return Err(invalid("Not a Correctify link"));
+    }
+    let segments: Vec<&str> = url.path_segments().into_iter().flatten().filter(|segment| !segment.is_empty()).collect();
+
+    match (url.host_str().unwrap_or_default(), segments.as_slice()) {
+        ("correct", []) => {
+            let style = url.query_pairs().find(|(name, _)| name == "style").map(|(_, value)| value.into_owned());
+            if style.as_deref().is_some_and(|style| !WRITING_STYLES.contains(&style)) {
+                return Err(invalid("Unknown writing style"));
+            }
+            Ok(Action::CorrectClipboard { style })
+        }
+        ("open-settings", []) => Ok(Action::OpenSettings),
+        ("preset", [id]) => {
+            let id = percent_decode_str(id).decode_utf8().map_err(|_| invalid("Malformed preset id"))?;
+            Ok(Action::ApplyPreset(id.into_owned()))
+        }
+        _ => Err(invalid("Unknown link")),
+    }
+}
+
+/// Holds actions until the webview listens for the events they emit.
+pub struct PendingActions {
+    /// `None` once the webview is ready and actions run straight away.
+    queue: Mutex<Option<Vec<Action>>>,
+}
+
+impl Default for PendingActions {
+    fn default() -> Self {
+        PendingActions { queue: Mutex::new(Some(Vec::new())) }
+    }
+}
+
+impl PendingActions {
+    /// Queues `actions` while the webview is loading; hands them back once it is ready.
+    pub fn defer(&self, actions: Vec<Action>) -> Vec<Action> {
+        match self.queue.lock().unwrap().as_mut() {
+            Some(queue) => {
+                queue.extend(actions);
+                Vec::new()
+            }
+            None => actions,
+        }
+    }
+
+    /// Marks the webview as ready and returns the actions queued until then.
+    pub fn release(&self) -> Vec<Action> {
+        self.queue.lock().unwrap().take().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arguments(arguments: &[&str]) -> Vec<String> {
+        std::iter::once("correctify").chain(arguments.iter().copied()).map(str::to_string).collect()
+    }
+
+    #[test]
+    fn reads_actions_from_the_command_line() {
+        let argv = arguments(&["--show", "--verbose", "--correct-clipboard", "correctify://nowhere", "correctify://open-settings"]);
+        let actions = parse(&argv);
+        assert_eq!(actions, vec![Action::Show, Action::CorrectClipboard { style: None }, Action::OpenSettings]);
+        // The executable name is never an action, even when it looks like one
+        assert!(parse(&["--show".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn holds_actions_until_the_webview_is_ready() {
+        let pending = PendingActions::default();
+        assert!(pending.defer(vec![Action::Show]).is_empty());
+        assert!(pending.defer(vec![Action::OpenSettings]).is_empty());
+        assert_eq!(pending.release(), vec![Action::Show, Action::OpenSettings]);
+        assert_eq!(pending.defer(vec![Action::Show]), vec![Action::Show]);
+        assert!(pending.release().is_empty());
+    }
+
+    #[test]
+    fn reads_the_supported_links() {
+        let cases = [
+            ("correctify://correct", Action::CorrectClipboard { style: None }),
+            ("correctify://correct/", Action::CorrectClipboard { style: None }),
+            ("correctify://correct?style=formal", Action::CorrectClipboard { style: Some("formal".to_string()) }),
+            ("correctify://open-settings", Action::OpenSettings),
+            ("correctify://preset/3f2a-b1", Action::ApplyPreset("3f2a-b1".to_string())),
+        ];
+        for (link, action) in cases {
+            assert_eq!(parse_link(link).unwrap(), action, "{}", link);
+        }
+    }
+
+    #[test]
+    fn decodes_percent_encoded_values() {
+        let style = parse_link("correctify://correct?style=%66ormal").unwrap();
+        assert_eq!(style, Action::CorrectClipboard { style: Some("formal".to_string()) });
+        let preset = parse_link("correctify://preset/Team%20notes%20%C3%A9t%C3%A9").unwrap();
+        assert_eq!(preset, Action::ApplyPreset("Team notes été".to_string()));
+        assert!(parse_link("correctify://preset/%FF").is_err());
+    }
+
+    #[test]
+    fn ignores_other_query_parameters() {
+        let action = parse_link("correctify://correct?source=raycast&style=concise&style=formal").unwrap();
+        assert_eq!(action, Action::CorrectClipboard { style: Some("concise".to_string()) });
+        assert_eq!(parse_link("correctify://open-settings?tab=shortcut").unwrap(), Action::OpenSettings);
+    }
+
+    #[test]
+    fn refuses_unknown_styles() {
+        for link in ["correctify://correct?style=pirate", "correctify://correct?style=", "correctify://correct?style=Formal"] {
+            assert!(matches!(parse_link(link), Err(AppError::InvalidArgument(details)) if details.contains("style")), "{}", link);
+        }
+    }
+
+    #[test]
+    fn refuses_unknown_links() {
+        let links = [
+            "correctify://delete-keys",
+            "correctify://correct/now",
+            "correctify://preset",
+            "correctify://preset/a/b",
+            "correctify://open-settings/general",
+            "https://correct?style=formal",
+            "correctify",
+        ];
+        for link in links {
+            assert!(matches!(parse_link(link), Err(AppError::InvalidArgument(_))), "{}", link);
+        }
+    }
+}
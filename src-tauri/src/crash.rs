@@ -0,0 +1,162 @@
+//! Local crash reports written from a panic hook.
+//!
+//! Release builds use `panic = "abort"`, so the hook is the last code that runs
+//! after a panic. Reports stay in the app data directory; nothing is uploaded.
+
+use std::backtrace::Backtrace;
+use std::collections::VecDeque;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MAX_LIFECYCLE_EVENTS: usize = 25;
+const PENDING_MARKER: &str = "pending";
+
+static REPORT_DIR: OnceLock<PathBuf> = OnceLock::new();
+static LIFECYCLE_EVENTS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Installs the panic hook. Call once, before anything that could panic.
+pub fn install() {
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let report = build_report(info);
+        tracing::error!("Correctify panicked: {}", panic_message(info));
+        if let Some(dir) = REPORT_DIR.get() {
+            if let Err(e) = write_report(dir, &report) {
+                eprintln!("Failed to write crash report: {}", e);
+            }
+        }
+        previous_hook(info);
+    }));
+}
+
+/// Sets where reports are written. Panics before this only reach stderr.
+pub fn set_report_dir(dir: PathBuf) {
+    let _ = REPORT_DIR.set(dir);
+}
+
+/// Records an app lifecycle event for the next crash report. Never pass user text.
+pub fn record(event: &str) {
+    let Ok(mut events) = LIFECYCLE_EVENTS.lock() else {
+        return;
+    };
+    if events.len() == MAX_LIFECYCLE_EVENTS {
+        events.pop_front();
+    }
+    events.push_back(format!("{} {}", unix_seconds(), event));
+}
+
+/// Returns the report from the previous run, if it has not been offered yet.
+/// The report is offered once; the file itself is kept.
+pub fn take_pending(dir: &Path) -> Option<PathBuf> {
+    let marker = dir.join(PENDING_MARKER);
+    let report_name = fs::read_to_string(&marker).ok()?;
+    let _ = fs::remove_file(&marker);
+    let report = dir.join(report_name.trim());
+    report.is_file().then_some(report)
+}
+
+fn build_report(info: &PanicHookInfo) -> String {
+    let location = info
+        .location()
+        .map(|location| format!("{}:{}", location.file(), location.line()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let thread = std::thread::current().name().unwrap_or("unnamed").to_string();
+    let events = LIFECYCLE_EVENTS
+        .try_lock()
+        .map(|events| events.iter().cloned().collect::<Vec<_>>().join("\n"))
+        .unwrap_or_else(|_| "(unavailable)".to_string());
+
+    format!(
+        "Correctify crash report\n\
+         Version: {}\n\
+         OS: {} {} ({})\n\
+         Time (unix): {}\n\
+         Thread: {}\n\
+         Message: {}\n\
+         Location: {}\n\n\
+         Backtrace:\n{}\n\n\
+         Recent events:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        tauri_plugin_os::platform(),
+        tauri_plugin_os::version(),
+        tauri_plugin_os::arch(),
+        unix_seconds(),
+        thread,
+        panic_message(info),
+        location,
+        Backtrace::force_capture(),
+        events,
+    )
+}
+
+fn write_report(dir: &Path, report: &str) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let name = format!("crash-{}.txt", unix_seconds());
+    fs::write(dir.join(&name), crate::logging::redact(report))?;
+    fs::write(dir.join(PENDING_MARKER), name)
+}
+
+fn panic_message(info: &PanicHookInfo) -> String {
+    if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "non-string panic payload".to_string()
+    }
+}
+
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn writes_a_redacted_report_and_offers_it_once() {
+        let data = testing::data_dir();
+        let dir = data.path().join("crashes");
+        let before = unix_seconds();
+        write_report(&dir, "Message: request failed for sk-proj-Ab3dEf6hIj9kLm2nOp5qRs8tUv\n").unwrap();
+
+        let report = take_pending(&dir).unwrap();
+        assert_eq!(report.parent(), Some(dir.as_path()));
+        let name = report.file_name().unwrap().to_str().unwrap();
+        let seconds: u64 = name.strip_prefix("crash-").and_then(|name| name.strip_suffix(".txt")).unwrap().parse().unwrap();
+        assert!((before..=unix_seconds()).contains(&seconds));
+        assert_eq!(fs::read_to_string(&report).unwrap(), "Message: request failed for [REDACTED]\n");
+
+        // Offered once, but kept on disk
+        assert!(take_pending(&dir).is_none());
+        assert!(report.is_file());
+    }
+
+    #[test]
+    fn no_report_is_pending_without_a_crash() {
+        let data = testing::data_dir();
+        assert!(take_pending(data.path()).is_none());
+        // A marker naming a report that is gone offers nothing
+        fs::write(data.path().join(PENDING_MARKER), "crash-1.txt").unwrap();
+        assert!(take_pending(data.path()).is_none());
+    }
+
+    #[test]
+    fn keeps_only_the_latest_lifecycle_events() {
+        for index in 0..MAX_LIFECYCLE_EVENTS + 5 {
+            record(&format!("event {}", index));
+        }
+        let events = LIFECYCLE_EVENTS.lock().unwrap();
+        assert_eq!(events.len(), MAX_LIFECYCLE_EVENTS);
+        assert!(events.front().unwrap().ends_with(" event 5"));
+        assert!(events.back().unwrap().ends_with(&format!(" event {}", MAX_LIFECYCLE_EVENTS + 4)));
+    }
+}
@@ -0,0 +1,650 @@
+//! Credential storage backends.
+//!
+//! The OS credential vault is preferred. When it cannot be reached (no Secret
+//! Service on minimal Linux desktops, a denied or broken vault), credentials go
+//! to a file in the app data directory instead, encrypted with a key derived
+//! from a user passphrase: Argon2id for the key, XChaCha20-Poly1305 for the data.
+
+use crate::error::AppError;
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const KEYRING_SERVICE: &str = "com.correctify";
+// Looked up, never written, to learn whether the vault answers at all
+const KEYRING_PROBE_RECORD: &str = "correctify_probe";
+const VAULT_FILE: &str = "credentials.vault";
+const PREFERENCE_FILE: &str = "credential-backend";
+const VAULT_VERSION: u32 = 1;
+const SALT_LENGTH: usize = 16;
+const MIN_PASSPHRASE_LENGTH: usize = 8;
+
+/// Records moved when migrating away from the OS vault, which cannot list its
+/// entries. The encrypted file migrates everything it holds.
+const KNOWN_RECORDS: [&str; 1] = [crate::api_keys::API_KEYS_RECORD];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    Keyring,
+    EncryptedFile,
+}
+
+impl Backend {
+    fn as_str(self) -> &'static str {
+        match self {
+            Backend::Keyring => "keyring",
+            Backend::EncryptedFile => "encrypted-file",
+        }
+    }
+}
+
+/// A place credentials can be kept. `get` fails with [`AppError::NotFound`]
+/// for a record that was never written.
+pub trait CredentialStore: Send {
+    fn get(&self, key: &str) -> Result<String, AppError>;
+    fn set(&mut self, key: &str, value: &str) -> Result<(), AppError>;
+    fn remove(&mut self, key: &str) -> Result<(), AppError>;
+}
+
+/// The OS credential vault: Keychain, Windows Credential Manager or Secret Service.
+pub struct KeyringStore;
+
+impl KeyringStore {
+    pub fn entry(key: &str) -> Result<Entry, AppError> {
+        Entry::new(KEYRING_SERVICE, key)
+            .map_err(|e| AppError::KeyringUnavailable(format!("Could not access the system credential vault: {}", e)))
+    }
+
+    /// Succeeds when the vault answers a lookup, even with "no such entry".
+    pub fn probe() -> Result<(), AppError> {
+        match Self::entry(KEYRING_PROBE_RECORD)?.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(AppError::keyring(KEYRING_PROBE_RECORD, e)),
+        }
+    }
+}
+
+impl CredentialStore for KeyringStore {
+    fn get(&self, key: &str) -> Result<String, AppError> {
+        Self::entry(key)?.get_password().map_err(|e| AppError::keyring(key, e))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+        Self::entry(key)?.set_password(value).map_err(|e| AppError::keyring(key, e))
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), AppError> {
+        Self::entry(key)?.delete_credential().map_err(|e| AppError::keyring(key, e))
+    }
+}
+
+/// Stands in for the OS vault in portable mode, which must leave it untouched.
+struct NoVault;
+
+impl NoVault {
+    fn unavailable() -> AppError {
+        AppError::KeyringUnavailable("Portable copies do not use the system credential vault".to_string())
+    }
+}
+
+impl CredentialStore for NoVault {
+    fn get(&self, _key: &str) -> Result<String, AppError> {
+        Err(Self::unavailable())
+    }
+
+    fn set(&mut self, _key: &str, _value: &str) -> Result<(), AppError> {
+        Err(Self::unavailable())
+    }
+
+    fn remove(&mut self, _key: &str) -> Result<(), AppError> {
+        Err(Self::unavailable())
+    }
+}
+
+/// On-disk layout of the encrypted file. Only the ciphertext is secret.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vault {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// A key derived from a passphrase and the salt it was derived with.
+type DerivedKey = ([u8; 32], [u8; SALT_LENGTH]);
+
+impl Vault {
+    // Encrypts under an already derived key with a fresh nonce
+    fn seal(key: &[u8; 32], salt: &[u8; SALT_LENGTH], kdf: KdfParams, plaintext: &[u8]) -> Result<Vault, AppError> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = XChaCha20Poly1305::new(&(*key).into())
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| AppError::StorageFailed("Failed to encrypt credentials".to_string()))?;
+        Ok(Vault {
+            version: VAULT_VERSION,
+            kdf,
+            salt: general_purpose::STANDARD.encode(salt),
+            nonce: general_purpose::STANDARD.encode(nonce),
+            ciphertext: general_purpose::STANDARD.encode(ciphertext),
+        })
+    }
+
+    // Returns the derived key and salt with the plaintext, so a caller can re-seal without deriving again
+    fn open(&self, passphrase: &str) -> Result<(DerivedKey, Vec<u8>), AppError> {
+        if self.version != VAULT_VERSION {
+            return Err(AppError::StorageFailed(format!("Unsupported encrypted file version {}", self.version)));
+        }
+        let salt: [u8; SALT_LENGTH] = decode(&self.salt)?
+            .try_into()
+            .map_err(|_| AppError::StorageFailed("Encrypted file has an invalid salt".to_string()))?;
+        let nonce = decode(&self.nonce)?;
+        if nonce.len() != 24 {
+            return Err(AppError::StorageFailed("Encrypted file has an invalid nonce".to_string()));
+        }
+        let key = derive_key(passphrase, &salt, self.kdf)?;
+        let plaintext = XChaCha20Poly1305::new(&key.into())
+            .decrypt(XNonce::from_slice(&nonce), decode(&self.ciphertext)?.as_slice())
+            .map_err(|_| AppError::WrongPassphrase("The file could not be decrypted with this passphrase".to_string()))?;
+        Ok(((key, salt), plaintext))
+    }
+}
+
+/// Encrypts `plaintext` in the credential file layout under a fresh salt, for
+/// files that leave the app data directory such as backups.
+pub fn encrypt_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Result<String, AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(AppError::InvalidArgument(format!(
+            "The passphrase must be at least {} characters long",
+            MIN_PASSPHRASE_LENGTH
+        )));
+    }
+    let mut salt = [0u8; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = KdfParams::default();
+    let key = derive_key(passphrase, &salt, kdf)?;
+    serde_json::to_string_pretty(&Vault::seal(&key, &salt, kdf, plaintext)?)
+        .map_err(|e| AppError::StorageFailed(format!("Failed to encode encrypted file: {}", e)))
+}
+
+/// Reverses [`encrypt_with_passphrase`].
+pub fn decrypt_with_passphrase(contents: &str, passphrase: &str) -> Result<Vec<u8>, AppError> {
+    let vault: Vault = serde_json::from_str(contents)
+        .map_err(|e| AppError::InvalidArgument(format!("Not an encrypted Correctify file: {}", e)))?;
+    vault.open(passphrase).map(|(_, plaintext)| plaintext)
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KdfParams {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: Params::DEFAULT_M_COST,
+            iterations: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+struct UnlockedVault {
+    key: [u8; 32],
+    salt: [u8; SALT_LENGTH],
+    kdf: KdfParams,
+    records: BTreeMap<String, String>,
+}
+
+/// A passphrase-protected file. Starts locked; nothing can be read or written
+/// until [`EncryptedFileStore::unlock`] succeeds.
+pub struct EncryptedFileStore {
+    path: PathBuf,
+    unlocked: Option<UnlockedVault>,
+}
+
+impl EncryptedFileStore {
+    pub fn new(path: PathBuf) -> Self {
+        EncryptedFileStore { path, unlocked: None }
+    }
+
+    pub fn exists(&self) -> bool {
+        self.path.is_file()
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.unlocked.is_none()
+    }
+
+    /// Opens the file with `passphrase`, or creates an empty one protected by
+    /// it when no file exists yet.
+    pub fn unlock(&mut self, passphrase: &str) -> Result<(), AppError> {
+        if !self.exists() {
+            if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+                return Err(AppError::InvalidArgument(format!(
+                    "The passphrase must be at least {} characters long",
+                    MIN_PASSPHRASE_LENGTH
+                )));
+            }
+            let mut salt = [0u8; SALT_LENGTH];
+            OsRng.fill_bytes(&mut salt);
+            let kdf = KdfParams::default();
+            let key = derive_key(passphrase, &salt, kdf)?;
+            self.unlocked = Some(UnlockedVault { key, salt, kdf, records: BTreeMap::new() });
+            return self.persist();
+        }
+
+        let contents = fs::read_to_string(&self.path)
+            .map_err(|e| AppError::StorageFailed(format!("Failed to read credential file: {}", e)))?;
+        let vault: Vault = serde_json::from_str(&contents)
+            .map_err(|e| AppError::StorageFailed(format!("Credential file is damaged: {}", e)))?;
+        let ((key, salt), plaintext) = vault.open(passphrase)?;
+        let records = serde_json::from_slice(&plaintext)
+            .map_err(|e| AppError::StorageFailed(format!("Credential file contents are damaged: {}", e)))?;
+        self.unlocked = Some(UnlockedVault { key, salt, kdf: vault.kdf, records });
+        Ok(())
+    }
+
+    fn vault(&self) -> Result<&UnlockedVault, AppError> {
+        self.unlocked
+            .as_ref()
+            .ok_or_else(|| AppError::CredentialsLocked("The encrypted credential file is locked".to_string()))
+    }
+
+    fn vault_mut(&mut self) -> Result<&mut UnlockedVault, AppError> {
+        self.unlocked
+            .as_mut()
+            .ok_or_else(|| AppError::CredentialsLocked("The encrypted credential file is locked".to_string()))
+    }
+
+    fn record_names(&self) -> Result<Vec<String>, AppError> {
+        Ok(self.vault()?.records.keys().cloned().collect())
+    }
+
+    // Re-encrypts every record under a fresh nonce and replaces the file atomically
+    fn persist(&self) -> Result<(), AppError> {
+        let vault = self.vault()?;
+        let plaintext = serde_json::to_vec(&vault.records)
+            .map_err(|e| AppError::StorageFailed(format!("Failed to encode credentials: {}", e)))?;
+        let contents = serde_json::to_string_pretty(&Vault::seal(&vault.key, &vault.salt, vault.kdf, &plaintext)?)
+            .map_err(|e| AppError::StorageFailed(format!("Failed to encode credential file: {}", e)))?;
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| AppError::StorageFailed(format!("Failed to create credential directory: {}", e)))?;
+        }
+        let temporary = self.path.with_extension("tmp");
+        write_private(&temporary, contents.as_bytes())
+            .map_err(|e| AppError::StorageFailed(format!("Failed to write credential file: {}", e)))?;
+        fs::rename(&temporary, &self.path)
+            .map_err(|e| AppError::StorageFailed(format!("Failed to replace credential file: {}", e)))
+    }
+}
+
+impl CredentialStore for EncryptedFileStore {
+    fn get(&self, key: &str) -> Result<String, AppError> {
+        self.vault()?
+            .records
+            .get(key)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound(format!("Credential '{}' is not stored", key)))
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+        self.vault_mut()?.records.insert(key.to_string(), value.to_string());
+        self.persist()
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), AppError> {
+        if self.vault_mut()?.records.remove(key).is_none() {
+            return Err(AppError::NotFound(format!("Credential '{}' is not stored", key)));
+        }
+        self.persist()
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], kdf: KdfParams) -> Result<[u8; 32], AppError> {
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| AppError::StorageFailed(format!("Invalid key derivation parameters: {}", e)))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::StorageFailed(format!("Key derivation failed: {}", e)))?;
+    Ok(key)
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, AppError> {
+    general_purpose::STANDARD
+        .decode(value)
+        .map_err(|e| AppError::StorageFailed(format!("Credential file is damaged: {}", e)))
+}
+
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    fs::write(path, contents)
+}
+
+// Copies every record to `destination` and reads each back, then saves the
+// backend choice, and only then deletes the source copies. A failure before
+// the choice is saved leaves the source complete and still in use.
+fn transfer(
+    source: &mut dyn CredentialStore,
+    destination: &mut dyn CredentialStore,
+    names: &[String],
+    save_choice: impl FnOnce() -> Result<(), AppError>,
+) -> Result<u32, AppError> {
+    let mut copied = Vec::new();
+    for name in names {
+        let value = match source.get(name) {
+            Ok(value) => value,
+            Err(AppError::NotFound(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        destination.set(name, &value)?;
+        if destination.get(name)? != value {
+            return Err(AppError::StorageFailed(format!("Credential '{}' did not verify after migration", name)));
+        }
+        copied.push(name);
+    }
+
+    save_choice()?;
+
+    for name in &copied {
+        match source.remove(name) {
+            Ok(()) | Err(AppError::NotFound(_)) => {}
+            Err(e) => tracing::warn!("Migrated credential '{}' but could not remove the old copy: {}", name, e),
+        }
+    }
+    Ok(copied.len() as u32)
+}
+
+/// What the settings screen shows about credential storage.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialStatus {
+    pub backend: Backend,
+    /// Why the OS vault was skipped, when the encrypted file was chosen automatically.
+    pub fallback_reason: Option<String>,
+    pub locked: bool,
+    pub vault_exists: bool,
+    pub keyring_available: bool,
+    /// Portable copies always use the encrypted file.
+    pub portable: bool,
+}
+
+struct Inner {
+    backend: Backend,
+    /// Set once the user picks a backend by migrating; disables automatic fallback.
+    preferred: Option<Backend>,
+    fallback_reason: Option<String>,
+    portable: bool,
+    keyring: KeyringStore,
+    file: EncryptedFileStore,
+}
+
+impl Inner {
+    fn active(&mut self) -> &mut dyn CredentialStore {
+        match self.backend {
+            Backend::Keyring => &mut self.keyring,
+            Backend::EncryptedFile => &mut self.file,
+        }
+    }
+
+    fn fall_back(&mut self, reason: String) {
+        tracing::warn!("System credential vault unavailable, using the encrypted file: {}", reason);
+        self.backend = Backend::EncryptedFile;
+        self.fallback_reason = Some(reason);
+    }
+}
+
+/// The credential store the app uses, chosen at startup.
+pub struct Credentials {
+    data_dir: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl Credentials {
+    /// Picks the backend: the encrypted file for portable copies, the user's
+    /// choice if they made one, otherwise the OS vault when it answers and the
+    /// encrypted file when it does not.
+    pub fn open(data_dir: PathBuf, portable: bool) -> Self {
+        let preferred = match fs::read_to_string(data_dir.join(PREFERENCE_FILE)).ok().as_deref().map(str::trim) {
+            _ if portable => Some(Backend::EncryptedFile),
+            Some("keyring") => Some(Backend::Keyring),
+            Some("encrypted-file") => Some(Backend::EncryptedFile),
+            _ => None,
+        };
+        let mut inner = Inner {
+            backend: preferred.unwrap_or(Backend::Keyring),
+            preferred,
+            fallback_reason: None,
+            portable,
+            keyring: KeyringStore,
+            file: EncryptedFileStore::new(data_dir.join(VAULT_FILE)),
+        };
+        if preferred.is_none() {
+            if let Err(e) = KeyringStore::probe() {
+                inner.fall_back(e.details().to_string());
+            }
+        }
+        tracing::info!(backend = inner.backend.as_str(), portable, "Credential storage ready");
+        Credentials { data_dir, inner: Mutex::new(inner) }
+    }
+
+    pub fn get(&self, key: &str) -> Result<String, AppError> {
+        self.run(|store| store.get(key))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), AppError> {
+        self.run(|store| store.set(key, value))
+    }
+
+    pub fn remove(&self, key: &str) -> Result<(), AppError> {
+        self.run(|store| store.remove(key))
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.inner.lock().unwrap().backend
+    }
+
+    pub fn status(&self) -> CredentialStatus {
+        let inner = self.inner.lock().unwrap();
+        CredentialStatus {
+            backend: inner.backend,
+            fallback_reason: inner.fallback_reason.clone(),
+            locked: inner.backend == Backend::EncryptedFile && inner.file.is_locked(),
+            vault_exists: inner.file.exists(),
+            keyring_available: !inner.portable && KeyringStore::probe().is_ok(),
+            portable: inner.portable,
+        }
+    }
+
+    pub fn unlock(&self, passphrase: &str) -> Result<(), AppError> {
+        self.inner.lock().unwrap().file.unlock(passphrase)
+    }
+
+    /// Moves every stored credential to `target` and makes it the active
+    /// backend. Source copies are deleted only once every record verified in
+    /// the target and the choice is saved, so an interrupted migration never
+    /// loses a key.
+    pub fn migrate(&self, target: Backend, passphrase: Option<&str>) -> Result<u32, AppError> {
+        let mut inner = self.inner.lock().unwrap();
+        if target == Backend::EncryptedFile && inner.file.is_locked() {
+            let passphrase = passphrase
+                .ok_or_else(|| AppError::CredentialsLocked("A passphrase is needed for the encrypted file".to_string()))?;
+            inner.file.unlock(passphrase)?;
+        }
+        if target == Backend::Keyring {
+            if inner.portable {
+                return Err(NoVault::unavailable());
+            }
+            KeyringStore::probe()?;
+        }
+
+        let save_choice = || {
+            fs::create_dir_all(&self.data_dir)
+                .and_then(|_| fs::write(self.data_dir.join(PREFERENCE_FILE), target.as_str()))
+                .map_err(|e| AppError::StorageFailed(format!("Failed to save credential backend choice: {}", e)))
+        };
+        let migrated = if target == inner.backend {
+            save_choice()?;
+            0
+        } else {
+            let Inner { keyring, file, backend, .. } = &mut *inner;
+            let (source, destination, names): (&mut dyn CredentialStore, &mut dyn CredentialStore, Vec<String>) =
+                match backend {
+                    Backend::Keyring => (keyring, file, KNOWN_RECORDS.iter().map(|name| name.to_string()).collect()),
+                    Backend::EncryptedFile => {
+                        let names = file.record_names()?;
+                        (file, keyring, names)
+                    }
+                };
+            transfer(source, destination, &names, save_choice)?
+        };
+        inner.backend = target;
+        inner.preferred = Some(target);
+        inner.fallback_reason = None;
+        tracing::info!(backend = target.as_str(), migrated, "Credential storage migrated");
+        Ok(migrated)
+    }
+
+    /// Runs `op` on the active store and the OS vault, where releases before
+    /// the consolidated API key record kept one entry per provider.
+    pub fn with_legacy<T>(&self, op: impl FnOnce(&mut dyn CredentialStore, &mut dyn CredentialStore) -> T) -> T {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.portable {
+            return op(inner.active(), &mut NoVault);
+        }
+        op(inner.active(), &mut KeyringStore)
+    }
+
+    // Runs `op` on the active store, switching to the encrypted file when the
+    // OS vault stops answering and the user has not pinned it
+    fn run<T>(&self, op: impl Fn(&mut dyn CredentialStore) -> Result<T, AppError>) -> Result<T, AppError> {
+        let mut inner = self.inner.lock().unwrap();
+        match op(inner.active()) {
+            Err(AppError::KeyringUnavailable(reason))
+                if inner.backend == Backend::Keyring && inner.preferred.is_none() =>
+            {
+                inner.fall_back(reason);
+                op(inner.active())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct FakeStore {
+        records: HashMap<String, String>,
+        /// Writes to this record fail, to interrupt a migration partway through.
+        failing: Option<String>,
+    }
+
+    impl CredentialStore for FakeStore {
+        fn get(&self, key: &str) -> Result<String, AppError> {
+            self.records.get(key).cloned().ok_or_else(|| AppError::NotFound(key.to_string()))
+        }
+
+        fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+            if self.failing.as_deref() == Some(key) {
+                return Err(AppError::StorageFailed(format!("refusing to write {}", key)));
+            }
+            self.records.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn remove(&mut self, key: &str) -> Result<(), AppError> {
+            self.records.remove(key).map(|_| ()).ok_or_else(|| AppError::NotFound(key.to_string()))
+        }
+    }
+
+    fn source() -> FakeStore {
+        let records = [("first", "one"), ("second", "two"), ("third", "three")];
+        FakeStore {
+            records: records.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            failing: None,
+        }
+    }
+
+    fn names() -> Vec<String> {
+        ["first", "second", "missing", "third"].iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn moves_every_record_after_saving_the_choice() {
+        let (mut source, mut destination) = (source(), FakeStore::default());
+        let mut saved = false;
+
+        let migrated = transfer(&mut source, &mut destination, &names(), || {
+            saved = true;
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(migrated, 3);
+        assert!(saved);
+        assert!(source.records.is_empty());
+        assert_eq!(destination.records["second"], "two");
+    }
+
+    #[test]
+    fn a_failed_copy_keeps_every_source_record() {
+        let mut source = source();
+        let mut destination = FakeStore { failing: Some("second".to_string()), ..FakeStore::default() };
+        let mut saved = false;
+
+        let result = transfer(&mut source, &mut destination, &names(), || {
+            saved = true;
+            Ok(())
+        });
+
+        assert!(matches!(result, Err(AppError::StorageFailed(_))));
+        assert!(!saved);
+        assert_eq!(source.records.len(), 3);
+    }
+
+    #[test]
+    fn a_failed_save_keeps_every_source_record() {
+        let (mut source, mut destination) = (source(), FakeStore::default());
+
+        let result = transfer(&mut source, &mut destination, &names(), || {
+            Err(AppError::StorageFailed("read-only".to_string()))
+        });
+
+        assert!(matches!(result, Err(AppError::StorageFailed(_))));
+        assert_eq!(source.records.len(), 3);
+        assert_eq!(destination.records.len(), 3);
+    }
+}
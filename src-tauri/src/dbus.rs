@@ -0,0 +1,294 @@
+//! Session-bus service on Linux.
+//!
+//! Correctify owns `com.correctify.Correctify` and serves the
+//! `com.correctify.Correctify1` interface at `/com/correctify/Correctify`, so
+//! desktop scripts, GNOME extensions and KDE widgets can drive it without
+//! faking keystrokes:
+//!
+//! ```sh
+//! gdbus call --session --dest com.correctify.Correctify \
+//!     --object-path /com/correctify/Correctify \
+//!     --method com.correctify.Correctify1.Correct "teh text" "formal"
+//! ```
+//!
+//! Failures come back as `com.correctify.Correctify1.Error.Failed` with the
+//! backend error code in front of the message.
+
+use crate::error::AppError;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use zbus::object_server::{InterfaceRef, SignalEmitter};
+use zbus::zvariant::{DeserializeDict, SerializeDict, Type};
+use zbus::{connection, interface, Connection};
+
+pub const BUS_NAME: &str = "com.correctify.Correctify";
+pub const OBJECT_PATH: &str = "/com/correctify/Correctify";
+
+/// Where a correction came from, as reported by the signals.
+pub const SOURCE_BUS: &str = "dbus";
+pub const SOURCE_CLIPBOARD: &str = "clipboard";
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// What the service needs from the app. A `None` style keeps the selected one.
+pub trait Backend: Send + Sync {
+    fn correct(&self, text: String, style: Option<String>) -> BoxFuture<'_, Result<String, AppError>>;
+    fn correct_clipboard(&self, style: Option<String>) -> Result<(), AppError>;
+    fn settings(&self) -> Settings;
+}
+
+/// The settings `GetSettings` returns, as an `a{sv}` dictionary.
+#[derive(Debug, Clone, PartialEq, SerializeDict, DeserializeDict, Type)]
+#[zvariant(signature = "a{sv}", rename_all = "camelCase")]
+pub struct Settings {
+    pub model: String,
+    pub style: String,
+    pub locale: String,
+    /// The shortcut as the platform writes it, such as `Ctrl+Shift+]`.
+    pub shortcut: String,
+    pub shortcut_registered: bool,
+    pub auto_paste_enabled: bool,
+    pub sound_enabled: bool,
+}
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "com.correctify.Correctify1.Error")]
+pub enum ServiceError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Failed(String),
+}
+
+impl From<AppError> for ServiceError {
+    fn from(error: AppError) -> Self {
+        ServiceError::Failed(format!("{}: {}", error.code(), error.details()))
+    }
+}
+
+fn requested_style(style: String) -> Option<String> {
+    (!style.is_empty()).then_some(style)
+}
+
+struct Service {
+    backend: Arc<dyn Backend>,
+}
+
+#[interface(name = "com.correctify.Correctify1")]
+impl Service {
+    /// Corrects `text` with the selected model and returns the result.
+    async fn correct(
+        &self,
+        text: String,
+        style: String,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> Result<String, ServiceError> {
+        Self::correction_started(&emitter, SOURCE_BUS).await?;
+        let result = self.backend.correct(text, requested_style(style)).await;
+        Self::correction_finished(&emitter, SOURCE_BUS, result.is_ok()).await?;
+        Ok(result?)
+    }
+
+    /// Corrects the clipboard as the global shortcut does. Returns once the
+    /// correction has started; `CorrectionFinished` reports the outcome.
+    async fn correct_clipboard(&self, style: String) -> Result<(), ServiceError> {
+        Ok(self.backend.correct_clipboard(requested_style(style))?)
+    }
+
+    async fn get_settings(&self) -> Settings {
+        self.backend.settings()
+    }
+
+    #[zbus(signal)]
+    async fn correction_started(emitter: &SignalEmitter<'_>, source: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn correction_finished(emitter: &SignalEmitter<'_>, source: &str, success: bool) -> zbus::Result<()>;
+}
+
+/// The running service. Dropping it releases the bus name.
+pub struct Bus {
+    connection: Connection,
+}
+
+/// Owns the bus name and serves the interface on the connection `builder` opens.
+pub async fn serve(builder: connection::Builder<'_>, backend: Arc<dyn Backend>) -> zbus::Result<Bus> {
+    let connection = builder.name(BUS_NAME)?.serve_at(OBJECT_PATH, Service { backend })?.build().await?;
+    Ok(Bus { connection })
+}
+
+impl Bus {
+    /// Signals a correction that did not start from the bus.
+    pub async fn correction_started(&self, source: &str) {
+        let result = match self.interface().await {
+            Ok(interface) => Service::correction_started(interface.signal_emitter(), source).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to signal a started correction: {}", e);
+        }
+    }
+
+    pub async fn correction_finished(&self, source: &str, success: bool) {
+        let result = match self.interface().await {
+            Ok(interface) => Service::correction_finished(interface.signal_emitter(), source, success).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to signal a finished correction: {}", e);
+        }
+    }
+
+    async fn interface(&self) -> zbus::Result<InterfaceRef<Service>> {
+        self.connection.object_server().interface::<_, Service>(OBJECT_PATH).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Mutex;
+
+    #[zbus::proxy(
+        interface = "com.correctify.Correctify1",
+        default_service = "com.correctify.Correctify",
+        default_path = "/com/correctify/Correctify"
+    )]
+    trait Correctify {
+        fn correct(&self, text: &str, style: &str) -> zbus::Result<String>;
+        fn correct_clipboard(&self, style: &str) -> zbus::Result<()>;
+        fn get_settings(&self) -> zbus::Result<Settings>;
+        #[zbus(signal)]
+        fn correction_started(&self, source: &str) -> zbus::Result<()>;
+        #[zbus(signal)]
+        fn correction_finished(&self, source: &str, success: bool) -> zbus::Result<()>;
+    }
+
+    // A dbus-daemon of its own for each test, stopped on drop
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<PrivateBus> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            let address = address.trim().to_string();
+            let bus = PrivateBus { daemon, address };
+            (!bus.address.is_empty()).then_some(bus)
+        }
+
+        fn connect(&self) -> connection::Builder<'static> {
+            connection::Builder::address(self.address.as_str()).unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeBackend {
+        clipboard_styles: Mutex<Vec<Option<String>>>,
+    }
+
+    impl Backend for FakeBackend {
+        fn correct(&self, text: String, style: Option<String>) -> BoxFuture<'_, Result<String, AppError>> {
+            Box::pin(async move {
+                match style.as_deref() {
+                    Some("shouty") => Err(AppError::InvalidArgument("Unknown writing style 'shouty'".to_string())),
+                    _ => Ok(text.replace("teh", "the")),
+                }
+            })
+        }
+
+        fn correct_clipboard(&self, style: Option<String>) -> Result<(), AppError> {
+            self.clipboard_styles.lock().unwrap().push(style);
+            Ok(())
+        }
+
+        fn settings(&self) -> Settings {
+            Settings {
+                model: "gpt-5.4-mini".to_string(),
+                style: "grammar".to_string(),
+                locale: "en".to_string(),
+                shortcut: "Ctrl+Shift+]".to_string(),
+                shortcut_registered: true,
+                auto_paste_enabled: false,
+                sound_enabled: true,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn corrects_text_and_signals_start_and_finish() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let _service = serve(bus.connect(), Arc::new(FakeBackend::default())).await.unwrap();
+        let client = bus.connect().build().await.unwrap();
+        let proxy = CorrectifyProxy::new(&client).await.unwrap();
+        let mut started = proxy.receive_correction_started().await.unwrap();
+        let mut finished = proxy.receive_correction_finished().await.unwrap();
+
+        assert_eq!(proxy.correct("teh text", "").await.unwrap(), "the text");
+        assert_eq!(started.next().await.unwrap().args().unwrap().source, SOURCE_BUS);
+        let args = finished.next().await.unwrap();
+        assert!(args.args().unwrap().success);
+    }
+
+    #[tokio::test]
+    async fn failures_carry_the_error_code() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let _service = serve(bus.connect(), Arc::new(FakeBackend::default())).await.unwrap();
+        let client = bus.connect().build().await.unwrap();
+        let proxy = CorrectifyProxy::new(&client).await.unwrap();
+        let mut finished = proxy.receive_correction_finished().await.unwrap();
+
+        let error = proxy.correct("teh text", "shouty").await.unwrap_err();
+        let zbus::Error::MethodError(name, Some(message), _) = error else {
+            panic!("unexpected error: {:?}", error);
+        };
+        assert_eq!(name.as_str(), "com.correctify.Correctify1.Error.Failed");
+        assert!(message.starts_with("invalid-argument: "));
+        assert!(!finished.next().await.unwrap().args().unwrap().success);
+    }
+
+    #[tokio::test]
+    async fn clipboard_corrections_and_settings_reach_the_backend() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not installed; skipping");
+            return;
+        };
+        let backend = Arc::new(FakeBackend::default());
+        let service = serve(bus.connect(), backend.clone()).await.unwrap();
+        let client = bus.connect().build().await.unwrap();
+        let proxy = CorrectifyProxy::new(&client).await.unwrap();
+        let mut started = proxy.receive_correction_started().await.unwrap();
+
+        proxy.correct_clipboard("formal").await.unwrap();
+        proxy.correct_clipboard("").await.unwrap();
+        assert_eq!(*backend.clipboard_styles.lock().unwrap(), vec![Some("formal".to_string()), None]);
+        assert_eq!(proxy.get_settings().await.unwrap(), backend.settings());
+
+        service.correction_started(SOURCE_CLIPBOARD).await;
+        assert_eq!(started.next().await.unwrap().args().unwrap().source, SOURCE_CLIPBOARD);
+    }
+}
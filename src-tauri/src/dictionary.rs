@@ -0,0 +1,335 @@
+//! Personal dictionary.
+//!
+//! Protected terms (product names, acronyms, people's names) must come back
+//! exactly as they were, and preferred spellings say how to write a word the
+//! user spells one way only. The terms found in a text are listed in the
+//! system prompt, and after the correction every edit that touches one of
+//! them is reverted, whatever the model did.
+
+use crate::chunks;
+use crate::error::AppError;
+use crate::review::{self, ReviewEdit};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const DICTIONARY_FILE: &str = "personal-dictionary.json";
+const MAX_TERMS: usize = 2_000;
+const MAX_TERM_CHARACTERS: usize = 100;
+
+/// Write `preferred` wherever the text has `variant`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Spelling {
+    pub variant: String,
+    pub preferred: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Terms {
+    pub protected: Vec<String>,
+    pub spellings: Vec<Spelling>,
+}
+
+impl Terms {
+    /// Reads a plain word list: one term per line, `variant -> preferred` for a
+    /// preferred spelling. Blank lines and lines starting with `#` are skipped.
+    pub fn parse(list: &str) -> Terms {
+        let mut terms = Terms::default();
+        for line in list.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            match line.split_once("->").or_else(|| line.split_once('→')) {
+                Some((variant, preferred)) => terms
+                    .spellings
+                    .push(Spelling { variant: variant.trim().to_string(), preferred: preferred.trim().to_string() }),
+                None => terms.protected.push(line.to_string()),
+            }
+        }
+        terms
+    }
+
+    /// The dictionary rules for the system prompt, naming only the terms that
+    /// occur in `text`. None when there are none.
+    pub fn prompt_rules(&self, text: &str) -> Option<String> {
+        let lowercase = text.to_lowercase();
+        let mentioned = |term: &str| lowercase.contains(&term.to_lowercase());
+        let protected: Vec<String> =
+            self.protected.iter().filter(|term| mentioned(term)).map(|term| format!("\"{}\"", term)).collect();
+        let spellings: Vec<String> = self
+            .spellings
+            .iter()
+            .filter(|spelling| mentioned(&spelling.variant) || mentioned(&spelling.preferred))
+            .map(|spelling| format!("write \"{}\", not \"{}\"", spelling.preferred, spelling.variant))
+            .collect();
+        if protected.is_empty() && spellings.is_empty() {
+            return None;
+        }
+        let mut rules = String::from("=== PERSONAL DICTIONARY ===");
+        if !protected.is_empty() {
+            rules.push_str(&format!(
+                "\nThese terms are correct as written. Never change their spelling, capitalization or punctuation: {}.",
+                protected.join(", ")
+            ));
+        }
+        if !spellings.is_empty() {
+            rules.push_str(&format!("\nUse the preferred spellings: {}.", spellings.join("; ")));
+        }
+        rules.push_str("\n=== END OF PERSONAL DICTIONARY ===");
+        Some(rules)
+    }
+
+    /// `corrected` with every edit that touches a protected term or a preferred
+    /// spelling of `original` undone.
+    pub fn enforce(&self, original: &str, corrected: &str) -> String {
+        let guarded: Vec<&str> = self
+            .protected
+            .iter()
+            .map(String::as_str)
+            .chain(self.spellings.iter().map(|spelling| spelling.preferred.as_str()))
+            .filter(|term| !term.is_empty())
+            .collect();
+        if guarded.is_empty() {
+            return corrected.to_string();
+        }
+
+        // Paragraph by paragraph keeps each diff small; merged or split paragraphs are compared whole
+        let before = chunks::paragraphs(original);
+        let after = chunks::paragraphs(corrected);
+        if before.len() != after.len() {
+            return restore(&guarded, original, corrected);
+        }
+        let mut text = String::with_capacity(corrected.len());
+        let mut position = 0;
+        for (a, b) in before.iter().zip(&after) {
+            text.push_str(&corrected[position..b.start]);
+            text.push_str(&restore(&guarded, &original[a.start..a.end], &corrected[b.start..b.end]));
+            position = b.end;
+        }
+        text.push_str(&corrected[position..]);
+        text
+    }
+
+    fn normalized(self) -> Result<Terms, AppError> {
+        let mut terms = Terms::default();
+        for term in self.protected.iter().map(|term| term.trim()).filter(|term| !term.is_empty()) {
+            if !terms.protected.iter().any(|known| known == term) {
+                terms.protected.push(term.to_string());
+            }
+        }
+        for spelling in self.spellings {
+            let (variant, preferred) = (spelling.variant.trim(), spelling.preferred.trim());
+            if variant.is_empty() || preferred.is_empty() || variant == preferred {
+                continue;
+            }
+            terms.spellings.retain(|known| known.variant != variant);
+            terms.spellings.push(Spelling { variant: variant.to_string(), preferred: preferred.to_string() });
+        }
+
+        if terms.protected.len() + terms.spellings.len() > MAX_TERMS {
+            return Err(AppError::InvalidArgument(format!("The dictionary can hold at most {} terms.", MAX_TERMS)));
+        }
+        let too_long = terms
+            .protected
+            .iter()
+            .chain(terms.spellings.iter().flat_map(|spelling| [&spelling.variant, &spelling.preferred]))
+            .find(|term| term.chars().count() > MAX_TERM_CHARACTERS);
+        if let Some(term) = too_long {
+            return Err(AppError::InvalidArgument(format!(
+                "'{}' is too long for the dictionary. Limit terms to {} characters.",
+                term, MAX_TERM_CHARACTERS
+            )));
+        }
+        Ok(terms)
+    }
+}
+
+// Undoes the edits of one paragraph that overlap a guarded term in the original
+fn restore(guarded: &[&str], original: &str, corrected: &str) -> String {
+    let spans: Vec<(usize, usize)> = guarded.iter().flat_map(|term| occurrences(original, term)).collect();
+    if spans.is_empty() {
+        return corrected.to_string();
+    }
+    let edits: Vec<ReviewEdit> = review::edits(original, corrected)
+        .into_iter()
+        .filter(|edit| !spans.iter().any(|&(start, end)| touches(edit, start, end)))
+        .collect();
+    review::apply(original, &edits)
+}
+
+// An insertion touches a term only from inside it
+fn touches(edit: &ReviewEdit, start: usize, end: usize) -> bool {
+    if edit.start == edit.end {
+        return start < edit.start && edit.start < end;
+    }
+    edit.start < end && start < edit.end
+}
+
+// Whole-word, case-sensitive occurrences of `term`, as byte ranges
+fn occurrences<'a>(text: &'a str, term: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    text.match_indices(term).map(|(start, _)| (start, start + term.len())).filter(move |&(start, end)| {
+        let before = text[..start].chars().next_back();
+        let after = text[end..].chars().next();
+        // A term that starts or ends with a word character must not continue a word there
+        let joins_before = word(term.chars().next()) && word(before);
+        let joins_after = word(term.chars().next_back()) && word(after);
+        !joins_before && !joins_after
+    })
+}
+
+/// The dictionary, kept in the data directory.
+pub struct Dictionary {
+    path: PathBuf,
+    terms: Mutex<Arc<Terms>>,
+}
+
+impl Dictionary {
+    /// Opens the dictionary in `data_dir`, starting empty when it is missing or unreadable.
+    pub fn open(data_dir: &Path) -> Self {
+        let path = data_dir.join(DICTIONARY_FILE);
+        let terms: Terms = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Dictionary { path, terms: Mutex::new(Arc::new(terms)) }
+    }
+
+    pub fn terms(&self) -> Arc<Terms> {
+        self.terms.lock().unwrap().clone()
+    }
+
+    /// Replaces the dictionary, dropping blank and repeated terms.
+    pub fn set(&self, terms: Terms) -> Result<Terms, AppError> {
+        let terms = terms.normalized()?;
+        let mut current = self.terms.lock().unwrap();
+        let contents = serde_json::to_string_pretty(&terms).map_err(|e| AppError::StorageFailed(e.to_string()))?;
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir).map_err(|e| AppError::StorageFailed(format!("Failed to save the dictionary: {}", e)))?;
+        }
+        fs::write(&self.path, contents).map_err(|e| AppError::StorageFailed(format!("Failed to save the dictionary: {}", e)))?;
+        *current = Arc::new(terms.clone());
+        Ok(terms)
+    }
+
+    /// Adds the terms of a plain word list file to the dictionary.
+    pub fn import(&self, path: &Path) -> Result<Terms, AppError> {
+        let list = fs::read_to_string(path)
+            .map_err(|e| AppError::InvalidArgument(format!("Failed to read {}: {}", path.display(), e)))?;
+        let imported = Terms::parse(&list);
+        let mut terms = (*self.terms()).clone();
+        terms.protected.extend(imported.protected);
+        terms.spellings.extend(imported.spellings);
+        self.set(terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(protected: &[&str], spellings: &[(&str, &str)]) -> Terms {
+        Terms {
+            protected: protected.iter().map(|term| term.to_string()).collect(),
+            spellings: spellings
+                .iter()
+                .map(|(variant, preferred)| Spelling { variant: variant.to_string(), preferred: preferred.to_string() })
+                .collect(),
+        }
+    }
+
+    fn found(text: &str, term: &str) -> Vec<String> {
+        occurrences(text, term).map(|(start, end)| format!("{}..{}", start, end)).collect()
+    }
+
+    #[test]
+    fn reverts_a_rewritten_protected_term() {
+        let terms = terms(&["iPhone"], &[("e-mail", "email")]);
+        assert_eq!(terms.enforce("My iPhone is grate.", "My IPhone is great."), "My iPhone is great.");
+        assert_eq!(terms.enforce("Send an email.", "Send an e-mail."), "Send an email.");
+        // The count is unchanged, but the term the user wrote was still rewritten
+        assert_eq!(
+            terms.enforce("Use iPhone here and iphone there.", "Use IPhone here and iPhone there."),
+            "Use iPhone here and iPhone there."
+        );
+        assert_eq!(terms.enforce("Nothing to protect.", "Nothing to protect!"), "Nothing to protect!");
+    }
+
+    #[test]
+    fn insertions_count_only_inside_a_term() {
+        let terms = terms(&["Acme Corp"], &[]);
+        assert_eq!(terms.enforce("Acme Corp rocks.", "The Acme Corp really rocks."), "The Acme Corp really rocks.");
+        assert_eq!(terms.enforce("Acme Corp rocks.", "Acme Big Corp rocks."), "Acme Corp rocks.");
+        assert_eq!(terms.enforce("Acme Corp rocks.", "Acme, Corp rocks."), "Acme Corp rocks.");
+    }
+
+    #[test]
+    fn matches_whole_words_only() {
+        assert_eq!(found("Zürichsee and Zürich.", "Zürich"), ["15..22"]);
+        assert_eq!(found("Grüße, grüße", "grüße"), ["9..16"]);
+        assert_eq!(found("Node.jsx or Node.js", "Node.js"), ["12..19"]);
+        // A term that ends in punctuation may be followed by anything
+        assert_eq!(found("C++ and C++11", "C++"), ["0..3", "8..11"]);
+        assert_eq!(found("ASP.NET", ".NET"), ["3..7"]);
+        assert!(found("MyAcme acme", "Acme").is_empty());
+
+        let terms = terms(&["Zürich"], &[]);
+        assert_eq!(terms.enforce("Zürichsee in Zürich.", "Zürichsees in Zurich."), "Zürichsees in Zürich.");
+    }
+
+    #[test]
+    fn merged_paragraphs_are_restored_as_a_whole() {
+        let terms = terms(&["Acme"], &[]);
+        let original = "Acme rocks.\n\nA second paragraph about Acme.";
+        assert_eq!(
+            terms.enforce(original, "ACME rocks. A second paragraph about ACME."),
+            "Acme rocks. A second paragraph about Acme."
+        );
+        // Paragraph by paragraph, the separators stay as corrected
+        assert_eq!(
+            terms.enforce("Acme teh.\r\n\r\nAnd acme.", "ACME the.\n\nAnd Acme."),
+            "Acme the.\n\nAnd Acme."
+        );
+    }
+
+    #[test]
+    fn parses_a_word_list() {
+        let parsed = Terms::parse("# Names\n  Acme  \n\ne-mail -> email\ncolour→color\n");
+        assert_eq!(parsed, terms(&["Acme"], &[("e-mail", "email"), ("colour", "color")]));
+    }
+
+    #[test]
+    fn prompt_rules_name_only_mentioned_terms() {
+        let terms = terms(&["Acme", "Globex"], &[("e-mail", "email")]);
+        let rules = terms.prompt_rules("Ask acme by E-mail").unwrap();
+        assert!(rules.contains("\"Acme\""));
+        assert!(!rules.contains("Globex"));
+        assert!(rules.contains("write \"email\", not \"e-mail\""));
+        assert!(terms.prompt_rules("Nothing here").is_none());
+    }
+
+    #[test]
+    fn normalizing_drops_blank_and_repeated_terms() {
+        let normalized = terms(
+            &[" Acme ", "", "Acme", "Globex"],
+            &[("e-mail", "email"), (" ", "x"), ("same", "same"), ("e-mail", "E-Mail")],
+        )
+        .normalized()
+        .unwrap();
+        assert_eq!(normalized, terms(&["Acme", "Globex"], &[("e-mail", "E-Mail")]));
+    }
+
+    #[test]
+    fn normalizing_enforces_the_limits() {
+        let longest = "x".repeat(MAX_TERM_CHARACTERS);
+        assert!(terms(&[longest.as_str()], &[]).normalized().is_ok());
+        let too_long = "ü".repeat(MAX_TERM_CHARACTERS + 1);
+        assert!(matches!(terms(&[], &[("a", too_long.as_str())]).normalized(), Err(AppError::InvalidArgument(_))));
+
+        let many: Vec<String> = (0..MAX_TERMS).map(|index| format!("term{}", index)).collect();
+        let mut full = Terms { protected: many, spellings: Vec::new() };
+        assert!(full.clone().normalized().is_ok());
+        full.spellings.push(Spelling { variant: "one".to_string(), preferred: "more".to_string() });
+        assert!(matches!(full.normalized(), Err(AppError::InvalidArgument(_))));
+    }
+}
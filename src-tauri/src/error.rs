@@ -0,0 +1,140 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+/// Error returned by every Tauri command.
+///
+/// The frontend receives `{ code, messageKey, details }`. `code` is stable and
+/// safe to branch on, `messageKey` points into the locale files, and `details`
+/// carries the untranslated cause for logs and bug reports.
+#[derive(Debug)]
+pub enum AppError {
+    /// The OS credential vault cannot be reached (locked, missing, or denied).
+    KeyringUnavailable(String),
+    NotFound(String),
+    InvalidShortcut(String),
+    /// The shortcut parsed, but another application (or the OS) owns it.
+    ShortcutUnavailable(String),
+    /// The shortcut is held back for the OS or for common editing commands.
+    ShortcutReserved(String),
+    ClipboardFailed(String),
+    /// The encrypted credential file needs its passphrase first.
+    CredentialsLocked(String),
+    WrongPassphrase(String),
+    InvalidArgument(String),
+    StorageFailed(String),
+    MissingApiKey(String),
+    /// The provider refused the API key.
+    ProviderAuthentication(String),
+    /// The provider refused the request itself (bad model, too much text).
+    ProviderRejected(String),
+    /// Rate limited or out of quota.
+    ProviderCapacity(String),
+    /// Timeouts, network failures, server errors and unusable responses.
+    ProviderUnavailable(String),
+    CorrectionFailed(String),
+    /// The administrator policy does not allow this.
+    BlockedByPolicy(String),
+    /// The user stopped the operation before it finished.
+    Cancelled(String),
+}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::KeyringUnavailable(_) => "keyring-unavailable",
+            AppError::NotFound(_) => "not-found",
+            AppError::InvalidShortcut(_) => "invalid-shortcut",
+            AppError::ShortcutUnavailable(_) => "shortcut-unavailable",
+            AppError::ShortcutReserved(_) => "shortcut-reserved",
+            AppError::ClipboardFailed(_) => "clipboard-failed",
+            AppError::CredentialsLocked(_) => "credentials-locked",
+            AppError::WrongPassphrase(_) => "wrong-passphrase",
+            AppError::InvalidArgument(_) => "invalid-argument",
+            AppError::StorageFailed(_) => "storage-failed",
+            AppError::MissingApiKey(_) => "missing-api-key",
+            AppError::ProviderAuthentication(_) => "provider-authentication",
+            AppError::ProviderRejected(_) => "provider-rejected",
+            AppError::ProviderCapacity(_) => "provider-capacity",
+            AppError::ProviderUnavailable(_) => "provider-unavailable",
+            AppError::CorrectionFailed(_) => "correction-failed",
+            AppError::BlockedByPolicy(_) => "blocked-by-policy",
+            AppError::Cancelled(_) => "cancelled",
+        }
+    }
+
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            AppError::KeyringUnavailable(_) => "errors.keyringUnavailable",
+            AppError::NotFound(_) => "errors.notFound",
+            AppError::InvalidShortcut(_) => "errors.invalidShortcut",
+            AppError::ShortcutUnavailable(_) => "errors.shortcutUnavailable",
+            AppError::ShortcutReserved(_) => "errors.shortcutReserved",
+            AppError::ClipboardFailed(_) => "errors.clipboardFailed",
+            AppError::CredentialsLocked(_) => "errors.credentialsLocked",
+            AppError::WrongPassphrase(_) => "errors.wrongPassphrase",
+            AppError::InvalidArgument(_) => "errors.invalidArgument",
+            AppError::StorageFailed(_) => "errors.storageFailed",
+            AppError::MissingApiKey(_) => "errors.missingApiKey",
+            AppError::ProviderAuthentication(_) => "errors.providerAuthentication",
+            AppError::ProviderRejected(_) => "errors.providerRejected",
+            AppError::ProviderCapacity(_) => "errors.providerCapacity",
+            AppError::ProviderUnavailable(_) => "errors.providerUnavailable",
+            AppError::CorrectionFailed(_) => "errors.correctionFailed",
+            AppError::BlockedByPolicy(_) => "errors.blockedByPolicy",
+            AppError::Cancelled(_) => "errors.cancelled",
+        }
+    }
+
+    pub fn details(&self) -> &str {
+        match self {
+            AppError::KeyringUnavailable(details)
+            | AppError::NotFound(details)
+            | AppError::InvalidShortcut(details)
+            | AppError::ShortcutUnavailable(details)
+            | AppError::ShortcutReserved(details)
+            | AppError::ClipboardFailed(details)
+            | AppError::CredentialsLocked(details)
+            | AppError::WrongPassphrase(details)
+            | AppError::InvalidArgument(details)
+            | AppError::StorageFailed(details)
+            | AppError::MissingApiKey(details)
+            | AppError::ProviderAuthentication(details)
+            | AppError::ProviderRejected(details)
+            | AppError::ProviderCapacity(details)
+            | AppError::ProviderUnavailable(details)
+            | AppError::CorrectionFailed(details)
+            | AppError::BlockedByPolicy(details)
+            | AppError::Cancelled(details) => details,
+        }
+    }
+
+    /// Classifies a credential-vault failure for the record `key`.
+    pub fn keyring(key: &str, error: keyring::Error) -> Self {
+        let details = format!("Credential '{}': {}", key, error);
+        match error {
+            keyring::Error::NoEntry => AppError::NotFound(details),
+            keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => {
+                AppError::KeyringUnavailable(details)
+            }
+            _ => AppError::StorageFailed(details),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.details())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("AppError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("messageKey", self.message_key())?;
+        error.serialize_field("details", self.details())?;
+        error.end()
+    }
+}
@@ -0,0 +1,262 @@
+//! Paragraph-level re-correction.
+//!
+//! Every correction also remembers each of its paragraphs in the correction
+//! cache. When the text comes back with a few paragraphs edited, the others
+//! are taken from the cache and only the edited ones are sent, each run of
+//! neighbouring edits in one request. The answer is still the whole
+//! document, so the review diffs it as a whole.
+
+use crate::cache::CorrectionCache;
+use crate::chunks::{self, Chunk};
+use crate::error::AppError;
+use crate::llm::{CorrectionRequest, CorrectionResult, LlmClient, Partial};
+
+/// Corrects `request.text`, sending only the paragraphs with no cached correction.
+pub async fn correct(
+    client: &LlmClient,
+    api_key: &str,
+    request: &CorrectionRequest,
+    cache: &CorrectionCache,
+    partial: Option<Partial<'_>>,
+) -> Result<CorrectionResult, AppError> {
+    let text = request.text.as_str();
+    let paragraphs = chunks::paragraphs(text);
+    let cached: Vec<Option<String>> = paragraphs
+        .iter()
+        .map(|paragraph| {
+            if paragraph.prose {
+                cache.get(&request.with_text(&text[paragraph.start..paragraph.end]))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    // Nothing to reuse: one ordinary correction, whose paragraphs are remembered for next time
+    if cached.iter().all(Option::is_none) {
+        let result = match partial {
+            Some(partial) => client.correct_streaming(api_key, request, partial).await?,
+            None => client.correct(api_key, request).await?,
+        };
+        if result.is_complete() {
+            remember(cache, request, text, &result.result);
+        }
+        return Ok(result);
+    }
+
+    // Cached paragraphs and code are known; neighbouring edited paragraphs go out together
+    let mut parts: Vec<(Chunk, Option<String>)> = Vec::new();
+    let mut run: Option<Chunk> = None;
+    for (paragraph, cached) in paragraphs.into_iter().zip(cached) {
+        if paragraph.prose && cached.is_none() {
+            run = Some(match run {
+                Some(open) => Chunk { end: paragraph.end, ..open },
+                None => paragraph,
+            });
+            continue;
+        }
+        if let Some(open) = run.take() {
+            parts.extend(pieces(text, open));
+        }
+        let known = cached.unwrap_or_else(|| text[paragraph.start..paragraph.end].to_string());
+        parts.push((paragraph, Some(known)));
+    }
+    if let Some(open) = run {
+        parts.extend(pieces(text, open));
+    }
+
+    let (chunks, known): (Vec<Chunk>, Vec<Option<String>>) = parts.into_iter().unzip();
+    let sent: Vec<usize> = (0..chunks.len()).filter(|index| known[*index].is_none()).collect();
+    tracing::info!(reused = chunks.len() - sent.len(), sent = sent.len(), "Correcting the edited paragraphs only");
+    let (mut result, texts) = client.correct_parts(api_key, request, &chunks, known, partial).await?;
+    if result.is_complete() {
+        for index in &sent {
+            let chunk = chunks[*index];
+            remember(cache, request, &text[chunk.start..chunk.end], &texts[*index]);
+        }
+    }
+    result.cached = sent.is_empty();
+    Ok(result)
+}
+
+// A run of edited paragraphs, split again when it is over the request limit.
+// Code never joins a run, so every piece is prose.
+fn pieces(text: &str, run: Chunk) -> Vec<(Chunk, Option<String>)> {
+    chunks::split(&text[run.start..run.end], chunks::MAX_CHUNK_CHARACTERS)
+        .into_iter()
+        .map(|piece| (Chunk { start: run.start + piece.start, end: run.start + piece.end, prose: true }, None))
+        .collect()
+}
+
+// Caches each paragraph of `original` with its correction, but only when the
+// model kept the paragraphs apart; merged or split ones cannot be paired up
+fn remember(cache: &CorrectionCache, request: &CorrectionRequest, original: &str, corrected: &str) {
+    let before = chunks::paragraphs(original);
+    let after = chunks::paragraphs(corrected);
+    if before.len() != after.len() || before.iter().zip(&after).any(|(a, b)| a.prose != b.prose) {
+        return;
+    }
+    let requests: Vec<(CorrectionRequest, &str)> = before
+        .iter()
+        .zip(&after)
+        .filter(|(paragraph, _)| paragraph.prose)
+        .map(|(a, b)| (request.with_text(&original[a.start..a.end]), &corrected[b.start..b.end]))
+        .collect();
+    if let Err(e) = cache.insert_all(requests.iter().map(|(request, result)| (request, *result))) {
+        tracing::warn!("Failed to cache corrected paragraphs: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use serde_json::{json, Value};
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::{Arc, Mutex};
+
+    // A model that fixes "teh" and runs a paragraph starting with "Join" into
+    // the one before. Hands back every text it was sent.
+    fn model() -> (LlmClient, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let received = sent.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let received = received.clone();
+                std::thread::spawn(move || answer(stream.unwrap(), &received));
+            }
+        });
+        (LlmClient::with_base_url(&base_url), sent)
+    }
+
+    fn answer(mut stream: TcpStream, received: &Mutex<Vec<String>>) {
+        let mut request = Vec::new();
+        let mut buffer = [0; 8192];
+        let body_start = loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let length: usize = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|value| value.trim().parse().unwrap())
+            .unwrap_or_default();
+        while request.len() < body_start + length {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body: Value = serde_json::from_slice(&request[body_start..]).unwrap();
+        let text = body["messages"][1]["content"].as_str().unwrap().to_string();
+        let corrected = text.replace("teh", "the").replace("\n\nJoin", " join");
+        received.lock().unwrap().push(text);
+
+        let payload = json!({ "choices": [{ "message": { "content": corrected }, "finish_reason": "stop" }] }).to_string();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            payload.len(),
+            payload
+        );
+        stream.write_all(response.as_bytes()).unwrap();
+    }
+
+    fn request(text: &str) -> CorrectionRequest {
+        CorrectionRequest {
+            text: text.to_string(),
+            model: "gpt-5.4-mini".to_string(),
+            writing_style: None,
+            custom_rules: None,
+            language: None,
+            temperature: None,
+            profile: None,
+            dictionary: Default::default(),
+        }
+    }
+
+    fn take(sent: &Mutex<Vec<String>>) -> Vec<String> {
+        std::mem::take(&mut *sent.lock().unwrap())
+    }
+
+    const DOCUMENT: &str = "One teh.\n\nTwo teh.\n\n```\nteh code\n```\n\nThree teh.\n\nFour teh.";
+
+    #[tokio::test]
+    async fn sends_only_edited_paragraphs_with_neighbours_together() {
+        let (client, sent) = model();
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+
+        let first = correct(&client, "key", &request(DOCUMENT), &cache, None).await.unwrap();
+        assert_eq!(first.result, DOCUMENT.replace("teh.", "the."));
+        // Code never reaches the model
+        assert_eq!(take(&sent), [DOCUMENT.replace("```\nteh code\n```", "[[CORRECTIFY_CODE_0_DO_NOT_EDIT]]")]);
+
+        // Edits with unchanged paragraphs between them are sent separately
+        let edited = DOCUMENT.replace("Two teh.", "Two teh, edited.").replace("Four teh.", "Four teh, edited.");
+        let second = correct(&client, "key", &request(&edited), &cache, None).await.unwrap();
+        assert_eq!(second.result, edited.replace("teh.", "the.").replace("teh,", "the,"));
+        assert!(!second.cached);
+        let mut texts = take(&sent);
+        texts.sort();
+        assert_eq!(texts, ["Four teh, edited.", "Two teh, edited."]);
+
+        // Neighbouring edits go out in one request
+        let edited = edited.replace("Three teh.", "Three teh again.").replace("Four teh, edited.", "Four teh again.");
+        let third = correct(&client, "key", &request(&edited), &cache, None).await.unwrap();
+        assert!(third.result.starts_with("One the.\n\nTwo the, edited."));
+        assert!(third.result.ends_with("Three the again.\n\nFour the again."));
+        assert_eq!(take(&sent), ["Three teh again.\n\nFour teh again."]);
+    }
+
+    #[tokio::test]
+    async fn a_fully_cached_document_sends_nothing() {
+        let (client, sent) = model();
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+        correct(&client, "key", &request(DOCUMENT), &cache, None).await.unwrap();
+        take(&sent);
+
+        // Reordered paragraphs are each still in the cache
+        let reordered = "Four teh.\n\nOne teh.";
+        let result = correct(&client, "key", &request(reordered), &cache, None).await.unwrap();
+        assert_eq!(result.result, "Four the.\n\nOne the.");
+        assert!(result.cached);
+        assert!(take(&sent).is_empty());
+    }
+
+    #[tokio::test]
+    async fn added_paragraphs_are_sent_alone() {
+        let (client, sent) = model();
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+        correct(&client, "key", &request("One teh.\n\nTwo teh."), &cache, None).await.unwrap();
+        take(&sent);
+
+        let result = correct(&client, "key", &request("One teh.\n\nNew teh.\n\nTwo teh."), &cache, None).await.unwrap();
+        assert_eq!(result.result, "One the.\n\nNew the.\n\nTwo the.");
+        assert_eq!(take(&sent), ["New teh."]);
+    }
+
+    #[tokio::test]
+    async fn merged_paragraphs_are_not_reused() {
+        let (client, sent) = model();
+        let data = testing::data_dir();
+        let cache = CorrectionCache::open(data.path());
+        let document = "One teh.\n\nJoin teh.";
+
+        let first = correct(&client, "key", &request(document), &cache, None).await.unwrap();
+        assert_eq!(first.result, "One the. join the.");
+        assert_eq!(take(&sent), [document]);
+
+        // The answer had one paragraph for two, so none was remembered
+        let again = correct(&client, "key", &request(document), &cache, None).await.unwrap();
+        assert_eq!(again.result, "One the. join the.");
+        assert!(!again.cached);
+        assert_eq!(take(&sent), [document]);
+    }
+}
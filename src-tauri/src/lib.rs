@@ -8,10 +8,8 @@ use std::sync::{Arc, Mutex};
 use std::fs;
 #[cfg(target_os = "macos")]
 use std::cell::RefCell;
-use std::io::Cursor;
+use std::io::{Cursor, Read, Seek};
 use enigo::{Enigo, Key, Keyboard, Settings};
-use base64::{Engine as _, engine::general_purpose};
-use keyring::Entry;
 use serde_json::Value;
 #[cfg(target_os = "macos")]
 use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
@@ -26,12 +24,45 @@ use objc2::runtime::{NSObjectProtocol, ProtocolObject};
 #[cfg(target_os = "macos")]
 use objc2_foundation::{NSActivityOptions, NSProcessInfo, NSString};
 
+mod api_keys;
+mod backup;
+mod batch;
+mod cache;
+mod chunks;
+mod cli;
+mod crash;
+mod credentials;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod dictionary;
+mod error;
+mod incremental;
+mod llm;
+mod logging;
+mod lsp;
+mod migrations;
+mod native_host;
+mod paths;
+mod policy;
+mod prompts;
+mod review;
+mod shortcut;
+#[cfg(target_os = "linux")]
+mod sound_theme;
+#[cfg(test)]
+mod testing;
+
+use error::AppError;
+
 // Locale JSON files loaded at compile time
 const LOCALE_EN: &str = include_str!("../../lib/locales/en.json");
 const LOCALE_DE: &str = include_str!("../../lib/locales/de.json");
 const LOCALE_FR: &str = include_str!("../../lib/locales/fr.json");
 const LOCALE_TR: &str = include_str!("../../lib/locales/tr.json");
 
+/// The environment variable holding the encrypted credential file's passphrase for `lsp` and `native-host`.
+const PASSPHRASE_VARIABLE: &str = "CORRECTIFY_PASSPHRASE";
+
 // Application state for settings
 struct AppState {
     sound_enabled: Arc<Mutex<bool>>,
@@ -39,6 +70,9 @@ struct AppState {
     shortcut_modifier: Arc<Mutex<String>>,
     auto_paste_enabled: Arc<Mutex<bool>>,
     auto_paste_in_flight: Arc<Mutex<bool>>,
+    shortcut_capturing: Arc<Mutex<bool>>,
+    /// False when no combination could be registered, so the shortcut does nothing.
+    shortcut_registered: Arc<Mutex<bool>>,
     current_model: Arc<Mutex<String>>,
     current_style: Arc<Mutex<String>>,
     locale: Arc<Mutex<String>>,
@@ -81,25 +115,43 @@ fn play_sound(sound_type: &str, sound_enabled: bool) {
         "completed" => include_bytes!("../sounds/completed.wav"),
         _ => return,
     };
-
-    let bytes = sound_bytes.to_vec();
+    #[cfg(target_os = "linux")]
+    let sound_name = sound_type.to_string();
 
     // Play sound in a separate thread to avoid blocking
     thread::spawn(move || {
-        if let Err(e) = play_sound_blocking(&bytes) {
-            eprintln!("Failed to play sound: {}", e);
+        // Follow the desktop sound theme on Linux; bundled sounds are the fallback
+        #[cfg(target_os = "linux")]
+        match sound_theme::resolve(&sound_name) {
+            sound_theme::ThemeSound::Muted => return,
+            sound_theme::ThemeSound::File(path) => {
+                let played = fs::File::open(&path)
+                    .map_err(Box::<dyn std::error::Error>::from)
+                    .and_then(|file| play_sound_blocking(std::io::BufReader::new(file)));
+                match played {
+                    Ok(()) => return,
+                    Err(e) => tracing::warn!("Failed to play themed sound {}: {}", path.display(), e),
+                }
+            }
+            sound_theme::ThemeSound::Missing => {}
+        }
+
+        if let Err(e) = play_sound_blocking(Cursor::new(sound_bytes)) {
+            tracing::warn!("Failed to play sound: {}", e);
         }
     });
 }
 
-fn play_sound_blocking(bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+fn play_sound_blocking<R>(reader: R) -> Result<(), Box<dyn std::error::Error>>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
     use rodio::{Decoder, OutputStream, Sink};
 
     let (_stream, stream_handle) = OutputStream::try_default()?;
     let sink = Sink::try_new(&stream_handle)?;
 
-    let cursor = Cursor::new(bytes.to_vec());
-    let source = Decoder::new(cursor)?;
+    let source = Decoder::new(reader)?;
 
     sink.append(source);
     sink.sleep_until_end();
@@ -109,19 +161,22 @@ fn play_sound_blocking(bytes: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
 
 // Tauri command to handle corrected text from frontend
 #[tauri::command]
+#[tracing::instrument(name = "deliver_correction", skip_all, fields(chars = text.chars().count()))]
 async fn handle_corrected_text(
     app: tauri::AppHandle,
     text: String,
     model: Option<String>,
     duration: Option<f64>,
     auto_paste: Option<bool>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     use tauri_plugin_clipboard_manager::ClipboardExt;
     use tauri_plugin_notification::NotificationExt;
 
     // Write corrected text to clipboard
+    tracing::debug!("Writing corrected text to the clipboard");
     app.clipboard().write_text(text.clone())
-        .map_err(|e| format!("Failed to write to clipboard: {}", e))?;
+        .map_err(|e| AppError::ClipboardFailed(e.to_string()))?;
+    signal_clipboard_correction(&app, Some(true));
 
     // Add a small delay to ensure the processing notification is visible
     thread::sleep(Duration::from_millis(500));
@@ -129,7 +184,8 @@ async fn handle_corrected_text(
     // Get settings state
     let state = app.state::<AppState>();
     let sound_enabled = *state.sound_enabled.lock().unwrap();
-    let should_auto_paste = auto_paste.unwrap_or(false);
+    let locked_auto_paste = app.state::<policy::Policy>().locked.auto_paste_enabled;
+    let should_auto_paste = locked_auto_paste.unwrap_or(auto_paste.unwrap_or(false));
     let locale = state.locale.lock().unwrap().clone();
 
     // Build notification body with optional model and duration
@@ -153,6 +209,7 @@ async fn handle_corrected_text(
 
     // Play completed sound
     play_sound("completed", sound_enabled);
+    crash::record("correction delivered");
 
     // If auto-paste is enabled, simulate paste using clipboard (Cmd+V/Ctrl+V)
     // Since text is already copied to clipboard, this is more reliable than typing
@@ -160,13 +217,12 @@ async fn handle_corrected_text(
         {
             let mut in_flight = state.auto_paste_in_flight.lock().unwrap();
             if *in_flight {
-                println!("[Auto-paste] Ignored duplicate paste request");
+                tracing::warn!("Ignored duplicate auto-paste request");
                 return Ok(());
             }
             *in_flight = true;
         }
-        let _ = app.emit("auto-paste-debug", "Auto-paste enabled, checking permissions...");
-        println!("[Auto-paste] Auto-paste enabled, checking permissions...");
+        tracing::debug!("Auto-paste enabled, checking permissions");
 
         // Check macOS accessibility permissions before attempting auto-paste
         #[cfg(target_os = "macos")]
@@ -180,16 +236,13 @@ async fn handle_corrected_text(
             let has_permission = match output {
                 Ok(result) => result.status.success(),
                 Err(e) => {
-                    let error_msg = format!("Failed to check permissions: {:?}", e);
-                    let _ = app.emit("auto-paste-debug", &error_msg);
-                    eprintln!("[Auto-paste] {}", error_msg);
+                    tracing::error!("Failed to check accessibility permission: {:?}", e);
                     false
                 }
             };
 
             if !has_permission {
-                let _ = app.emit("auto-paste-debug", "Accessibility permission not granted");
-                eprintln!("[Auto-paste] Accessibility permission not granted");
+                tracing::warn!("Accessibility permission not granted");
                 let state = app.state::<AppState>();
                 let locale = state.locale.lock().unwrap().clone();
                 let title = get_translation(&locale, "notifications.permissionRequired");
@@ -202,29 +255,23 @@ async fn handle_corrected_text(
                 *state.auto_paste_in_flight.lock().unwrap() = false;
                 return Ok(()); // Don't try to use enigo without permission
             }
-            let _ = app.emit("auto-paste-debug", "Accessibility permission granted");
-            println!("[Auto-paste] Accessibility permission granted");
+            tracing::debug!("Accessibility permission granted");
         }
 
-        // Clone app handle for use in thread
+        // Clone app handle and span for use in thread
         let app_clone = app.clone();
+        let span = tracing::info_span!("auto_paste");
 
         // Spawn a separate thread to avoid blocking
         thread::spawn(move || {
             use tauri_plugin_notification::NotificationExt;
             use std::panic;
 
-            // Helper function to safely emit diagnostics without persisting them.
-            let emit_debug = |msg: &str| {
-                println!("[Auto-paste] {}", msg);
-                let _ = app_clone.emit("auto-paste-debug", msg);
-            };
+            let _span = span.entered();
+            crash::record("auto-paste started");
 
-            // Emit initial message
-            emit_debug("Thread spawned, waiting before paste...");
+            tracing::debug!("Waiting before paste");
             thread::sleep(Duration::from_millis(1200));
-            emit_debug("Sleep completed, proceeding...");
-            emit_debug("Attempting to create Enigo instance...");
 
             // Now wrap Enigo operations in catch_unwind
             let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
@@ -232,7 +279,7 @@ async fn handle_corrected_text(
                 #[cfg(target_os = "macos")]
                 {
                     use std::process::Command;
-                    emit_debug("Pasting via AppleScript...");
+                    tracing::debug!("Pasting via AppleScript");
                     let applescript_result = Command::new("osascript")
                         .arg("-e")
                         .arg("tell application \"System Events\" to keystroke \"v\" using command down")
@@ -241,7 +288,7 @@ async fn handle_corrected_text(
                     match applescript_result {
                         Ok(output) => {
                             if output.status.success() {
-                                emit_debug("Auto-paste completed successfully");
+                                tracing::info!("Auto-paste completed");
                                 let state = app_clone.state::<AppState>();
                                 let locale = state.locale.lock().unwrap().clone();
                                 let title = get_translation(&locale, "notifications.title");
@@ -254,7 +301,7 @@ async fn handle_corrected_text(
                             } else {
                                 let stderr = String::from_utf8_lossy(&output.stderr);
                                 let error_msg = format!("AppleScript paste failed: {}", stderr);
-                                emit_debug(&error_msg);
+                                tracing::error!("{}", error_msg);
                                 let state = app_clone.state::<AppState>();
                                 let locale = state.locale.lock().unwrap().clone();
                                 let title = get_translation(&locale, "notifications.autoPasteFailed");
@@ -268,7 +315,7 @@ async fn handle_corrected_text(
                         }
                         Err(e) => {
                             let error_msg = format!("AppleScript command failed: {:?}", e);
-                            emit_debug(&error_msg);
+                            tracing::error!("{}", error_msg);
                             let state = app_clone.state::<AppState>();
                             let locale = state.locale.lock().unwrap().clone();
                             let title = get_translation(&locale, "notifications.autoPasteFailed");
@@ -289,8 +336,7 @@ async fn handle_corrected_text(
                         Ok(enigo) => enigo,
                         Err(e) => {
                             let error_msg = format!("Failed to create Enigo instance: {:?}", e);
-                            println!("[Auto-paste] {}", error_msg);
-                            let _ = app_clone.emit("auto-paste-debug", &error_msg);
+                            tracing::error!("{}", error_msg);
                             let state = app_clone.state::<AppState>();
                             let locale = state.locale.lock().unwrap().clone();
                             let title = get_translation(&locale, "notifications.autoPasteFailed");
@@ -305,18 +351,18 @@ async fn handle_corrected_text(
                     };
 
                     // Use Enigo for non-macOS platforms
-                    emit_debug("Pressing Ctrl key...");
+                    tracing::debug!("Pressing Ctrl key");
                     match enigo.key(Key::Control, enigo::Direction::Press) {
                         Ok(_) => {
                             thread::sleep(Duration::from_millis(100));
-                            emit_debug("Pressing 'v' key...");
+                            tracing::debug!("Pressing 'v' key");
                             match enigo.key(Key::Unicode('v'), enigo::Direction::Click) {
                                 Ok(_) => {
                                     thread::sleep(Duration::from_millis(100));
-                                    emit_debug("Releasing Ctrl key...");
+                                    tracing::debug!("Releasing Ctrl key");
                                     match enigo.key(Key::Control, enigo::Direction::Release) {
                                         Ok(_) => {
-                                            emit_debug("Auto-paste completed successfully");
+                                            tracing::info!("Auto-paste completed");
                                             let state = app_clone.state::<AppState>();
                                             let locale = state.locale.lock().unwrap().clone();
                                             let title = get_translation(&locale, "notifications.title");
@@ -329,20 +375,20 @@ async fn handle_corrected_text(
                                         }
                                         Err(e) => {
                                             let error_msg = format!("Failed to release Control key: {:?}", e);
-                                            emit_debug(&error_msg);
+                                            tracing::error!("{}", error_msg);
                                         }
                                     }
                                 }
                                 Err(e) => {
                                     let error_msg = format!("Failed to press 'v' key: {:?}", e);
-                                    emit_debug(&error_msg);
+                                    tracing::error!("{}", error_msg);
                                     let _ = enigo.key(Key::Control, enigo::Direction::Release);
                                 }
                             }
                         }
                         Err(e) => {
                             let error_msg = format!("Failed to press Control key: {:?}", e);
-                            emit_debug(&error_msg);
+                            tracing::error!("{}", error_msg);
                         }
                     }
                 }
@@ -351,10 +397,9 @@ async fn handle_corrected_text(
             // Handle panic result
             if let Err(panic_payload) = result {
                 let error_msg = format!("Thread panicked: {:?}", panic_payload);
-                eprintln!("[Auto-paste] {}", error_msg);
-                // Try to emit and show notification, but don't panic if it fails
+                tracing::error!("{}", error_msg);
+                // Try to show a notification, but don't panic if it fails
                 let _ = std::panic::catch_unwind(panic::AssertUnwindSafe(|| {
-                    let _ = app_clone.emit("auto-paste-debug", &error_msg);
                     let state = app_clone.state::<AppState>();
                     let locale = state.locale.lock().unwrap().clone();
                     let title = get_translation(&locale, "notifications.autoPasteFailed");
@@ -366,9 +411,10 @@ async fn handle_corrected_text(
                         .show();
                 }));
             } else {
-                emit_debug("Thread completed without panicking");
+                tracing::debug!("Auto-paste thread completed without panicking");
             }
             *app_clone.state::<AppState>().auto_paste_in_flight.lock().unwrap() = false;
+            crash::record("auto-paste finished");
         });
     }
 
@@ -377,7 +423,8 @@ async fn handle_corrected_text(
 
 // Tauri command to update sound setting
 #[tauri::command]
-fn set_sound_enabled(enabled: bool, state: tauri::State<AppState>) -> Result<(), String> {
+fn set_sound_enabled(enabled: bool, state: tauri::State<AppState>, policy: tauri::State<policy::Policy>) -> Result<(), AppError> {
+    policy.check_locked("soundEnabled", policy.locked.sound_enabled.as_ref(), &enabled)?;
     let mut sound_enabled = state.sound_enabled.lock().unwrap();
     *sound_enabled = enabled;
     Ok(())
@@ -385,25 +432,103 @@ fn set_sound_enabled(enabled: bool, state: tauri::State<AppState>) -> Result<(),
 
 // Tauri command to get sound setting
 #[tauri::command]
-fn get_sound_enabled(state: tauri::State<AppState>) -> Result<bool, String> {
+fn get_sound_enabled(state: tauri::State<AppState>) -> Result<bool, AppError> {
     let sound_enabled = state.sound_enabled.lock().unwrap();
     Ok(*sound_enabled)
 }
 
-// Helper function to convert modifier string to platform-specific format
-// Tauri uses "Alt" for Option key on macOS, and "Alt" for Alt key on Windows/Linux
-fn convert_modifier_to_platform(modifier: &str) -> String {
-    #[cfg(target_os = "macos")]
-    {
-        modifier
-            .replace("CmdOrCtrl", "Cmd")
-            .replace("AltOrOption", "Alt")
+// Helper function to read the shortcut currently held in state
+fn current_shortcut(state: &AppState) -> shortcut::Combination {
+    shortcut::Combination {
+        modifier: state.shortcut_modifier.lock().unwrap().clone(),
+        key: state.shortcut_key.lock().unwrap().clone(),
     }
-    #[cfg(not(target_os = "macos"))]
-    {
-        modifier
-            .replace("CmdOrCtrl", "Ctrl")
-            .replace("AltOrOption", "Alt")
+}
+
+// Tray menu items whose text follows the app's state
+struct TrayItems {
+    shortcut_status: tauri::menu::MenuItem<tauri::Wry>,
+}
+
+// Helper function to show the live shortcut, or that there is none, in the tray menu
+fn update_tray_shortcut(app: &tauri::AppHandle, combination: Option<&shortcut::Combination>) {
+    let Some(tray) = app.try_state::<TrayItems>() else {
+        return;
+    };
+    let text = match combination {
+        Some(combination) => format!("Shortcut: {}", combination.accelerator()),
+        None => "Shortcut: none, choose one in Settings".to_string(),
+    };
+    if let Err(e) = tray.shortcut_status.set_text(text) {
+        tracing::warn!("Failed to update the tray shortcut status: {}", e);
+    }
+}
+
+// Registers the current shortcut at startup or, when it is invalid or another app holds it,
+// the first fallback that is free. Startup carries on without a shortcut when none is;
+// the webview then opens Settings on the shortcut tab.
+fn register_startup_shortcut(app: &tauri::AppHandle) {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    let state = app.state::<AppState>();
+    let preferred = current_shortcut(&state);
+    let locale = state.locale.lock().unwrap().clone();
+
+    let mut registered = None;
+    for candidate in shortcut::fallbacks(&preferred, &locale) {
+        let accelerator = candidate.accelerator();
+        let result = accelerator
+            .parse::<Shortcut>()
+            .map_err(|e| e.to_string())
+            .and_then(|parsed| app.global_shortcut().register(parsed).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => {
+                registered = Some(candidate);
+                break;
+            }
+            Err(e) => tracing::warn!("Failed to register global shortcut {}: {}", accelerator, e),
+        }
+    }
+
+    match &registered {
+        Some(combination) => {
+            if *combination != preferred {
+                tracing::warn!("Using fallback global shortcut {} instead of {}", combination.accelerator(), preferred.accelerator());
+            }
+            tracing::info!("Global shortcut registered: {}", combination.accelerator());
+            *state.shortcut_key.lock().unwrap() = combination.key.clone();
+            *state.shortcut_modifier.lock().unwrap() = combination.modifier.clone();
+        }
+        None => tracing::error!("No global shortcut could be registered"),
+    }
+    *state.shortcut_registered.lock().unwrap() = registered.is_some();
+    update_tray_shortcut(app, registered.as_ref());
+}
+
+// Helper function to learn whether a combination is free by registering it briefly.
+// Best effort: some platforms accept a combination another app already uses.
+fn shortcut_availability(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    combination: &shortcut::Combination,
+) -> shortcut::Availability {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    if *combination == current_shortcut(state) {
+        return shortcut::Availability::Current;
+    }
+    let Ok(candidate) = combination.accelerator().parse::<Shortcut>() else {
+        return shortcut::Availability::Taken;
+    };
+    match app.global_shortcut().register(candidate) {
+        Ok(_) => {
+            let _ = app.global_shortcut().unregister(candidate);
+            shortcut::Availability::Available
+        }
+        Err(e) => {
+            tracing::debug!("Shortcut {} is taken: {}", combination.accelerator(), e);
+            shortcut::Availability::Taken
+        }
     }
 }
 
@@ -414,19 +539,17 @@ fn update_shortcut(
     new_modifier: String,
     app: tauri::AppHandle,
     state: tauri::State<AppState>,
-) -> Result<(), String> {
+) -> Result<shortcut::Combination, AppError> {
     use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
 
+    let new_combination = shortcut::Combination::parse(&new_modifier, &new_key)?;
+
     // Get the current shortcut key and modifier
     let mut shortcut_key = state.shortcut_key.lock().unwrap();
     let mut shortcut_modifier = state.shortcut_modifier.lock().unwrap();
 
-    // Convert modifiers to platform-specific format
-    let old_modifier_platform = convert_modifier_to_platform(&*shortcut_modifier);
-    let new_modifier_platform = convert_modifier_to_platform(&new_modifier);
-
-    let old_shortcut_str = format!("{}+{}", old_modifier_platform, *shortcut_key);
-    let new_shortcut_str = format!("{}+{}", new_modifier_platform, new_key);
+    let old_shortcut_str = format!("{}+{}", shortcut::platform_modifier(&shortcut_modifier), *shortcut_key);
+    let new_shortcut_str = new_combination.accelerator();
 
     // Unregister old shortcut
     if let Ok(old_shortcut) = old_shortcut_str.parse::<Shortcut>() {
@@ -438,40 +561,136 @@ fn update_shortcut(
         Ok(new_shortcut) => {
             match app.global_shortcut().register(new_shortcut) {
                 Ok(_) => {
-                    *shortcut_key = new_key.clone();
-                    *shortcut_modifier = new_modifier.clone();
-                    Ok(())
+                    *shortcut_key = new_combination.key.clone();
+                    *shortcut_modifier = new_combination.modifier.clone();
+                    *state.shortcut_registered.lock().unwrap() = true;
+                    update_tray_shortcut(&app, Some(&new_combination));
+                    tracing::info!("Global shortcut changed to {}", new_shortcut_str);
+                    Ok(new_combination)
                 }
                 Err(e) => {
                     // If registration fails, re-register the old one
-                    if let Ok(old_shortcut) = old_shortcut_str.parse::<Shortcut>() {
-                        let _ = app.global_shortcut().register(old_shortcut);
+                    let restored = match old_shortcut_str.parse::<Shortcut>() {
+                        Ok(old_shortcut) => app.global_shortcut().register(old_shortcut).is_ok(),
+                        Err(_) => false,
+                    };
+                    let old_combination = shortcut::Combination { modifier: shortcut_modifier.clone(), key: shortcut_key.clone() };
+                    *state.shortcut_registered.lock().unwrap() = restored;
+                    update_tray_shortcut(&app, restored.then_some(&old_combination));
+                    Err(AppError::ShortcutUnavailable(format!("{}: {}", new_shortcut_str, e)))
+                }
+            }
+        }
+        Err(e) => Err(AppError::InvalidShortcut(format!("{}: {}", new_shortcut_str, e)))
+    }
+}
+
+// Tauri command to validate a shortcut and report whether it is free, without saving it
+#[tauri::command]
+fn check_shortcut(
+    key: String,
+    modifier: String,
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<shortcut::ShortcutCheck, AppError> {
+    let combination = shortcut::Combination::parse(&modifier, &key)?;
+    let availability = shortcut_availability(&app, &state, &combination);
+    Ok(shortcut::ShortcutCheck { combination, availability })
+}
+
+// Tauri command to start recording a shortcut. The live shortcut is released so
+// pressing it reaches the settings window instead of starting a correction.
+#[tauri::command]
+fn begin_shortcut_capture(app: tauri::AppHandle, state: tauri::State<AppState>) -> Result<(), AppError> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    let mut capturing = state.shortcut_capturing.lock().unwrap();
+    if !*capturing {
+        if let Ok(live) = current_shortcut(&state).accelerator().parse::<Shortcut>() {
+            let _ = app.global_shortcut().unregister(live);
+        }
+        *capturing = true;
+    }
+    Ok(())
+}
+
+// Tauri command to finish recording with the next key press, or cancel when `event` is None.
+// The live shortcut is restored either way; saving the result goes through update_shortcut.
+#[tauri::command]
+fn finish_shortcut_capture(
+    event: Option<shortcut::KeyEvent>,
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<Option<shortcut::ShortcutCheck>, AppError> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+    {
+        let mut capturing = state.shortcut_capturing.lock().unwrap();
+        if *capturing {
+            let live = current_shortcut(&state).accelerator();
+            let restored = match live.parse::<Shortcut>() {
+                Ok(parsed) => match app.global_shortcut().register(parsed) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!("Failed to restore global shortcut {}: {}", live, e);
+                        false
                     }
-                    Err(format!("Failed to register shortcut: {}", e))
+                },
+                Err(e) => {
+                    tracing::warn!("Failed to parse global shortcut {}: {}", live, e);
+                    false
                 }
+            };
+            if !restored {
+                *state.shortcut_registered.lock().unwrap() = false;
+                update_tray_shortcut(&app, None);
             }
+            *capturing = false;
         }
-        Err(e) => Err(format!("Invalid shortcut format: {}", e))
     }
+
+    let Some(event) = event else {
+        return Ok(None);
+    };
+    let combination = shortcut::Combination::from_key_event(&event)?;
+    let availability = shortcut_availability(&app, &state, &combination);
+    Ok(Some(shortcut::ShortcutCheck { combination, availability }))
+}
+
+// Tauri command to report the live shortcut and whether it is registered, so the
+// webview can ask for another one when startup fell back or registered nothing
+#[tauri::command]
+fn get_shortcut_status(state: tauri::State<AppState>) -> Result<shortcut::ShortcutStatus, AppError> {
+    Ok(shortcut::ShortcutStatus {
+        combination: current_shortcut(&state),
+        registered: *state.shortcut_registered.lock().unwrap(),
+    })
+}
+
+// Tauri command to get the default shortcut for the current UI locale
+#[tauri::command]
+fn get_default_shortcut(state: tauri::State<AppState>) -> Result<shortcut::Combination, AppError> {
+    let locale = state.locale.lock().unwrap();
+    Ok(shortcut::default_for_locale(&locale))
 }
 
 // Tauri command to get current shortcut key
 #[tauri::command]
-fn get_shortcut_key(state: tauri::State<AppState>) -> Result<String, String> {
+fn get_shortcut_key(state: tauri::State<AppState>) -> Result<String, AppError> {
     let shortcut_key = state.shortcut_key.lock().unwrap();
     Ok(shortcut_key.clone())
 }
 
 // Tauri command to get current shortcut modifier
 #[tauri::command]
-fn get_shortcut_modifier(state: tauri::State<AppState>) -> Result<String, String> {
+fn get_shortcut_modifier(state: tauri::State<AppState>) -> Result<String, AppError> {
     let shortcut_modifier = state.shortcut_modifier.lock().unwrap();
     Ok(shortcut_modifier.clone())
 }
 
 // Tauri command to play sound in app (respects sound_enabled setting)
 #[tauri::command]
-fn play_sound_in_app(sound_type: String, state: tauri::State<AppState>) -> Result<(), String> {
+fn play_sound_in_app(sound_type: String, state: tauri::State<AppState>) -> Result<(), AppError> {
     let sound_enabled = *state.sound_enabled.lock().unwrap();
     play_sound(&sound_type, sound_enabled);
     Ok(())
@@ -479,7 +698,8 @@ fn play_sound_in_app(sound_type: String, state: tauri::State<AppState>) -> Resul
 
 // Tauri command to update auto-paste setting
 #[tauri::command]
-fn set_auto_paste_enabled(enabled: bool, state: tauri::State<AppState>) -> Result<(), String> {
+fn set_auto_paste_enabled(enabled: bool, state: tauri::State<AppState>, policy: tauri::State<policy::Policy>) -> Result<(), AppError> {
+    policy.check_locked("autoPasteEnabled", policy.locked.auto_paste_enabled.as_ref(), &enabled)?;
     let mut auto_paste_enabled = state.auto_paste_enabled.lock().unwrap();
     *auto_paste_enabled = enabled;
     Ok(())
@@ -487,7 +707,7 @@ fn set_auto_paste_enabled(enabled: bool, state: tauri::State<AppState>) -> Resul
 
 // Tauri command to get auto-paste setting
 #[tauri::command]
-fn get_auto_paste_enabled(state: tauri::State<AppState>) -> Result<bool, String> {
+fn get_auto_paste_enabled(state: tauri::State<AppState>) -> Result<bool, AppError> {
     let auto_paste_enabled = state.auto_paste_enabled.lock().unwrap();
     Ok(*auto_paste_enabled)
 }
@@ -560,7 +780,17 @@ fn set_correction_settings(
     model: Option<String>,
     style: Option<String>,
     state: tauri::State<AppState>,
-) -> Result<(), String> {
+    policy: tauri::State<policy::Policy>,
+    paths: tauri::State<paths::AppPaths>,
+) -> Result<(), AppError> {
+    if let Some(model_value) = &model {
+        let provider = llm::provider_for_model(model_value)
+            .ok_or_else(|| AppError::InvalidArgument(format!("Unknown model '{}'", model_value)))?;
+        policy.check_model(model_value, provider)?;
+    }
+    if let Some(style_value) = &style {
+        policy.check_locked("writingStyle", policy.locked.writing_style.as_deref(), style_value.as_str())?;
+    }
     if let Some(model_value) = model {
         let mut current_model = state.current_model.lock().unwrap();
         *current_model = model_value;
@@ -569,131 +799,822 @@ fn set_correction_settings(
         let mut current_style = state.current_style.lock().unwrap();
         *current_style = style_value;
     }
+
+    // Corrections made without the app, such as from the browser extension, use the same selection
+    let selected = native_host::SelectedSettings {
+        model: Some(state.current_model.lock().unwrap().clone()),
+        style: Some(state.current_style.lock().unwrap().clone()),
+    };
+    if let Err(e) = selected.save(&paths.data_dir) {
+        tracing::warn!("{}", e.details());
+    }
     Ok(())
 }
 
 // Tauri command to get current model
 #[tauri::command]
-fn get_current_model(state: tauri::State<AppState>) -> Result<String, String> {
+fn get_current_model(state: tauri::State<AppState>) -> Result<String, AppError> {
     let current_model = state.current_model.lock().unwrap();
     Ok(current_model.clone())
 }
 
 // Tauri command to get current style
 #[tauri::command]
-fn get_current_style(state: tauri::State<AppState>) -> Result<String, String> {
+fn get_current_style(state: tauri::State<AppState>) -> Result<String, AppError> {
     let current_style = state.current_style.lock().unwrap();
     Ok(current_style.clone())
 }
 
 // Tauri command to set locale
 #[tauri::command]
-fn set_locale(locale: String, state: tauri::State<AppState>) -> Result<(), String> {
+fn set_locale(locale: String, state: tauri::State<AppState>) -> Result<(), AppError> {
     let mut current_locale = state.locale.lock().unwrap();
     // Validate locale
     if ["en", "de", "fr", "tr"].contains(&locale.as_str()) {
         *current_locale = locale;
         Ok(())
     } else {
-        Err(format!("Invalid locale: {}", locale))
+        Err(AppError::InvalidArgument(format!("Invalid locale: {}", locale)))
     }
 }
 
 // Tauri command to get current locale
 #[tauri::command]
-fn get_locale(state: tauri::State<AppState>) -> Result<String, String> {
+fn get_locale(state: tauri::State<AppState>) -> Result<String, AppError> {
     let current_locale = state.locale.lock().unwrap();
     Ok(current_locale.clone())
 }
 
-fn keyring_entry(key: &str) -> Result<Entry, String> {
-    Entry::new("com.correctify", key)
-        .map_err(|e| format!("Could not access the system credential vault: {}", e))
+/// Bundles the rotating logs, the backend state, the frontend's non-secret
+/// preferences (`settings`) and platform details into a zip at `path`.
+#[tauri::command]
+fn export_diagnostics(
+    app: tauri::AppHandle,
+    path: String,
+    settings: Value,
+    state: tauri::State<AppState>,
+) -> Result<(), AppError> {
+    let log_dir = app.state::<paths::AppPaths>().log_dir.clone();
+    let backend_state = serde_json::json!({
+        "soundEnabled": *state.sound_enabled.lock().unwrap(),
+        "shortcutKey": state.shortcut_key.lock().unwrap().clone(),
+        "shortcutModifier": state.shortcut_modifier.lock().unwrap().clone(),
+        "autoPasteEnabled": *state.auto_paste_enabled.lock().unwrap(),
+        "model": state.current_model.lock().unwrap().clone(),
+        "style": state.current_style.lock().unwrap().clone(),
+        "locale": state.locale.lock().unwrap().clone(),
+        "credentialBackend": app.state::<credentials::Credentials>().backend(),
+        "portable": app.state::<paths::AppPaths>().portable,
+        "policy": *app.state::<policy::Policy>(),
+    });
+    let platform = serde_json::json!({
+        "appVersion": app.package_info().version.to_string(),
+        "tauriVersion": tauri::VERSION,
+        "os": tauri_plugin_os::platform(),
+        "osVersion": tauri_plugin_os::version().to_string(),
+        "family": tauri_plugin_os::family(),
+        "arch": tauri_plugin_os::arch(),
+        "systemLocale": tauri_plugin_os::locale(),
+    });
+
+    logging::bundle_diagnostics(
+        std::path::Path::new(&path),
+        &log_dir,
+        &[("backend-state.json", backend_state), ("settings.json", settings), ("platform.json", platform)],
+    )
+    .map_err(|e| AppError::StorageFailed(format!("Failed to write diagnostics: {}", e)))?;
+    tracing::info!("Diagnostics exported");
+    Ok(())
+}
+
+// Tauri command to write an encrypted backup of keys, presets and settings
+#[tauri::command]
+fn export_backup(
+    app: tauri::AppHandle,
+    path: String,
+    passphrase: String,
+    settings: std::collections::BTreeMap<String, String>,
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<(), AppError> {
+    let version = app.package_info().version.to_string();
+    backup::export(std::path::Path::new(&path), &passphrase, &version, settings, &credentials)
+}
+
+// Tauri command to decrypt a backup and show what restoring it would change
+#[tauri::command]
+fn preview_backup(
+    path: String,
+    passphrase: String,
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<backup::BackupPreview, AppError> {
+    backup::preview(std::path::Path::new(&path), &passphrase, &credentials)
+}
+
+// Tauri command to restore the key profiles from a backup
+#[tauri::command]
+fn restore_backup(
+    path: String,
+    passphrase: String,
+    resolution: api_keys::ConflictResolution,
+    credentials: tauri::State<credentials::Credentials>,
+    policy: tauri::State<policy::Policy>,
+) -> Result<u32, AppError> {
+    backup::restore(std::path::Path::new(&path), &passphrase, resolution, &credentials, &policy)
+}
+
+// Lets the user open or copy the crash report left by the previous run
+fn offer_crash_report(app: &tauri::AppHandle, report: std::path::PathBuf) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogResult};
+
+    let locale = app.state::<AppState>().locale.lock().unwrap().clone();
+    let open_label = get_translation(&locale, "crashReport.open");
+    let copy_label = get_translation(&locale, "crashReport.copy");
+    let app_handle = app.clone();
+    app.dialog()
+        .message(format!("{}\n\n{}", get_translation(&locale, "crashReport.body"), report.display()))
+        .title(get_translation(&locale, "crashReport.title"))
+        .buttons(MessageDialogButtons::YesNoCancelCustom(
+            open_label.clone(),
+            copy_label.clone(),
+            get_translation(&locale, "crashReport.dismiss"),
+        ))
+        .show_with_result(move |result| match result {
+            MessageDialogResult::Yes => open_crash_report(&report),
+            MessageDialogResult::Custom(label) if label == open_label => open_crash_report(&report),
+            MessageDialogResult::No => copy_crash_report(&app_handle, &report),
+            MessageDialogResult::Custom(label) if label == copy_label => copy_crash_report(&app_handle, &report),
+            _ => {}
+        });
+
+    fn open_crash_report(report: &std::path::Path) {
+        if let Err(e) = tauri_plugin_opener::open_path(report, None::<&str>) {
+            tracing::error!("Failed to open crash report: {}", e);
+        }
+    }
+
+    fn copy_crash_report(app: &tauri::AppHandle, report: &std::path::Path) {
+        match fs::read_to_string(report) {
+            Ok(contents) => {
+                if let Err(e) = app.clipboard().write_text(contents) {
+                    tracing::error!("Failed to copy crash report: {}", e);
+                }
+            }
+            Err(e) => tracing::error!("Failed to read crash report: {}", e),
+        }
+    }
 }
 
-// Secure storage commands backed by the OS credential vault.
+// Tauri command to report which providers have a key, with a masked preview
 #[tauri::command]
-fn secure_storage_get(key: String) -> Result<String, String> {
-    keyring_entry(&key)?.get_password()
-        .map_err(|e| format!("Could not read credential '{}': {}", key, e))
+fn get_api_key_status(
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<std::collections::BTreeMap<llm::Provider, api_keys::ApiKeyStatus>, AppError> {
+    api_keys::status(&credentials)
 }
 
+// Tauri command to store a provider key in a profile; it is never returned to the interface
 #[tauri::command]
-fn secure_storage_set(key: String, value: String) -> Result<(), String> {
-    keyring_entry(&key)?.set_password(&value)
-        .map_err(|e| format!("Could not save credential '{}': {}", key, e))
+fn set_api_key(
+    provider: llm::Provider,
+    key: String,
+    profile: Option<String>,
+    credentials: tauri::State<credentials::Credentials>,
+    policy: tauri::State<policy::Policy>,
+) -> Result<(), AppError> {
+    policy.check_provider(provider)?;
+    api_keys::set(&credentials, provider, profile.as_deref(), &key)
+}
+
+// Tauri command to forget a provider key profile
+#[tauri::command]
+fn remove_api_key(
+    provider: llm::Provider,
+    profile: Option<String>,
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<(), AppError> {
+    api_keys::remove(&credentials, provider, profile.as_deref())
+}
+
+// Tauri command to choose which key profile signs requests for a provider
+#[tauri::command]
+fn set_active_key_profile(
+    provider: llm::Provider,
+    profile: String,
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<(), AppError> {
+    api_keys::set_active(&credentials, provider, &profile)
+}
+
+// Helper function to correct text with the stored key, within the administrator policy, keeping
+// the personal dictionary's terms. Repeated requests and unchanged paragraphs are answered from
+// `cache` when one is given.
+async fn correct_with_stored_key(
+    request: &llm::CorrectionRequest,
+    credentials: &credentials::Credentials,
+    client: &llm::LlmClient,
+    policy: &policy::Policy,
+    cache: Option<&cache::CorrectionCache>,
+) -> Result<llm::CorrectionResult, AppError> {
+    let provider = llm::provider_for_model(&request.model)
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unknown model '{}'", request.model)))?;
+    policy.check_model(&request.model, provider)?;
+    check_style_override(policy, request.writing_style.as_deref())?;
+    if let Some(result) = cache.and_then(|cache| cache.get(request)) {
+        tracing::info!(provider = provider.label(), "Answered a correction from the cache");
+        return Ok(llm::CorrectionResult::from_cache(result));
+    }
+    let signing = api_keys::get(credentials, provider, request.profile.as_deref())?;
+    let mut result = match cache {
+        Some(cache) => incremental::correct(client, &signing.key, request, cache, None).await?,
+        None => client.correct(&signing.key, request).await?,
+    };
+    result.profile = Some(signing.profile);
+    result.result = request.dictionary.enforce(&request.text, &result.result);
+    remember_correction(cache, request, &result);
+    Ok(result)
 }
 
+// Helper function to cache a finished correction; one cut short by the token limit is not kept
+fn remember_correction(cache: Option<&cache::CorrectionCache>, request: &llm::CorrectionRequest, result: &llm::CorrectionResult) {
+    let Some(cache) = cache else {
+        return;
+    };
+    if !result.is_complete() {
+        return;
+    }
+    if let Err(e) = cache.insert(request, &result.result) {
+        tracing::warn!("Failed to cache a correction: {}", e);
+    }
+}
+
+// Tauri command to correct text with the stored key for the requested model
 #[tauri::command]
-fn secure_storage_remove(key: String) -> Result<(), String> {
-    keyring_entry(&key)?.delete_credential()
-        .map_err(|e| format!("Could not remove credential '{}': {}", key, e))
+async fn correct_text(
+    mut request: llm::CorrectionRequest,
+    credentials: tauri::State<'_, credentials::Credentials>,
+    client: tauri::State<'_, llm::LlmClient>,
+    policy: tauri::State<'_, policy::Policy>,
+    cache: tauri::State<'_, cache::CorrectionCache>,
+    dictionary: tauri::State<'_, dictionary::Dictionary>,
+) -> Result<llm::CorrectionResult, AppError> {
+    request.dictionary = dictionary.terms();
+    correct_with_stored_key(&request, &credentials, &client, &policy, Some(&cache)).await
+}
+
+// Tauri command to report whether the correction cache is on and how much it holds
+#[tauri::command]
+fn get_correction_cache(cache: tauri::State<cache::CorrectionCache>) -> Result<cache::CacheStatus, AppError> {
+    Ok(cache.status())
+}
+
+// Tauri command to turn the correction cache on or off; off also empties it
+#[tauri::command]
+fn set_correction_cache_enabled(enabled: bool, cache: tauri::State<cache::CorrectionCache>) -> Result<cache::CacheStatus, AppError> {
+    cache.set_enabled(enabled)?;
+    tracing::info!(enabled, "Correction cache setting changed");
+    Ok(cache.status())
+}
+
+// Tauri command to forget every cached correction
+#[tauri::command]
+fn clear_correction_cache(cache: tauri::State<cache::CorrectionCache>) -> Result<cache::CacheStatus, AppError> {
+    cache.clear()?;
+    Ok(cache.status())
+}
+
+// Tauri command to read the personal dictionary
+#[tauri::command]
+fn get_dictionary(dictionary: tauri::State<dictionary::Dictionary>) -> Result<dictionary::Terms, AppError> {
+    Ok(dictionary::Terms::clone(&dictionary.terms()))
+}
+
+// Tauri command to replace the personal dictionary; blank and repeated terms are dropped
+#[tauri::command]
+fn set_dictionary(
+    terms: dictionary::Terms,
+    dictionary: tauri::State<dictionary::Dictionary>,
+) -> Result<dictionary::Terms, AppError> {
+    dictionary.set(terms)
+}
+
+// Tauri command to add the terms of a plain word list file to the personal dictionary
+#[tauri::command]
+fn import_dictionary(
+    path: std::path::PathBuf,
+    dictionary: tauri::State<dictionary::Dictionary>,
+) -> Result<dictionary::Terms, AppError> {
+    let terms = dictionary.import(&path)?;
+    tracing::info!(protected = terms.protected.len(), spellings = terms.spellings.len(), "Word list imported");
+    Ok(terms)
+}
+
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CorrectionPartial<'a> {
+    stream_id: &'a str,
+    text: &'a str,
+}
+
+// Tauri command to correct text while sending the text so far as "correction-partial" events.
+// `stream_id` names the correction for those events and for cancel_correction.
+#[tauri::command]
+async fn correct_text_streaming(
+    app: tauri::AppHandle,
+    stream_id: String,
+    mut request: llm::CorrectionRequest,
+    credentials: tauri::State<'_, credentials::Credentials>,
+    client: tauri::State<'_, llm::LlmClient>,
+    policy: tauri::State<'_, policy::Policy>,
+    streams: tauri::State<'_, llm::CorrectionStreams>,
+    cache: tauri::State<'_, cache::CorrectionCache>,
+    dictionary: tauri::State<'_, dictionary::Dictionary>,
+) -> Result<llm::CorrectionResult, AppError> {
+    request.dictionary = dictionary.terms();
+    let provider = llm::provider_for_model(&request.model)
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unknown model '{}'", request.model)))?;
+    policy.check_model(&request.model, provider)?;
+    check_style_override(&policy, request.writing_style.as_deref())?;
+    if let Some(result) = cache.get(&request) {
+        tracing::info!(provider = provider.label(), "Answered a correction from the cache");
+        return Ok(llm::CorrectionResult::from_cache(result));
+    }
+    let signing = api_keys::get(&credentials, provider, request.profile.as_deref())?;
+
+    let partial = |text: &str| {
+        if let Err(e) = app.emit_to("main", "correction-partial", CorrectionPartial { stream_id: &stream_id, text }) {
+            tracing::warn!("Failed to send partial correction: {}", e);
+        }
+    };
+    let outcome = streams
+        .run(&stream_id, incremental::correct(&client, &signing.key, &request, &cache, Some(&partial)))
+        .await;
+
+    let Ok(result) = outcome else {
+        tracing::info!("Streamed correction cancelled");
+        return Err(AppError::Cancelled("The correction was cancelled".to_string()));
+    };
+    let mut result = result?;
+    result.profile = Some(signing.profile);
+    result.result = request.dictionary.enforce(&request.text, &result.result);
+    remember_correction(Some(&cache), &request, &result);
+    Ok(result)
+}
+
+// Tauri command to stop a streamed correction; the response so far is discarded
+#[tauri::command]
+fn cancel_correction(stream_id: String, streams: tauri::State<llm::CorrectionStreams>) -> Result<(), AppError> {
+    streams.cancel(&stream_id);
+    Ok(())
+}
+
+// Tauri command to correct Markdown and text files, reporting each file as a "batch-progress" event
+#[tauri::command]
+async fn correct_files(
+    app: tauri::AppHandle,
+    mut request: batch::BatchRequest,
+    credentials: tauri::State<'_, credentials::Credentials>,
+    client: tauri::State<'_, llm::LlmClient>,
+    policy: tauri::State<'_, policy::Policy>,
+    cache: tauri::State<'_, cache::CorrectionCache>,
+    dictionary: tauri::State<'_, dictionary::Dictionary>,
+) -> Result<batch::BatchSummary, AppError> {
+    request.dictionary = dictionary.terms();
+    let provider = llm::provider_for_model(&request.model)
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unknown model '{}'", request.model)))?;
+    policy.check_model(&request.model, provider)?;
+    check_style_override(&policy, request.writing_style.as_deref())?;
+    let files = batch::collect_files(&request.paths)?;
+    tracing::info!(files = files.len(), output = ?request.output, "Batch correction started");
+
+    let (request, credentials, client, policy, cache) = (&request, credentials.inner(), client.inner(), policy.inner(), cache.inner());
+    let correct = |text: String| async move {
+        correct_with_stored_key(&request.correction(text), credentials, client, policy, Some(cache)).await
+    };
+    let progress = |progress: batch::BatchProgress<'_>| {
+        if let Err(e) = app.emit("batch-progress", progress) {
+            tracing::warn!("Failed to report batch progress: {}", e);
+        }
+    };
+    Ok(batch::run(files, request.output, correct, progress).await)
+}
+
+// Helper function to check a writing style that replaces the selected one for a single correction
+fn check_style_override(policy: &policy::Policy, style: Option<&str>) -> Result<(), AppError> {
+    let Some(style) = style else {
+        return Ok(());
+    };
+    if !prompts::WRITING_STYLES.contains(&style) {
+        return Err(AppError::InvalidArgument(format!("Unknown writing style '{}'", style)));
+    }
+    policy.check_locked("writingStyle", policy.locked.writing_style.as_deref(), style)
 }
 
-/// Imports credentials written by pre-1.1 releases. A file is removed only
-/// after its value is safely committed to the system credential vault.
+// Tells D-Bus listeners that a clipboard correction started (`None`) or finished
+// with the given success. Other platforms have no bus to tell.
+fn signal_clipboard_correction(app: &tauri::AppHandle, finished: Option<bool>) {
+    #[cfg(target_os = "linux")]
+    {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            let Some(bus) = app.try_state::<dbus::Bus>() else {
+                return;
+            };
+            match finished {
+                None => bus.correction_started(dbus::SOURCE_CLIPBOARD).await,
+                Some(success) => bus.correction_finished(dbus::SOURCE_CLIPBOARD, success).await,
+            }
+        });
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = (app, finished);
+}
+
+// Tauri command the webview calls when a clipboard correction fails, so D-Bus listeners learn it ended
 #[tauri::command]
-fn migrate_legacy_key_files(app: tauri::AppHandle) -> Result<u32, String> {
-    let legacy_dir = app.path().app_data_dir()
-        .map_err(|e| format!("Failed to locate app data: {}", e))?
-        .join(".keys");
-    if !legacy_dir.exists() { return Ok(0); }
-
-    let mut migrated = 0;
-    for entry in fs::read_dir(&legacy_dir).map_err(|e| format!("Failed to read legacy keys: {}", e))? {
-        let entry = entry.map_err(|e| format!("Failed to read legacy key entry: {}", e))?;
-        let path = entry.path();
-        if path.extension().and_then(|ext| ext.to_str()) != Some("dat") { continue; }
-        let encoded = fs::read_to_string(&path).map_err(|e| format!("Failed to read legacy credential: {}", e))?;
-        let value = String::from_utf8(general_purpose::STANDARD.decode(encoded.trim()).map_err(|e| format!("Failed to decode legacy credential: {}", e))?)
-            .map_err(|e| format!("Failed to decode legacy credential text: {}", e))?;
-        let key = path.file_stem().and_then(|stem| stem.to_str()).ok_or("Invalid legacy credential filename")?;
-        keyring_entry(key)?.set_password(&value).map_err(|e| format!("Could not migrate credential '{}': {}", key, e))?;
-        fs::remove_file(&path).map_err(|e| format!("Credential migrated, but legacy file could not be removed: {}", e))?;
-        migrated += 1;
+fn clipboard_correction_failed(app: tauri::AppHandle) -> Result<(), AppError> {
+    signal_clipboard_correction(&app, Some(false));
+    Ok(())
+}
+
+// Serves the D-Bus interface from the app's state and stored keys
+#[cfg(target_os = "linux")]
+struct DbusBackend {
+    app: tauri::AppHandle,
+}
+
+#[cfg(target_os = "linux")]
+impl dbus::Backend for DbusBackend {
+    fn correct(&self, text: String, style: Option<String>) -> dbus::BoxFuture<'_, Result<String, AppError>> {
+        Box::pin(async move {
+            let policy = self.app.state::<policy::Policy>();
+            check_style_override(&policy, style.as_deref())?;
+            let state = self.app.state::<AppState>();
+            let request = llm::CorrectionRequest {
+                text,
+                model: state.current_model.lock().unwrap().clone(),
+                writing_style: Some(style.unwrap_or_else(|| state.current_style.lock().unwrap().clone())),
+                custom_rules: None,
+                language: None,
+                temperature: None,
+                profile: None,
+                dictionary: self.app.state::<dictionary::Dictionary>().terms(),
+            };
+            let credentials = self.app.state::<credentials::Credentials>();
+            let client = self.app.state::<llm::LlmClient>();
+            let cache = self.app.state::<cache::CorrectionCache>();
+            Ok(correct_with_stored_key(&request, &credentials, &client, &policy, Some(&cache)).await?.result)
+        })
+    }
+
+    fn correct_clipboard(&self, style: Option<String>) -> Result<(), AppError> {
+        check_style_override(&self.app.state::<policy::Policy>(), style.as_deref())?;
+        run_actions(&self.app, vec![cli::Action::CorrectClipboard { style }]);
+        Ok(())
+    }
+
+    fn settings(&self) -> dbus::Settings {
+        let state = self.app.state::<AppState>();
+        let settings = dbus::Settings {
+            model: state.current_model.lock().unwrap().clone(),
+            style: state.current_style.lock().unwrap().clone(),
+            locale: state.locale.lock().unwrap().clone(),
+            shortcut: current_shortcut(&state).accelerator(),
+            shortcut_registered: *state.shortcut_registered.lock().unwrap(),
+            auto_paste_enabled: *state.auto_paste_enabled.lock().unwrap(),
+            sound_enabled: *state.sound_enabled.lock().unwrap(),
+        };
+        settings
+    }
+}
+
+// Corrects text with the stored keys for the modes that run without the app: `correctify lsp` and the
+// browser's native messaging host. Unset options fall back to what was last selected in the app.
+struct StandaloneCorrector {
+    runtime: tokio::runtime::Runtime,
+    credentials: credentials::Credentials,
+    client: llm::LlmClient,
+    policy: policy::Policy,
+    selected: native_host::SelectedSettings,
+    dictionary: Arc<dictionary::Terms>,
+}
+
+impl lsp::Corrector for StandaloneCorrector {
+    fn correct(&self, text: &str, options: &lsp::Options) -> Result<String, AppError> {
+        let (defaults, selected) = (&self.policy.defaults, &self.selected);
+        let style = options
+            .style
+            .clone()
+            .or_else(|| self.policy.locked.writing_style.clone())
+            .or_else(|| selected.style.clone())
+            .or_else(|| defaults.writing_style.clone());
+        check_style_override(&self.policy, style.as_deref())?;
+        let model = options.model.clone().or_else(|| selected.model.clone()).or_else(|| defaults.model.clone());
+        let request = llm::CorrectionRequest {
+            text: text.to_string(),
+            model: model.unwrap_or_else(|| "gpt-5.4-mini".to_string()),
+            writing_style: Some(style.unwrap_or_else(|| "grammar".to_string())),
+            custom_rules: options.custom_rules.clone(),
+            language: options.language.clone(),
+            temperature: None,
+            profile: None,
+            dictionary: self.dictionary.clone(),
+        };
+        // The app may be writing the cache file at the same time, so standalone modes leave it alone
+        let result = self.runtime.block_on(correct_with_stored_key(&request, &self.credentials, &self.client, &self.policy, None))?;
+        Ok(result.result)
+    }
+}
+
+// Helper function to start a mode that runs without the app. stdout carries its protocol, so logs
+// only go to the file and stderr.
+fn start_standalone(mode: &str) -> StandaloneCorrector {
+    let Some(paths) = paths::AppPaths::resolve() else {
+        eprintln!("Failed to find the Correctify data directory");
+        std::process::exit(1);
+    };
+    if let Err(e) = logging::init(&paths.log_dir) {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), portable = paths.portable, mode, "Correctify starting without the app");
+
+    let runtime = match tokio::runtime::Builder::new_current_thread().enable_all().build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            tracing::error!("Failed to start the async runtime: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let policy = policy::Policy::load();
+    let credentials = credentials::Credentials::open(paths.data_dir.clone(), paths.portable);
+    unlock_standalone(&credentials);
+    StandaloneCorrector {
+        runtime,
+        credentials,
+        client: llm::LlmClient::with_endpoints(policy.endpoints.clone()),
+        selected: native_host::SelectedSettings::load(&paths.data_dir),
+        dictionary: dictionary::Dictionary::open(&paths.data_dir).terms(),
+        policy,
+    }
+}
+
+// Helper function to open the encrypted credential file for a mode without the app. stdin carries the
+// protocol, so the passphrase comes from CORRECTIFY_PASSPHRASE; without it the mode stops at startup
+// instead of failing every request.
+fn unlock_standalone(credentials: &credentials::Credentials) {
+    let status = credentials.status();
+    if !status.locked {
+        return;
+    }
+    let failure = if !status.vault_exists {
+        "No API keys are stored yet. Add one in Correctify first.".to_string()
+    } else {
+        match std::env::var(PASSPHRASE_VARIABLE) {
+            Ok(passphrase) => match credentials.unlock(&passphrase) {
+                Ok(()) => return,
+                Err(e) => format!("Failed to unlock the encrypted credential file: {}", e.details()),
+            },
+            Err(_) => format!("The API keys are in the encrypted credential file. Set {} to its passphrase.", PASSPHRASE_VARIABLE),
+        }
+    };
+    tracing::error!("{}", failure);
+    eprintln!("{}", failure);
+    std::process::exit(1);
+}
+
+/// Runs `correctify lsp`: serves grammar diagnostics to an editor over stdio until it exits.
+pub fn run_lsp() {
+    let corrector = start_standalone("lsp");
+    let clean_exit = match lsp::serve(std::io::stdin(), std::io::stdout(), Arc::new(corrector)) {
+        Ok(clean_exit) => clean_exit,
+        Err(e) => {
+            tracing::error!("The language server failed: {}", e);
+            false
+        }
+    };
+    tracing::info!(clean_exit, "Correctify language server stopped");
+    std::process::exit(if clean_exit { 0 } else { 1 });
+}
+
+/// Whether a browser started this process to talk to an extension.
+pub fn launched_by_browser(argv: &[String]) -> bool {
+    native_host::launched_by_browser(argv)
+}
+
+/// Runs the native messaging host until the browser disconnects.
+pub fn run_native_host() {
+    let corrector = start_standalone("native-host");
+    if let Err(e) = native_host::serve(std::io::stdin(), std::io::stdout(), &corrector) {
+        tracing::error!("The native messaging host failed: {}", e);
+        std::process::exit(1);
     }
-    if fs::read_dir(&legacy_dir).map_err(|e| format!("Failed to inspect legacy key directory: {}", e))?.next().is_none() {
-        let _ = fs::remove_dir(&legacy_dir);
+}
+
+/// Runs `correctify native-host install <extension-id>...` or `correctify native-host uninstall`.
+pub fn run_native_host_setup(args: &[String]) {
+    let Some(paths) = paths::AppPaths::resolve() else {
+        eprintln!("Failed to find the Correctify data directory");
+        std::process::exit(1);
+    };
+    let result = match args.first().map(String::as_str) {
+        Some("install") => std::env::current_exe()
+            .map_err(|e| AppError::NotFound(format!("Failed to find the Correctify executable: {}", e)))
+            .and_then(|executable| native_host::install(&executable, &paths.data_dir, &args[1..])),
+        Some("uninstall") => native_host::uninstall(&paths.data_dir),
+        _ => Err(AppError::InvalidArgument(
+            "Usage: correctify native-host install <extension-id>... | correctify native-host uninstall".to_string(),
+        )),
+    };
+    match result {
+        Ok(locations) if locations.is_empty() => println!("No browser to register with"),
+        Ok(locations) => locations.iter().for_each(|location| println!("{}", location)),
+        Err(e) => {
+            eprintln!("{}", e.details());
+            std::process::exit(1);
+        }
     }
-    Ok(migrated)
 }
 
-/// Consolidates pre-v2 native credential records into one Keychain item. This
-/// avoids a separate Keychain approval prompt for each provider on startup.
+// Tauri command to check a key with the provider before it is saved
 #[tauri::command]
-fn migrate_legacy_keyring_entries(key: String) -> Result<u32, String> {
-    let legacy_keys = [
-        ("openai", "correctify_openai-api-key"),
-        ("anthropic", "correctify_anthropic-api-key"),
-        ("mistral", "correctify_mistral-api-key"),
-        ("openrouter", "correctify_openrouter-api-key"),
-    ];
-    let destination = keyring_entry(&key)?;
-    if destination.get_password().is_ok() { return Ok(0); }
-
-    let mut values = serde_json::Map::new();
-    let mut entries_to_delete = Vec::new();
-    let mut migrated = 0;
-    for (provider, legacy_key) in legacy_keys {
-        let entry = keyring_entry(legacy_key)?;
-        if let Ok(value) = entry.get_password() {
-            values.insert(provider.to_string(), Value::String(value));
-            entries_to_delete.push((legacy_key, entry));
-            migrated += 1;
+async fn verify_api_key(
+    provider: llm::Provider,
+    key: String,
+    client: tauri::State<'_, llm::LlmClient>,
+    policy: tauri::State<'_, policy::Policy>,
+) -> Result<llm::KeyVerification, AppError> {
+    policy.check_provider(provider)?;
+    client.verify_key(provider, &key).await
+}
+
+// Clipboard text handed to the webview, with the writing style a link asked for
+#[derive(Clone, serde::Serialize)]
+struct ClipboardCorrection {
+    text: String,
+    style: Option<String>,
+}
+
+// Sends the clipboard text to the webview for correction, as the global shortcut does.
+// `style` replaces the selected writing style for this correction only.
+fn correct_clipboard(app: &tauri::AppHandle, style: Option<String>) {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    use tauri_plugin_notification::NotificationExt;
+
+    let state = app.state::<AppState>();
+    let sound_enabled = *state.sound_enabled.lock().unwrap();
+
+    match app.clipboard().read_text() {
+        Ok(text) => {
+            if text.is_empty() {
+                tracing::info!("Clipboard is empty, nothing to correct");
+                #[cfg(target_os = "macos")]
+                let copy_instruction = "Please copy text first (Cmd+C), then use Cmd+Shift+]";
+
+                #[cfg(not(target_os = "macos"))]
+                let copy_instruction = "Please copy text first (Ctrl+C), then use Ctrl+Shift+]";
+
+                let _ = app.notification()
+                    .builder()
+                    .title("Correctify")
+                    .body(copy_instruction)
+                    .show();
+
+                // Play empty sound
+                play_sound("empty", sound_enabled);
+                return;
+            }
+
+            // Emit event to frontend with text to correct
+            tracing::info!(chars = text.chars().count(), "Sending clipboard text for correction");
+            let current_style = style.clone().unwrap_or_else(|| state.current_style.lock().unwrap().clone());
+            let _ = app.emit("correct-clipboard-text", ClipboardCorrection { text, style });
+            signal_clipboard_correction(app, None);
+
+            // Get current model from state
+            let current_model = state.current_model.lock().unwrap().clone();
+            let locale = state.locale.lock().unwrap().clone();
+            let style_label = style_to_label(&current_style, &locale);
+
+            // Build notification body with model and style
+            let mut notification_body = get_translation(&locale, "notifications.processing");
+            let model_label = get_translation(&locale, "notifications.model");
+            let style_label_key = get_translation(&locale, "notifications.style");
+            notification_body.push_str(&format!("\n{}: {}", model_label, current_model));
+            notification_body.push_str(&format!("\n{}: {}", style_label_key, style_label));
+
+            // Show notification that we're processing
+            let title = get_translation(&locale, "notifications.title");
+            let _ = app.notification()
+                .builder()
+                .title(&title)
+                .body(&notification_body)
+                .show();
+
+            // Play processing sound
+            play_sound("processing", sound_enabled);
         }
+        Err(e) => {
+            tracing::error!("Failed to read clipboard: {}", e);
+        }
+    }
+}
+
+// Helper function to bring the main window to the front
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
     }
-    if migrated > 0 {
-        destination.set_password(&Value::Object(values).to_string())
-            .map_err(|e| format!("Could not consolidate credentials: {}", e))?;
-        for (legacy_key, entry) in entries_to_delete {
-            entry.delete_credential().map_err(|e| format!("Could not remove migrated credential '{}': {}", legacy_key, e))?;
+}
+
+// Runs command-line and deep-link actions, queueing them until the webview listens for their events
+fn run_actions(app: &tauri::AppHandle, actions: Vec<cli::Action>) {
+    use tauri_plugin_notification::NotificationExt;
+
+    for action in app.state::<cli::PendingActions>().defer(actions) {
+        tracing::info!(?action, "Running command-line action");
+        match action {
+            cli::Action::Show => show_main_window(app),
+            cli::Action::OpenSettings => {
+                show_main_window(app);
+                let _ = app.emit("open-settings", ());
+            }
+            cli::Action::CorrectClipboard { style } => {
+                crash::record("clipboard correction from the command line");
+                if let Err(e) = check_style_override(&app.state::<policy::Policy>(), style.as_deref()) {
+                    tracing::warn!("Refusing the requested writing style: {}", e.details());
+                    let _ = app.notification().builder().title("Correctify").body(e.details()).show();
+                    continue;
+                }
+                correct_clipboard(app, style);
+            }
+            // Presets live in the webview, which applies them
+            cli::Action::ApplyPreset(id) => {
+                let _ = app.emit("apply-preset", id);
+            }
         }
     }
-    Ok(migrated)
+}
+
+// Tauri command the webview calls once it listens for events, releasing queued command-line actions
+#[tauri::command]
+fn frontend_ready(app: tauri::AppHandle, pending: tauri::State<cli::PendingActions>) -> Result<(), AppError> {
+    let actions = pending.release();
+    run_actions(&app, actions);
+    Ok(())
+}
+
+// Tauri command to report where data is kept and whether this copy is portable
+#[tauri::command]
+fn get_app_paths(paths: tauri::State<paths::AppPaths>) -> Result<paths::AppPaths, AppError> {
+    Ok(paths.inner().clone())
+}
+
+// Tauri command to report the administrator policy, so the interface can disable locked settings
+#[tauri::command]
+fn get_policy(policy: tauri::State<policy::Policy>) -> Result<Option<policy::Policy>, AppError> {
+    Ok(policy.is_managed().then(|| policy.inner().clone()))
+}
+
+// Tauri command to report which credential backend is in use and whether it is locked
+#[tauri::command]
+fn get_credential_status(credentials: tauri::State<credentials::Credentials>) -> Result<credentials::CredentialStatus, AppError> {
+    Ok(credentials.status())
+}
+
+// Tauri command to unlock the encrypted credential file, creating it on first use
+#[tauri::command]
+fn unlock_credential_store(passphrase: String, credentials: tauri::State<credentials::Credentials>) -> Result<(), AppError> {
+    credentials.unlock(&passphrase)
+}
+
+// Tauri command to move stored credentials to another backend and keep using it
+#[tauri::command]
+fn migrate_credentials(
+    target: credentials::Backend,
+    passphrase: Option<String>,
+    credentials: tauri::State<credentials::Credentials>,
+) -> Result<u32, AppError> {
+    credentials.migrate(target, passphrase.as_deref())
+}
+
+// Tauri command to bring stored keys, settings, presets and usage history up to
+// the current schema. `local` is the webview's localStorage, which it updates
+// from the returned report
+#[tauri::command]
+fn run_migrations(
+    local: std::collections::BTreeMap<String, String>,
+    credentials: tauri::State<credentials::Credentials>,
+    paths: tauri::State<paths::AppPaths>,
+) -> Result<migrations::MigrationReport, AppError> {
+    Ok(credentials.with_legacy(|store, legacy| migrations::run(store, legacy, &paths.data_dir, local)))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    crash::install();
+
     // Initialize app state with default values
     let app_state = AppState {
         sound_enabled: Arc::new(Mutex::new(true)), // Default: sound enabled
@@ -701,21 +1622,41 @@ pub fn run() {
         shortcut_modifier: Arc::new(Mutex::new("CmdOrCtrl+Shift".to_string())), // Default: Cmd+Shift on Mac, Ctrl+Shift on Win/Linux
         auto_paste_enabled: Arc::new(Mutex::new(false)), // Default: auto-paste disabled
         auto_paste_in_flight: Arc::new(Mutex::new(false)),
+        shortcut_capturing: Arc::new(Mutex::new(false)),
+        shortcut_registered: Arc::new(Mutex::new(false)),
         current_model: Arc::new(Mutex::new("gpt-5.4-mini".to_string())), // Recommended default model
         current_style: Arc::new(Mutex::new("grammar".to_string())), // Default style
         locale: Arc::new(Mutex::new("en".to_string())), // Default locale: English
     };
 
-    tauri::Builder::default()
+    let portable_root = paths::AppPaths::portable_root();
+
+    let builder = tauri::Builder::default()
+        // Must come first: a second launch forwards its arguments here and exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
+            let mut actions = cli::parse(&argv);
+            if actions.is_empty() {
+                actions.push(cli::Action::Show);
+            }
+            run_actions(app, actions);
+        }))
         .manage(app_state)
+        .manage(cli::PendingActions::default())
+        .manage(llm::CorrectionStreams::default())
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .plugin(tauri_plugin_notification::init())
-        .plugin(tauri_plugin_os::init())
-        .plugin(tauri_plugin_autostart::init(
+        .plugin(tauri_plugin_os::init());
+    // Portable copies never register themselves to start at login
+    let builder = match portable_root {
+        Some(_) => builder,
+        None => builder.plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
             Some(vec![]),
-        ))
+        )),
+    };
+    builder
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
@@ -723,17 +1664,20 @@ pub fn run() {
             tauri_plugin_global_shortcut::Builder::new()
                 .with_handler(|app, _shortcut, event| {
                     use tauri_plugin_global_shortcut::ShortcutState;
-                    use tauri_plugin_clipboard_manager::ClipboardExt;
+                    #[cfg(target_os = "macos")]
                     use tauri_plugin_notification::NotificationExt;
 
                     if event.state == ShortcutState::Pressed {
+                        let _span = tracing::info_span!("shortcut_correction").entered();
+                        tracing::info!("Global shortcut pressed");
+                        crash::record("global shortcut pressed");
+
                         // Get settings state
                         let state = app.state::<AppState>();
                         #[cfg(target_os = "macos")]
                         {
                             refresh_app_nap_activity();
                         }
-                        let sound_enabled = *state.sound_enabled.lock().unwrap();
                         let auto_paste_enabled = *state.auto_paste_enabled.lock().unwrap();
 
                         // If auto-paste is enabled, simulate Cmd+C/Ctrl+C to copy selected text
@@ -754,6 +1698,7 @@ pub fn run() {
                                 };
 
                                 if !has_permission {
+                                    tracing::warn!("Accessibility permission not granted");
                                     // Show notification to user
                                     let state = app.state::<AppState>();
                                     let locale = state.locale.lock().unwrap().clone();
@@ -770,6 +1715,7 @@ pub fn run() {
                             }
 
                             // Simulate copy shortcut
+                            tracing::debug!("Copying the current selection");
                             match Enigo::new(&Settings::default()) {
                                 Ok(mut enigo) => {
                                     #[cfg(target_os = "macos")]
@@ -791,62 +1737,14 @@ pub fn run() {
                                     // Wait briefly for clipboard to populate
                                     thread::sleep(Duration::from_millis(100));
                                 }
-                                Err(_) => return,
-                            }
-                        }
-
-                        // Read from clipboard
-                        match app.clipboard().read_text() {
-                            Ok(text) => {
-                                if text.is_empty() {
-                                    #[cfg(target_os = "macos")]
-                                    let copy_instruction = "Please copy text first (Cmd+C), then use Cmd+Shift+]";
-
-                                    #[cfg(not(target_os = "macos"))]
-                                    let copy_instruction = "Please copy text first (Ctrl+C), then use Ctrl+Shift+]";
-
-                                    let _ = app.notification()
-                                        .builder()
-                                        .title("Correctify")
-                                        .body(copy_instruction)
-                                        .show();
-
-                                    // Play empty sound
-                                    play_sound("empty", sound_enabled);
+                                Err(e) => {
+                                    tracing::error!("Failed to create Enigo instance: {:?}", e);
                                     return;
                                 }
-
-                                // Emit event to frontend with text to correct
-                                let _ = app.emit("correct-clipboard-text", text.clone());
-
-                                // Get current model and style from state
-                                let current_model = state.current_model.lock().unwrap().clone();
-                                let current_style = state.current_style.lock().unwrap().clone();
-                                let locale = state.locale.lock().unwrap().clone();
-                                let style_label = style_to_label(&current_style, &locale);
-
-                                // Build notification body with model and style
-                                let mut notification_body = get_translation(&locale, "notifications.processing");
-                                let model_label = get_translation(&locale, "notifications.model");
-                                let style_label_key = get_translation(&locale, "notifications.style");
-                                notification_body.push_str(&format!("\n{}: {}", model_label, current_model));
-                                notification_body.push_str(&format!("\n{}: {}", style_label_key, style_label));
-
-                                // Show notification that we're processing
-                                let title = get_translation(&locale, "notifications.title");
-                                let _ = app.notification()
-                                    .builder()
-                                    .title(&title)
-                                    .body(&notification_body)
-                                    .show();
-
-                                // Play processing sound
-                                play_sound("processing", sound_enabled);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to read clipboard: {}", e);
                             }
                         }
+
+                        correct_clipboard(app, None);
                     }
                 })
                 .build(),
@@ -856,23 +1754,96 @@ pub fn run() {
             set_sound_enabled,
             get_sound_enabled,
             update_shortcut,
+            check_shortcut,
+            begin_shortcut_capture,
+            finish_shortcut_capture,
+            get_default_shortcut,
+            get_shortcut_status,
             get_shortcut_key,
             get_shortcut_modifier,
             play_sound_in_app,
             set_auto_paste_enabled,
             get_auto_paste_enabled,
-            secure_storage_get,
-            secure_storage_set,
-            secure_storage_remove,
-            migrate_legacy_key_files,
-            migrate_legacy_keyring_entries,
+            get_api_key_status,
+            set_api_key,
+            remove_api_key,
+            set_active_key_profile,
+            correct_text,
+            correct_text_streaming,
+            cancel_correction,
+            get_correction_cache,
+            set_correction_cache_enabled,
+            clear_correction_cache,
+            get_dictionary,
+            set_dictionary,
+            import_dictionary,
+            correct_files,
+            verify_api_key,
+            get_policy,
+            get_app_paths,
+            frontend_ready,
+            clipboard_correction_failed,
+            run_migrations,
+            get_credential_status,
+            unlock_credential_store,
+            migrate_credentials,
+            export_diagnostics,
+            export_backup,
+            preview_backup,
+            restore_backup,
             set_correction_settings,
             get_current_model,
             get_current_style,
             set_locale,
             get_locale
         ])
-        .setup(|app| {
+        .setup(move |app| {
+            let paths = match &portable_root {
+                Some(root) => paths::AppPaths::portable(root),
+                None => {
+                    let data_dir = app.path().app_data_dir()?;
+                    let log_dir = app.path().app_log_dir().unwrap_or_else(|_| data_dir.join("logs"));
+                    paths::AppPaths::installed(data_dir, log_dir)
+                }
+            };
+
+            // Logging must never keep Correctify from starting
+            if let Err(e) = logging::init(&paths.log_dir) {
+                eprintln!("Failed to initialize logging: {}", e);
+            }
+            tracing::info!(version = %app.package_info().version, portable = paths.portable, "Correctify starting");
+
+            // Reports stay local; offer the one left by a crash in the previous run
+            let crash_dir = paths.data_dir.join("crash-reports");
+            crash::set_report_dir(crash_dir.clone());
+            if let Some(report) = crash::take_pending(&crash_dir) {
+                offer_crash_report(app.handle(), report);
+            }
+            crash::record("startup");
+
+            // Probes the OS vault and falls back to the encrypted file when it does not answer
+            let credentials = credentials::Credentials::open(paths.data_dir.clone(), paths.portable);
+            app.manage(credentials);
+            app.manage(cache::CorrectionCache::open(&paths.data_dir));
+            app.manage(dictionary::Dictionary::open(&paths.data_dir));
+
+            // Locked settings override the defaults before the webview reports its own
+            let policy = policy::Policy::load();
+            {
+                let state = app.state::<AppState>();
+                if let Some(enabled) = policy.locked.auto_paste_enabled {
+                    *state.auto_paste_enabled.lock().unwrap() = enabled;
+                }
+                if let Some(enabled) = policy.locked.sound_enabled {
+                    *state.sound_enabled.lock().unwrap() = enabled;
+                }
+                if let Some(style) = policy.locked.writing_style.as_ref().or(policy.defaults.writing_style.as_ref()) {
+                    *state.current_style.lock().unwrap() = style.clone();
+                }
+            }
+            app.manage(llm::LlmClient::with_endpoints(policy.endpoints.clone()));
+            app.manage(policy);
+
             // Set activation policy to Accessory on macOS to hide dock icon
             #[cfg(target_os = "macos")]
             {
@@ -883,7 +1854,7 @@ pub fn run() {
             #[cfg(target_os = "macos")]
             {
                 refresh_app_nap_activity();
-                println!("macOS App Nap disabled");
+                tracing::debug!("macOS App Nap disabled");
             }
 
             // Left-click opens Correctify; the contextual controls remain on right-click.
@@ -899,6 +1870,7 @@ pub fn run() {
             let shortcut_status_item = MenuItemBuilder::with_id("shortcut_status", "Shortcut: Cmd/Ctrl+Shift+]")
                 .enabled(false)
                 .build(app)?;
+            app.manage(TrayItems { shortcut_status: shortcut_status_item.clone() });
             let tray_quit_item = MenuItemBuilder::with_id("tray_quit", "Quit Correctify")
                 .build(app)?;
             let tray_menu = MenuBuilder::new(app)
@@ -921,6 +1893,7 @@ pub fn run() {
                 .on_tray_icon_event(move |_tray, event| {
                     if let TrayIconEvent::Click { button, button_state, .. } = event {
                         if button == MouseButton::Left && button_state == MouseButtonState::Up {
+                            crash::record("window opened from tray");
                             if let Some(window) = app_handle.get_webview_window("main") {
                                 let _ = window.show();
                                 let _ = window.set_focus();
@@ -943,7 +1916,10 @@ pub fn run() {
                             }
                             let _ = app.emit("open-settings", ());
                         }
-                        "tray_quit" => app.exit(0),
+                        "tray_quit" => {
+                            crash::record("quit from tray");
+                            app.exit(0)
+                        }
                         _ => {}
                     }
                 })
@@ -951,34 +1927,40 @@ pub fn run() {
                 .expect("Failed to build tray icon");
 
 
-            // Register the global shortcut with configurable modifier
-            use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
-            let state = app.state::<AppState>();
-            let shortcut_modifier = state.shortcut_modifier.lock().unwrap();
-            let shortcut_key = state.shortcut_key.lock().unwrap();
-            let modifier_platform = convert_modifier_to_platform(&*shortcut_modifier);
-            let shortcut_str = format!("{}+{}", modifier_platform, *shortcut_key);
-            let shortcut = shortcut_str.parse::<Shortcut>().unwrap();
-            app.global_shortcut().register(shortcut)
-                .expect("Failed to register global shortcut");
-            println!("Global shortcut registered: {}", shortcut_str);
-
-            // Get window for all platforms
-            let window = app.get_webview_window("main").unwrap();
+            // Register the global shortcut, falling back to a free combination
+            register_startup_shortcut(app.handle());
+
+            // The main window is created here rather than from the config, so a
+            // portable copy can keep its webview profile beside the executable
+            let window_config = app
+                .config()
+                .app
+                .windows
+                .iter()
+                .find(|config| config.label == "main")
+                .cloned()
+                .ok_or("The main window is missing from the configuration")?;
+            let mut window_builder = tauri::WebviewWindowBuilder::from_config(app.handle(), &window_config)?;
+            if let Some(webview_dir) = &paths.webview_dir {
+                window_builder = window_builder.data_directory(webview_dir.clone());
+            }
+            // Managed first: the webview may ask for the paths as soon as it loads
+            app.manage(paths);
+            let window = window_builder.build()?;
 
             // Apply window vibrancy effects based on platform
             #[cfg(target_os = "macos")]
             {
                 apply_vibrancy(&window, NSVisualEffectMaterial::HudWindow, None, None)
                     .expect("Failed to apply vibrancy on macOS");
-                println!("Applied macOS vibrancy effect");
+                tracing::debug!("Applied macOS vibrancy effect");
             }
 
             #[cfg(target_os = "windows")]
             {
                 apply_blur(&window, Some((18, 18, 18, 125)))
                     .expect("Failed to apply blur on Windows");
-                println!("Applied Windows blur effect");
+                tracing::debug!("Applied Windows blur effect");
             }
 
             #[cfg(debug_assertions)]
@@ -1111,6 +2093,59 @@ pub fn run() {
                 });
             }
 
+            // Linux has no installer to register the URL scheme, and Windows only has one in release builds.
+            // Portable copies leave the system alone.
+            #[cfg(any(target_os = "linux", all(debug_assertions, target_os = "windows")))]
+            if !app.state::<paths::AppPaths>().portable {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                if let Err(e) = app.deep_link().register_all() {
+                    tracing::warn!("Failed to register the {} URL scheme: {}", cli::SCHEME, e);
+                }
+            }
+
+            // macOS delivers opened links as events; elsewhere they arrive as arguments
+            #[cfg(target_os = "macos")]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                let app_handle = app.handle().clone();
+                app.deep_link().on_open_url(move |event| {
+                    let actions = event
+                        .urls()
+                        .iter()
+                        .filter_map(|url| {
+                            cli::parse_link(url.as_str())
+                                .inspect_err(|e| tracing::warn!("Ignoring link: {}", e.details()))
+                                .ok()
+                        })
+                        .collect();
+                    run_actions(&app_handle, actions);
+                });
+            }
+
+            // Scripts and desktop widgets drive Correctify over the session bus
+            #[cfg(target_os = "linux")]
+            {
+                let backend = Arc::new(DbusBackend { app: app.handle().clone() });
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let bus = match zbus::connection::Builder::session() {
+                        Ok(builder) => dbus::serve(builder, backend).await,
+                        Err(e) => Err(e),
+                    };
+                    match bus {
+                        Ok(bus) => {
+                            tracing::info!("D-Bus service available as {}", dbus::BUS_NAME);
+                            app_handle.manage(bus);
+                        }
+                        Err(e) => tracing::warn!("Failed to start the D-Bus service: {}", e),
+                    }
+                });
+            }
+
+            // Actions asked for on this launch's own command line
+            let actions = cli::parse(&std::env::args().collect::<Vec<_>>());
+            run_actions(app.handle(), actions);
+
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -0,0 +1,1270 @@
+//! Provider requests for corrections.
+//!
+//! API keys never leave the Rust process: the webview asks for a correction
+//! and gets text back. Code spans are swapped for opaque markers before the
+//! text reaches a model, and a response that damages a marker is refused.
+
+use crate::chunks;
+use crate::dictionary;
+use crate::error::AppError;
+use crate::prompts;
+use futures_util::future::{self, AbortHandle, Abortable, Aborted};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+// One request, and a whole document split into requests
+const MAX_INPUT_CHARACTERS: usize = 100_000;
+const MAX_DOCUMENT_CHARACTERS: usize = 1_000_000;
+const MAX_OUTPUT_TOKENS: u32 = 8_192;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// A streamed response may take longer as a whole, as long as it keeps arriving
+const STREAM_TIMEOUT: Duration = Duration::from_secs(300);
+const PARTIAL_INTERVAL: Duration = Duration::from_millis(100);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    OpenAI,
+    Anthropic,
+    Mistral,
+    OpenRouter,
+}
+
+impl Provider {
+    pub const ALL: [Provider; 4] = [Provider::OpenAI, Provider::Anthropic, Provider::Mistral, Provider::OpenRouter];
+
+    /// The name used in stored records and by the frontend.
+    pub fn id(self) -> &'static str {
+        match self {
+            Provider::OpenAI => "openai",
+            Provider::Anthropic => "anthropic",
+            Provider::Mistral => "mistral",
+            Provider::OpenRouter => "openrouter",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Provider::OpenAI => "OpenAI",
+            Provider::Anthropic => "Anthropic",
+            Provider::Mistral => "Mistral",
+            Provider::OpenRouter => "OpenRouter",
+        }
+    }
+
+    /// How many requests the client sends to the provider at once, kept
+    /// below its usual rate limits.
+    pub fn max_concurrent_requests(self) -> usize {
+        match self {
+            Provider::OpenAI => 4,
+            Provider::Anthropic => 3,
+            Provider::Mistral => 2,
+            // Free models allow about 20 requests a minute
+            Provider::OpenRouter => 1,
+        }
+    }
+
+    fn base_url(self) -> &'static str {
+        match self {
+            Provider::OpenAI => "https://api.openai.com/v1",
+            Provider::Anthropic => "https://api.anthropic.com/v1",
+            Provider::Mistral => "https://api.mistral.ai/v1",
+            Provider::OpenRouter => "https://openrouter.ai/api/v1",
+        }
+    }
+}
+
+// Mirrors the catalog in lib/models.ts; any other model is refused
+const MODELS: [(&str, Provider); 7] = [
+    ("gpt-5.4-nano", Provider::OpenAI),
+    ("gpt-5.4-mini", Provider::OpenAI),
+    ("gpt-5.4", Provider::OpenAI),
+    ("gpt-5.5", Provider::OpenAI),
+    ("claude-haiku-4-5-20251001", Provider::Anthropic),
+    ("ministral-3b-2512", Provider::Mistral),
+    ("openrouter/free", Provider::OpenRouter),
+];
+
+pub fn provider_for_model(model: &str) -> Option<Provider> {
+    MODELS.iter().find(|(id, _)| *id == model).map(|(_, provider)| *provider)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionRequest {
+    pub text: String,
+    pub model: String,
+    pub writing_style: Option<String>,
+    pub custom_rules: Option<String>,
+    pub language: Option<String>,
+    pub temperature: Option<f32>,
+    /// The key profile to sign with instead of the provider's active one.
+    pub profile: Option<String>,
+    /// The personal dictionary, filled in by the backend.
+    #[serde(skip)]
+    pub dictionary: Arc<dictionary::Terms>,
+}
+
+impl CorrectionRequest {
+    /// The same settings for another text, such as one paragraph of this one.
+    pub fn with_text(&self, text: &str) -> CorrectionRequest {
+        CorrectionRequest {
+            text: text.to_string(),
+            model: self.model.clone(),
+            writing_style: self.writing_style.clone(),
+            custom_rules: self.custom_rules.clone(),
+            language: self.language.clone(),
+            temperature: self.temperature,
+            profile: self.profile.clone(),
+            dictionary: self.dictionary.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Usage {
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    pub total_tokens: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionResult {
+    pub result: String,
+    pub usage: Usage,
+    pub finish_reason: Option<String>,
+    pub request_id: Option<String>,
+    /// The key profile that paid for the request, filled in by whoever picked the key.
+    pub profile: Option<String>,
+    /// Answered from the local cache without a provider request.
+    pub cached: bool,
+}
+
+impl CorrectionResult {
+    /// A correction found in the cache, which cost no tokens.
+    pub fn from_cache(result: String) -> Self {
+        CorrectionResult { result, usage: Usage::default(), finish_reason: None, request_id: None, profile: None, cached: true }
+    }
+
+    /// False when the model ran out of output tokens before finishing.
+    pub fn is_complete(&self) -> bool {
+        !matches!(self.finish_reason.as_deref(), Some("length" | "max_tokens"))
+    }
+}
+
+/// Called with the corrected text so far while a response streams in.
+pub type Partial<'a> = &'a (dyn Fn(&str) + Sync);
+
+/// Streamed corrections still running, by the stream id the main window gave
+/// them, so it can cancel them.
+#[derive(Default)]
+pub struct CorrectionStreams {
+    /// Each stream's abort handle, with the serial number of the run that registered it.
+    running: Mutex<HashMap<String, (u64, AbortHandle)>>,
+    next_serial: AtomicU64,
+}
+
+impl CorrectionStreams {
+    /// Runs `correction` under `stream_id`, or stops it where it is when
+    /// [`CorrectionStreams::cancel`] is called first. A correction still
+    /// running under the same id is stopped in favour of this one.
+    pub async fn run<T>(&self, stream_id: &str, correction: impl Future<Output = T>) -> Result<T, Aborted> {
+        let (abort, registration) = AbortHandle::new_pair();
+        let serial = self.next_serial.fetch_add(1, Ordering::Relaxed);
+        if let Some((_, previous)) = self.running.lock().unwrap().insert(stream_id.to_string(), (serial, abort)) {
+            tracing::warn!("A correction was started again under a running stream id; stopping the first");
+            previous.abort();
+        }
+        let _registration = StreamRegistration { streams: self, stream_id, serial };
+        Abortable::new(correction, registration).await
+    }
+
+    pub fn cancel(&self, stream_id: &str) {
+        if let Some((_, abort)) = self.running.lock().unwrap().remove(stream_id) {
+            abort.abort();
+        }
+    }
+}
+
+// Unregisters a run however it ends, including when the command's future is dropped
+struct StreamRegistration<'a> {
+    streams: &'a CorrectionStreams,
+    stream_id: &'a str,
+    serial: u64,
+}
+
+impl Drop for StreamRegistration<'_> {
+    fn drop(&mut self) {
+        let Ok(mut running) = self.streams.running.lock() else {
+            return;
+        };
+        // A newer run under the same id keeps its registration
+        if running.get(self.stream_id).is_some_and(|(serial, _)| *serial == self.serial) {
+            running.remove(self.stream_id);
+        }
+    }
+}
+
+// One request to a model
+#[derive(Clone, Copy)]
+struct Prompt<'a> {
+    model: &'a str,
+    system: &'a str,
+    text: &'a str,
+    temperature: Option<f32>,
+}
+
+struct Completion {
+    text: String,
+    usage: Usage,
+    finish_reason: Option<String>,
+    request_id: Option<String>,
+}
+
+struct ProtectedCode {
+    text: String,
+    markers: Vec<(String, String)>,
+}
+
+/// Outcome of checking a key against its provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyStatus {
+    Valid,
+    Invalid,
+    InsufficientQuota,
+    NetworkError,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyVerification {
+    pub status: KeyStatus,
+    /// The organization or account the key belongs to, where the provider exposes it.
+    pub account: Option<String>,
+    pub details: Option<String>,
+}
+
+/// HTTP client for every provider.
+pub struct LlmClient {
+    http: reqwest::Client,
+    // Replaces every provider's base URL, for pointing the client at a stub server
+    base_url: Option<String>,
+    /// Base URLs that replace individual providers' public APIs.
+    endpoints: BTreeMap<Provider, String>,
+    /// Requests each provider may have in flight, shared by every caller of this client.
+    slots: BTreeMap<Provider, Semaphore>,
+}
+
+impl Default for LlmClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LlmClient {
+    pub fn new() -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        let slots = Provider::ALL
+            .into_iter()
+            .map(|provider| (provider, Semaphore::new(provider.max_concurrent_requests())))
+            .collect();
+        LlmClient { http, base_url: None, endpoints: BTreeMap::new(), slots }
+    }
+
+    /// A client that sends each provider in `endpoints` to that base URL instead.
+    pub fn with_endpoints(endpoints: BTreeMap<Provider, String>) -> Self {
+        LlmClient { endpoints, ..Self::new() }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn with_base_url(base_url: &str) -> Self {
+        LlmClient { base_url: Some(base_url.to_string()), ..Self::new() }
+    }
+
+    fn endpoint(&self, provider: Provider, path: &str) -> String {
+        let base_url = self
+            .base_url
+            .as_deref()
+            .or_else(|| self.endpoints.get(&provider).map(|url| url.trim_end_matches('/')))
+            .unwrap_or(provider.base_url());
+        format!("{}{}", base_url, path)
+    }
+
+    // Waits until the provider may take another request. The permit is held until the response is read.
+    async fn slot(&self, provider: Provider) -> SemaphorePermit<'_> {
+        self.slots[&provider].acquire().await.expect("request slots are never closed")
+    }
+
+    /// Checks `api_key` against the provider's cheapest authenticated endpoint
+    /// without spending tokens. Only unexpected responses are errors.
+    pub async fn verify_key(&self, provider: Provider, api_key: &str) -> Result<KeyVerification, AppError> {
+        let api_key = api_key.trim();
+        if api_key.is_empty() {
+            return Err(AppError::InvalidArgument("The API key is empty".to_string()));
+        }
+        let request = match provider {
+            Provider::Anthropic => self
+                .http
+                .get(self.endpoint(provider, "/models"))
+                .header("x-api-key", api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION),
+            // OpenRouter lists models without a key; its key endpoint checks the key and reports credit
+            Provider::OpenRouter => self.http.get(self.endpoint(provider, "/key")).bearer_auth(api_key),
+            _ => self.http.get(self.endpoint(provider, "/models")).bearer_auth(api_key),
+        };
+
+        let _slot = self.slot(provider).await;
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                return Ok(KeyVerification {
+                    status: KeyStatus::NetworkError,
+                    account: None,
+                    details: Some(format!("Could not reach {}: {}", provider.label(), e)),
+                })
+            }
+        };
+        let status = response.status().as_u16();
+        let mut account = ["openai-organization", "anthropic-organization-id"]
+            .iter()
+            .find_map(|name| response.headers().get(*name))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let payload: Value = response.json().await.unwrap_or(Value::Null);
+        let details = Some(format!("{} returned HTTP {}: {}", provider.label(), status, error_message(&payload)));
+
+        let verification = match status {
+            200..=299 if provider == Provider::OpenRouter => {
+                let key = &payload["data"];
+                account = key["label"].as_str().map(str::to_string);
+                // A null limit means the key has no credit cap
+                let exhausted = key["limit_remaining"].as_f64().is_some_and(|remaining| remaining <= 0.0);
+                let status = if exhausted { KeyStatus::InsufficientQuota } else { KeyStatus::Valid };
+                KeyVerification { status, account, details: None }
+            }
+            200..=299 => KeyVerification { status: KeyStatus::Valid, account, details: None },
+            401 | 403 => KeyVerification { status: KeyStatus::Invalid, account: None, details },
+            402 => KeyVerification { status: KeyStatus::InsufficientQuota, account, details },
+            // A rate limit still proves the key was accepted, unless the quota is what ran out
+            429 => {
+                let quota = [&payload["error"]["code"], &payload["error"]["type"]]
+                    .iter()
+                    .any(|value| value.as_str().is_some_and(|value| value.contains("quota")));
+                let status = if quota { KeyStatus::InsufficientQuota } else { KeyStatus::Valid };
+                KeyVerification { status, account, details }
+            }
+            408 | 500..=599 => KeyVerification { status: KeyStatus::NetworkError, account: None, details },
+            _ => return Err(provider_error(provider, status, &payload)),
+        };
+        tracing::info!(provider = provider.label(), status = ?verification.status, "Verified API key");
+        Ok(verification)
+    }
+
+    /// Corrects `request.text` with the model it names, signing the request with `api_key`.
+    /// Long documents are split at Markdown block boundaries and the chunks
+    /// corrected a few at a time.
+    pub async fn correct(&self, api_key: &str, request: &CorrectionRequest) -> Result<CorrectionResult, AppError> {
+        self.correct_with(api_key, request, None).await
+    }
+
+    /// Corrects like [`LlmClient::correct`], but streams the responses and
+    /// calls `partial` with the corrected document so far, code restored. The
+    /// result is checked only once complete, exactly as an unstreamed one.
+    pub async fn correct_streaming(
+        &self,
+        api_key: &str,
+        request: &CorrectionRequest,
+        partial: Partial<'_>,
+    ) -> Result<CorrectionResult, AppError> {
+        self.correct_with(api_key, request, Some(partial)).await
+    }
+
+    async fn correct_with(
+        &self,
+        api_key: &str,
+        request: &CorrectionRequest,
+        partial: Option<Partial<'_>>,
+    ) -> Result<CorrectionResult, AppError> {
+        let provider = validate(request)?;
+        if request.text.chars().count() <= chunks::MAX_CHUNK_CHARACTERS {
+            return self.correct_chunk(provider, api_key, request, &request.text, partial).await;
+        }
+
+        let chunks = chunks::split(&request.text, chunks::MAX_CHUNK_CHARACTERS);
+        tracing::info!(provider = provider.label(), chunks = chunks.len(), "Correcting a long text in chunks");
+        // Code-only chunks are kept as they are
+        let known = chunks.iter().map(|chunk| (!chunk.prose).then(|| request.text[chunk.start..chunk.end].to_string())).collect();
+        let (result, _) = self.correct_parts(api_key, request, &chunks, known, partial).await?;
+        Ok(result)
+    }
+
+    /// Corrects each of `chunks` that has no text in `known` yet, a few at a
+    /// time, and puts `request.text` back together with the corrected chunks
+    /// between the untouched separators. Also returns every chunk's final text.
+    pub async fn correct_parts(
+        &self,
+        api_key: &str,
+        request: &CorrectionRequest,
+        chunks: &[chunks::Chunk],
+        known: Vec<Option<String>>,
+        partial: Option<Partial<'_>>,
+    ) -> Result<(CorrectionResult, Vec<String>), AppError> {
+        let provider = validate(request)?;
+        let pending: Vec<(usize, chunks::Chunk)> =
+            chunks.iter().copied().enumerate().filter(|(index, _)| known[*index].is_none()).collect();
+        // Each chunk's text so far, and whether it is final
+        let parts = Mutex::new(known.into_iter().map(|text| (text.clone().unwrap_or_default(), text.is_some())).collect::<Vec<_>>());
+        let parts_so_far = &parts;
+        // The client's request slots hold each provider to its limit however many chunks there are
+        let corrections = pending.into_iter().map(|(index, chunk)| async move {
+            let record = |text: &str, done: bool| {
+                let mut parts = parts_so_far.lock().unwrap();
+                parts[index] = (text.to_string(), done);
+                if let Some(partial) = partial {
+                    partial(&assemble(&request.text, chunks, &parts));
+                }
+            };
+            let progress = |text: &str| record(text, false);
+            let text = &request.text[chunk.start..chunk.end];
+            let result = self
+                .correct_chunk(provider, api_key, request, text, partial.map(|_| &progress as Partial))
+                .await?;
+            record(&result.result, true);
+            Ok::<_, AppError>(result)
+        });
+        let results: Vec<CorrectionResult> = future::try_join_all(corrections).await?;
+
+        let parts = parts.into_inner().unwrap();
+        let mut usage = Usage::default();
+        let (mut finish_reason, mut request_id) = (None, None);
+        for result in results {
+            usage.input_tokens = sum(usage.input_tokens, result.usage.input_tokens);
+            usage.output_tokens = sum(usage.output_tokens, result.usage.output_tokens);
+            usage.total_tokens = sum(usage.total_tokens, result.usage.total_tokens);
+            // A chunk cut short by the token limit matters more than the last one finishing
+            if !matches!(finish_reason.as_deref(), Some("length" | "max_tokens")) {
+                finish_reason = result.finish_reason;
+            }
+            request_id = result.request_id;
+        }
+        let text = assemble(&request.text, chunks, &parts);
+        let result = CorrectionResult { result: text, usage, finish_reason, request_id, profile: None, cached: false };
+        Ok((result, parts.into_iter().map(|(text, _)| text).collect()))
+    }
+
+    async fn correct_chunk(
+        &self,
+        provider: Provider,
+        api_key: &str,
+        request: &CorrectionRequest,
+        text: &str,
+        partial: Option<Partial<'_>>,
+    ) -> Result<CorrectionResult, AppError> {
+        if text.chars().count() > MAX_INPUT_CHARACTERS {
+            return Err(AppError::InvalidArgument(format!(
+                "A paragraph is too long. Limit paragraphs to {} characters.",
+                MAX_INPUT_CHARACTERS
+            )));
+        }
+
+        let protected = protect_code(text);
+        let system = prompts::system_prompt(
+            request.writing_style.as_deref().unwrap_or("grammar"),
+            request.custom_rules.as_deref(),
+            request.language.as_deref(),
+            request.dictionary.prompt_rules(text).as_deref(),
+        );
+        let reveal = |text: &str| {
+            if let Some(partial) = partial {
+                partial(&reveal_code(text, &protected.markers));
+            }
+        };
+        let on_text = partial.map(|_| &reveal as Partial);
+        for attempt in 0..2 {
+            let system = if attempt == 0 { system.clone() } else { format!("{}{}", system, prompts::MARKER_RETRY_NOTE) };
+            let prompt = Prompt { model: &request.model, system: &system, text: &protected.text, temperature: request.temperature };
+            let completion = self.complete_with_retry(provider, api_key, &prompt, on_text).await?;
+            let text = completion.text.trim();
+            if text.is_empty() {
+                return Err(AppError::ProviderUnavailable("The model returned an empty correction.".to_string()));
+            }
+            if let Some(result) = restore_code(text, &protected.markers) {
+                return Ok(CorrectionResult {
+                    result,
+                    usage: completion.usage,
+                    finish_reason: completion.finish_reason,
+                    request_id: completion.request_id,
+                    profile: None,
+                    cached: false,
+                });
+            }
+            tracing::warn!(attempt, "Model altered a protected code marker");
+        }
+        Err(AppError::ProviderUnavailable(
+            "The model could not preserve protected code. No correction was applied.".to_string(),
+        ))
+    }
+
+    // Retries once when the provider is busy or unreachable
+    async fn complete_with_retry(
+        &self,
+        provider: Provider,
+        api_key: &str,
+        prompt: &Prompt<'_>,
+        on_text: Option<Partial<'_>>,
+    ) -> Result<Completion, AppError> {
+        match self.complete(provider, api_key, prompt, on_text).await {
+            Err(AppError::ProviderUnavailable(reason)) | Err(AppError::ProviderCapacity(reason)) => {
+                tracing::info!(provider = provider.label(), "Retrying correction: {}", reason);
+                tokio::time::sleep(RETRY_DELAY).await;
+                self.complete(provider, api_key, prompt, on_text).await
+            }
+            result => result,
+        }
+    }
+
+    // Streams the response when `on_text` wants the text as it arrives
+    async fn complete(
+        &self,
+        provider: Provider,
+        api_key: &str,
+        prompt: &Prompt<'_>,
+        on_text: Option<Partial<'_>>,
+    ) -> Result<Completion, AppError> {
+        let Prompt { model, system, text, temperature } = *prompt;
+        let temperature = temperature.unwrap_or(0.0);
+        let mut request = match provider {
+            Provider::Anthropic => {
+                let mut body = json!({
+                    "model": model,
+                    "system": system,
+                    "messages": [{ "role": "user", "content": text }],
+                    "max_tokens": MAX_OUTPUT_TOKENS,
+                    "temperature": temperature,
+                });
+                if on_text.is_some() {
+                    body["stream"] = json!(true);
+                }
+                self.http
+                    .post(self.endpoint(provider, "/messages"))
+                    .header("x-api-key", api_key)
+                    .header("anthropic-version", ANTHROPIC_VERSION)
+                    .json(&body)
+            }
+            _ => {
+                let mut body = json!({
+                    "model": model,
+                    "messages": [
+                        { "role": "system", "content": system },
+                        { "role": "user", "content": text },
+                    ],
+                });
+                // GPT-5 models reject a custom temperature and the legacy token limit
+                if model.starts_with("gpt-5") {
+                    body["max_completion_tokens"] = json!(MAX_OUTPUT_TOKENS);
+                } else {
+                    body["max_tokens"] = json!(MAX_OUTPUT_TOKENS);
+                    body["temperature"] = json!(temperature);
+                }
+                if on_text.is_some() {
+                    body["stream"] = json!(true);
+                    // Mistral and OpenRouter send usage with the last event unasked
+                    if provider == Provider::OpenAI {
+                        body["stream_options"] = json!({ "include_usage": true });
+                    }
+                }
+                self.http
+                    .post(self.endpoint(provider, "/chat/completions"))
+                    .bearer_auth(api_key)
+                    .json(&body)
+            }
+        };
+        if on_text.is_some() {
+            request = request.timeout(STREAM_TIMEOUT);
+        }
+
+        let _slot = self.slot(provider).await;
+        let response = request.send().await.map_err(|e| network_error(provider, e))?;
+        let status = response.status();
+        let request_id = ["x-request-id", "request-id"]
+            .iter()
+            .find_map(|name| response.headers().get(*name))
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        if !status.is_success() {
+            let payload: Value = response.json().await.unwrap_or(Value::Null);
+            return Err(provider_error(provider, status.as_u16(), &payload));
+        }
+
+        let mut completion = match on_text {
+            Some(on_text) => read_stream(provider, response, on_text).await?,
+            None => {
+                let payload: Value = response.json().await.unwrap_or(Value::Null);
+                match provider {
+                    Provider::Anthropic => parse_anthropic(&payload),
+                    _ => parse_chat_completion(&payload),
+                }
+            }
+        };
+        completion.request_id = request_id;
+        Ok(completion)
+    }
+}
+
+fn parse_chat_completion(payload: &Value) -> Completion {
+    let choice = &payload["choices"][0];
+    let usage = &payload["usage"];
+    Completion {
+        text: choice["message"]["content"].as_str().unwrap_or_default().to_string(),
+        usage: Usage {
+            input_tokens: usage["prompt_tokens"].as_u64(),
+            output_tokens: usage["completion_tokens"].as_u64(),
+            total_tokens: usage["total_tokens"].as_u64(),
+        },
+        finish_reason: choice["finish_reason"].as_str().map(str::to_string),
+        request_id: None,
+    }
+}
+
+fn parse_anthropic(payload: &Value) -> Completion {
+    let text = payload["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|block| block["type"] == "text")
+                .filter_map(|block| block["text"].as_str())
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+    let input_tokens = payload["usage"]["input_tokens"].as_u64();
+    let output_tokens = payload["usage"]["output_tokens"].as_u64();
+    Completion {
+        text,
+        usage: Usage {
+            input_tokens,
+            output_tokens,
+            total_tokens: input_tokens.zip(output_tokens).map(|(input, output)| input + output),
+        },
+        finish_reason: payload["stop_reason"].as_str().map(str::to_string),
+        request_id: None,
+    }
+}
+
+// Reads a server-sent event stream, passing the text so far to `on_text` as it
+// grows. A stream that ends without a finish reason was cut off and is refused.
+async fn read_stream(provider: Provider, mut response: reqwest::Response, on_text: Partial<'_>) -> Result<Completion, AppError> {
+    let mut completion = Completion { text: String::new(), usage: Usage::default(), finish_reason: None, request_id: None };
+    let mut buffer = Vec::new();
+    let mut reported: Option<Instant> = None;
+    loop {
+        let bytes = tokio::time::timeout(REQUEST_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| AppError::ProviderUnavailable("The correction stopped arriving. Please try again.".to_string()))?
+            .map_err(|e| network_error(provider, e))?;
+        let Some(bytes) = bytes else {
+            break;
+        };
+        buffer.extend_from_slice(&bytes);
+        let mut grew = false;
+        // Line breaks never occur inside a multi-byte character, so splitting bytes is safe
+        while let Some(end) = buffer.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim_end().strip_prefix("data:") else {
+                continue;
+            };
+            if let Ok(event) = serde_json::from_str::<Value>(data.trim_start()) {
+                grew |= apply_event(provider, &event, &mut completion)?;
+            }
+        }
+        if grew && reported.is_none_or(|at| at.elapsed() >= PARTIAL_INTERVAL) {
+            on_text(&completion.text);
+            reported = Some(Instant::now());
+        }
+    }
+    if completion.finish_reason.is_none() {
+        return Err(AppError::ProviderUnavailable(
+            "The correction ended before the model finished. No correction was applied.".to_string(),
+        ));
+    }
+    on_text(&completion.text);
+    Ok(completion)
+}
+
+// Folds one streamed event into `completion`; true when text arrived
+fn apply_event(provider: Provider, event: &Value, completion: &mut Completion) -> Result<bool, AppError> {
+    if !event["error"].is_null() {
+        let details = format!("{} stopped the correction: {}", provider.label(), error_message(event));
+        return Err(match event["error"]["type"].as_str() {
+            Some("overloaded_error" | "rate_limit_error") => AppError::ProviderCapacity(details),
+            _ => AppError::ProviderUnavailable(details),
+        });
+    }
+    let text = match provider {
+        Provider::Anthropic => match event["type"].as_str() {
+            Some("message_start") => {
+                completion.usage.input_tokens = event["message"]["usage"]["input_tokens"].as_u64();
+                None
+            }
+            Some("content_block_delta") => event["delta"]["text"].as_str(),
+            Some("message_delta") => {
+                completion.finish_reason = event["delta"]["stop_reason"].as_str().map(str::to_string);
+                completion.usage.output_tokens = event["usage"]["output_tokens"].as_u64();
+                completion.usage.total_tokens = sum(completion.usage.input_tokens, completion.usage.output_tokens);
+                None
+            }
+            _ => None,
+        },
+        _ => {
+            let choice = &event["choices"][0];
+            if let Some(reason) = choice["finish_reason"].as_str() {
+                completion.finish_reason = Some(reason.to_string());
+            }
+            if event["usage"].is_object() {
+                completion.usage = Usage {
+                    input_tokens: event["usage"]["prompt_tokens"].as_u64(),
+                    output_tokens: event["usage"]["completion_tokens"].as_u64(),
+                    total_tokens: event["usage"]["total_tokens"].as_u64(),
+                };
+            }
+            choice["delta"]["content"].as_str()
+        }
+    };
+    match text {
+        Some(text) if !text.is_empty() => {
+            completion.text.push_str(text);
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
+
+// The model must be known and the text within the document limit
+fn validate(request: &CorrectionRequest) -> Result<Provider, AppError> {
+    let provider = provider_for_model(&request.model)
+        .ok_or_else(|| AppError::InvalidArgument(format!("Unknown model '{}'", request.model)))?;
+    if request.text.trim().is_empty() {
+        return Err(AppError::InvalidArgument("Text is required".to_string()));
+    }
+    if request.text.chars().count() > MAX_DOCUMENT_CHARACTERS {
+        return Err(AppError::InvalidArgument(format!(
+            "Text is too long. Limit corrections to {} characters.",
+            MAX_DOCUMENT_CHARACTERS
+        )));
+    }
+    Ok(provider)
+}
+
+// The chunks so far, in order, up to the first one still arriving
+fn assemble(text: &str, chunks: &[chunks::Chunk], parts: &[(String, bool)]) -> String {
+    let mut assembled = String::with_capacity(text.len());
+    let mut position = 0;
+    for (chunk, (part, done)) in chunks.iter().zip(parts) {
+        assembled.push_str(&text[position..chunk.start]);
+        assembled.push_str(part);
+        if !done {
+            return assembled;
+        }
+        position = chunk.end;
+    }
+    assembled.push_str(&text[position..]);
+    assembled
+}
+
+fn sum(total: Option<u64>, more: Option<u64>) -> Option<u64> {
+    match (total, more) {
+        (None, None) => None,
+        (total, more) => Some(total.unwrap_or(0) + more.unwrap_or(0)),
+    }
+}
+
+fn network_error(provider: Provider, error: reqwest::Error) -> AppError {
+    if error.is_timeout() {
+        AppError::ProviderUnavailable("Correction timed out. Please try again.".to_string())
+    } else {
+        AppError::ProviderUnavailable(format!("Could not reach {}: {}", provider.label(), error))
+    }
+}
+
+// Classifies a failed response so the UI can decide whether a retry or another model helps
+fn provider_error(provider: Provider, status: u16, payload: &Value) -> AppError {
+    let details = format!("{} returned HTTP {}: {}", provider.label(), status, error_message(payload));
+    match status {
+        401 | 403 => AppError::ProviderAuthentication(details),
+        429 | 529 => AppError::ProviderCapacity(details),
+        400 | 404 | 413 | 422 => AppError::ProviderRejected(details),
+        408 | 500..=599 => AppError::ProviderUnavailable(details),
+        _ => AppError::CorrectionFailed(details),
+    }
+}
+
+fn error_message(payload: &Value) -> &str {
+    payload["error"]["message"]
+        .as_str()
+        .or_else(|| payload["message"].as_str())
+        .or_else(|| payload["error"].as_str())
+        .unwrap_or("no details")
+}
+
+// Finds the next fenced (```...``` or ~~~...~~~) or inline (`...`) code span at
+// or after `from`, trying the fenced forms first like the original
+// /```[\s\S]*?```|~~~[\s\S]*?~~~|`[^`\n]*`/
+fn next_code_span(text: &str, from: usize) -> Option<(usize, usize)> {
+    let mut position = from;
+    while let Some(offset) = text[position..].find(['`', '~']) {
+        let start = position + offset;
+        for fence in ["```", "~~~"] {
+            if text[start..].starts_with(fence) {
+                if let Some(close) = text[start + 3..].find(fence) {
+                    return Some((start, start + 3 + close + 3));
+                }
+            }
+        }
+        if text[start..].starts_with('`') {
+            let rest = &text[start + 1..];
+            if let Some(close) = rest.find(['`', '\n']) {
+                if rest[close..].starts_with('`') {
+                    return Some((start, start + 1 + close + 1));
+                }
+            }
+        }
+        position = start + 1;
+    }
+    None
+}
+
+fn code_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut position = 0;
+    while let Some((start, end)) = next_code_span(text, position) {
+        spans.push((start, end));
+        position = end;
+    }
+    spans
+}
+
+/// Models must never receive executable Markdown as editable prose, so each
+/// code span is replaced by an opaque marker.
+fn protect_code(text: &str) -> ProtectedCode {
+    let mut protected = String::with_capacity(text.len());
+    let mut markers = Vec::new();
+    let mut position = 0;
+    for (start, end) in code_spans(text) {
+        let marker = format!("[[CORRECTIFY_CODE_{}_DO_NOT_EDIT]]", markers.len());
+        protected.push_str(&text[position..start]);
+        protected.push_str(&marker);
+        markers.push((marker, text[start..end].to_string()));
+        position = end;
+    }
+    protected.push_str(&text[position..]);
+    ProtectedCode { text: protected, markers }
+}
+
+// Text still streaming in, for display only: whole markers show their code and
+// a marker that has not fully arrived is left out
+fn reveal_code(text: &str, markers: &[(String, String)]) -> String {
+    let mut revealed = text.trim_start().to_string();
+    if let Some(start) = revealed.rfind("[[") {
+        if !revealed[start..].contains("]]") {
+            revealed.truncate(start);
+        }
+    }
+    for (marker, source) in markers {
+        revealed = revealed.replacen(marker.as_str(), source, 1);
+    }
+    revealed
+}
+
+/// Puts the original code back, or returns None when a marker was damaged.
+fn restore_code(text: &str, markers: &[(String, String)]) -> Option<String> {
+    let mut restored = text.to_string();
+    for (marker, source) in markers {
+        if !restored.contains(marker.as_str()) {
+            return None;
+        }
+        restored = restored.replacen(marker.as_str(), source, 1);
+    }
+
+    // Do not trust a model to keep code syntax intact around a restored marker.
+    // Reapply each original span in order, then fail closed if the structure changed.
+    let spans = code_spans(&restored);
+    if spans.len() != markers.len() {
+        return None;
+    }
+    let mut enforced = String::with_capacity(restored.len());
+    let mut position = 0;
+    for ((start, end), (_, source)) in spans.into_iter().zip(markers) {
+        enforced.push_str(&restored[position..start]);
+        enforced.push_str(source);
+        position = end;
+    }
+    enforced.push_str(&restored[position..]);
+    Some(enforced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::net::TcpStream;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    // Answers one request with a canned response and hands back what it received
+    fn stub(status: &str, headers: &str, body: &str) -> (LlmClient, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            headers,
+            body
+        );
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 8192];
+            let read = stream.read(&mut buffer).unwrap();
+            // Tests that ignore the request drop the receiver
+            let _ = sender.send(String::from_utf8_lossy(&buffer[..read]).to_lowercase());
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        (LlmClient::with_base_url(&base_url), receiver)
+    }
+
+    #[tokio::test]
+    async fn valid_openai_key_reports_the_organization() {
+        let (client, request) = stub("200 OK", "openai-organization: acme-corp\r\n", r#"{"data":[]}"#);
+        let verification = client.verify_key(Provider::OpenAI, " sk-test ").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::Valid);
+        assert_eq!(verification.account.as_deref(), Some("acme-corp"));
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("get /models "));
+        assert!(request.contains("authorization: bearer sk-test\r\n"));
+    }
+
+    #[tokio::test]
+    async fn rejected_anthropic_key_is_invalid() {
+        let (client, request) = stub(
+            "401 Unauthorized",
+            "",
+            r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#,
+        );
+        let verification = client.verify_key(Provider::Anthropic, "sk-ant-test").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::Invalid);
+        assert!(verification.details.unwrap().contains("invalid x-api-key"));
+        let request = request.recv().unwrap();
+        assert!(request.contains("x-api-key: sk-ant-test\r\n"));
+        assert!(request.contains("anthropic-version: 2023-06-01\r\n"));
+    }
+
+    #[tokio::test]
+    async fn exhausted_quota_is_distinguished_from_a_rate_limit() {
+        let (client, _) = stub(
+            "429 Too Many Requests",
+            "",
+            r#"{"error":{"message":"You exceeded your current quota","type":"insufficient_quota"}}"#,
+        );
+        let verification = client.verify_key(Provider::Mistral, "key").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::InsufficientQuota);
+
+        let (client, _) = stub("429 Too Many Requests", "", r#"{"error":{"message":"Slow down"}}"#);
+        let verification = client.verify_key(Provider::Mistral, "key").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::Valid);
+    }
+
+    #[tokio::test]
+    async fn openrouter_reports_the_key_label_and_remaining_credit() {
+        let (client, request) = stub("200 OK", "", r#"{"data":{"label":"Work laptop","limit":5,"limit_remaining":0}}"#);
+        let verification = client.verify_key(Provider::OpenRouter, "sk-or-test").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::InsufficientQuota);
+        assert_eq!(verification.account.as_deref(), Some("Work laptop"));
+        assert!(request.recv().unwrap().starts_with("get /key "));
+
+        let (client, _) = stub("200 OK", "", r#"{"data":{"label":"Personal","limit":null,"limit_remaining":null}}"#);
+        let verification = client.verify_key(Provider::OpenRouter, "sk-or-test").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::Valid);
+    }
+
+    #[tokio::test]
+    async fn unreachable_or_failing_provider_is_a_network_error() {
+        let address = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let client = LlmClient::with_base_url(&format!("http://{}", address));
+        let verification = client.verify_key(Provider::OpenAI, "sk-test").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::NetworkError);
+
+        let (client, _) = stub("503 Service Unavailable", "", "{}");
+        let verification = client.verify_key(Provider::OpenAI, "sk-test").await.unwrap();
+        assert_eq!(verification.status, KeyStatus::NetworkError);
+    }
+
+    #[tokio::test]
+    async fn unexpected_responses_are_errors() {
+        let (client, _) = stub("404 Not Found", "", r#"{"error":{"message":"Unknown path"}}"#);
+        let error = client.verify_key(Provider::OpenAI, "sk-test").await.unwrap_err();
+        assert_eq!(error.code(), "provider-rejected");
+    }
+
+    #[test]
+    fn protects_backtick_tilde_and_inline_code() {
+        let text = "Run `make` first.\n\n~~~sh\nmake `test`\n~~~\n\n```\nfoo ~~~ bar\n```\nA ~ tilde ~~ stays.";
+        let protected = protect_code(text);
+        let sources: Vec<&str> = protected.markers.iter().map(|(_, source)| source.as_str()).collect();
+        assert_eq!(sources, ["`make`", "~~~sh\nmake `test`\n~~~", "```\nfoo ~~~ bar\n```"]);
+        assert!(protected.text.ends_with("A ~ tilde ~~ stays."));
+        assert_eq!(restore_code(&protected.text, &protected.markers).as_deref(), Some(text));
+    }
+
+    // Streams `parts` as separate writes, then holds the connection open for
+    // `hold` before closing it
+    fn event_stream(parts: Vec<Vec<u8>>, hold: Duration) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0; 8192];
+            let _ = stream.read(&mut buffer).unwrap();
+            let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n";
+            stream.write_all(headers.as_bytes()).unwrap();
+            for part in parts {
+                stream.write_all(&part).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(hold);
+        });
+        base_url
+    }
+
+    fn openai_event(content: Option<&str>, finish_reason: Option<&str>) -> String {
+        let delta = content.map_or(json!({}), |content| json!({ "content": content }));
+        format!("data: {}\n\n", json!({ "choices": [{ "delta": delta, "finish_reason": finish_reason }] }))
+    }
+
+    // Reads a stream of `parts` and returns the completion with every text passed to `on_text`
+    async fn read(provider: Provider, parts: Vec<Vec<u8>>) -> (Result<Completion, AppError>, Vec<String>) {
+        let response = reqwest::get(event_stream(parts, Duration::ZERO)).await.unwrap();
+        let seen = Mutex::new(Vec::new());
+        let completion = read_stream(provider, response, &|text: &str| seen.lock().unwrap().push(text.to_string())).await;
+        (completion, seen.into_inner().unwrap())
+    }
+
+    fn request(model: &str, text: &str) -> CorrectionRequest {
+        CorrectionRequest {
+            text: text.to_string(),
+            model: model.to_string(),
+            writing_style: None,
+            custom_rules: None,
+            language: None,
+            temperature: None,
+            profile: None,
+            dictionary: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn joins_events_split_across_reads() {
+        let stream = [
+            openai_event(Some("Grüße, "), None),
+            openai_event(Some("Welt."), None),
+            openai_event(None, Some("stop")),
+            format!("data: {}\n\n", json!({ "choices": [], "usage": { "prompt_tokens": 4, "completion_tokens": 3, "total_tokens": 7 } })),
+            "data: [DONE]\n\n".to_string(),
+        ]
+        .concat()
+        .into_bytes();
+        // Cut inside the "ü", inside the JSON and between "data:" and its payload
+        let umlaut = stream.windows(2).position(|pair| pair == "ü".as_bytes()).unwrap() + 1;
+        let cuts = [3, umlaut, umlaut + 9, 90, 120, stream.len() - 3];
+        let mut parts = Vec::new();
+        let mut position = 0;
+        for cut in cuts {
+            parts.push(stream[position..cut].to_vec());
+            position = cut;
+        }
+        parts.push(stream[position..].to_vec());
+
+        let (completion, seen) = read(Provider::OpenAI, parts).await;
+        let completion = completion.unwrap();
+        assert_eq!(completion.text, "Grüße, Welt.");
+        assert_eq!(completion.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(completion.usage.total_tokens, Some(7));
+        assert_eq!(seen.last().map(String::as_str), Some("Grüße, Welt."));
+        assert!(seen.iter().all(|text| "Grüße, Welt.".starts_with(text.as_str())));
+    }
+
+    #[tokio::test]
+    async fn reads_anthropic_events_and_their_usage() {
+        let events = [
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 12 } } }),
+            json!({ "type": "ping" }),
+            json!({ "type": "content_block_delta", "delta": { "type": "text_delta", "text": "Hello" } }),
+            json!({ "type": "message_delta", "delta": { "stop_reason": "end_turn" }, "usage": { "output_tokens": 2 } }),
+            json!({ "type": "message_stop" }),
+        ];
+        let parts = events
+            .iter()
+            .map(|event| format!("event: {}\ndata: {}\n\n", event["type"].as_str().unwrap(), event).into_bytes())
+            .collect();
+
+        let (completion, _) = read(Provider::Anthropic, parts).await;
+        let completion = completion.unwrap();
+        assert_eq!(completion.text, "Hello");
+        assert_eq!(completion.finish_reason.as_deref(), Some("end_turn"));
+        assert_eq!(completion.usage.total_tokens, Some(14));
+    }
+
+    #[tokio::test]
+    async fn a_stream_without_a_finish_reason_is_refused() {
+        let parts = vec![openai_event(Some("Half a "), None).into_bytes(), "data: [DONE]\n\n".into()];
+        let (completion, seen) = read(Provider::OpenAI, parts).await;
+        assert!(matches!(completion, Err(AppError::ProviderUnavailable(_))));
+        assert_eq!(seen, ["Half a "]);
+    }
+
+    #[tokio::test]
+    async fn an_error_event_stops_the_stream() {
+        let error = json!({ "type": "error", "error": { "type": "overloaded_error", "message": "Overloaded" } });
+        let parts = vec![format!("event: error\ndata: {}\n\n", error).into_bytes()];
+        let (completion, _) = read(Provider::Anthropic, parts).await;
+        assert!(matches!(completion, Err(AppError::ProviderCapacity(details)) if details.contains("Overloaded")));
+    }
+
+    #[tokio::test]
+    async fn cancelling_stops_a_running_correction() {
+        let base_url = event_stream(vec![openai_event(Some("The text"), None).into_bytes()], Duration::from_secs(10));
+        let client = LlmClient::with_base_url(&base_url);
+        let streams = CorrectionStreams::default();
+        let started = AtomicBool::new(false);
+        let partial = |_: &str| started.store(true, Ordering::SeqCst);
+        let request = request("gpt-5.4-mini", "Teh text");
+
+        let correction = streams.run("stream-1", client.correct_streaming("key", &request, &partial));
+        let cancel = async {
+            while !started.load(Ordering::SeqCst) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            streams.cancel("stream-1");
+        };
+        let (outcome, ()) = tokio::time::timeout(Duration::from_secs(5), async { tokio::join!(correction, cancel) })
+            .await
+            .expect("the correction was not cancelled");
+        assert!(outcome.is_err());
+        assert!(streams.running.lock().unwrap().is_empty());
+        // Cancelling an unknown or finished stream does nothing
+        streams.cancel("stream-1");
+    }
+
+    // A model that answers every request with its text after a pause, counting
+    // the most requests it had in flight at once
+    fn slow_model() -> (LlmClient, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (active, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let counted = most.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let (active, most) = (active.clone(), counted.clone());
+                std::thread::spawn(move || {
+                    most.fetch_max(active.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    let mut stream = stream.unwrap();
+                    let text = read_text(&mut stream);
+                    std::thread::sleep(Duration::from_millis(100));
+                    // Released before answering, as the client releases its slot only after reading the answer
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    let payload = json!({ "choices": [{ "message": { "content": text }, "finish_reason": "stop" }] }).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        payload.len(),
+                        payload
+                    );
+                    stream.write_all(response.as_bytes()).unwrap();
+                });
+            }
+        });
+        (LlmClient::with_base_url(&base_url), most)
+    }
+
+    // The text to correct from a chat completion request
+    fn read_text(stream: &mut TcpStream) -> String {
+        let mut request = Vec::new();
+        let mut buffer = [0; 8192];
+        let body_start = loop {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+        let length: usize =
+            headers.lines().find_map(|line| line.strip_prefix("content-length:")).map(|value| value.trim().parse().unwrap()).unwrap();
+        while request.len() < body_start + length {
+            let read = stream.read(&mut buffer).unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let body: Value = serde_json::from_slice(&request[body_start..]).unwrap();
+        body["messages"][1]["content"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn keeps_to_the_provider_limit_across_chunks_and_callers() {
+        let (client, most) = slow_model();
+        let paragraph = "A sentence that is long enough to fill a paragraph. ".repeat(60);
+        let document = [paragraph.trim_end(); 8].join("\n\n");
+        assert!(document.chars().count() > chunks::MAX_CHUNK_CHARACTERS * 2);
+        let (long, short) = (request("ministral-3b-2512", &document), request("ministral-3b-2512", "Teh text"));
+
+        let corrections = [client.correct("key", &long), client.correct("key", &long), client.correct("key", &short)];
+        let results = future::join_all(corrections).await;
+        assert_eq!(results[0].as_ref().unwrap().result, document);
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(most.load(Ordering::SeqCst), Provider::Mistral.max_concurrent_requests());
+    }
+
+    #[tokio::test]
+    async fn a_dropped_correction_is_unregistered() {
+        let streams = CorrectionStreams::default();
+        let correction = streams.run("stream-1", std::future::pending::<()>());
+        assert!(tokio::time::timeout(Duration::from_millis(20), correction).await.is_err());
+        assert!(streams.running.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_reused_stream_id_stops_the_first_correction() {
+        let streams = CorrectionStreams::default();
+        let first = streams.run("stream-1", std::future::pending::<()>());
+        let second = async {
+            tokio::task::yield_now().await;
+            let registered = async {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                streams.running.lock().unwrap().contains_key("stream-1")
+            };
+            streams.run("stream-1", registered).await
+        };
+        let (first, second) = tokio::time::timeout(Duration::from_secs(5), async { tokio::join!(first, second) })
+            .await
+            .expect("the first correction was not stopped");
+        assert!(first.is_err());
+        // The first run ending did not unregister the second
+        assert_eq!(second, Ok(true));
+        assert!(streams.running.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,191 @@
+//! Leveled logging to a rotating file in the app log directory.
+//!
+//! Every line passes through [`redact`] before it reaches disk, so an API key
+//! that slips into an error message is never persisted. Clipboard and
+//! correction text must not be logged at all; log lengths instead.
+
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+const LOG_FILE_PREFIX: &str = "correctify";
+const MAX_LOG_FILES: usize = 7;
+const REDACTED: &str = "[REDACTED]";
+// Long unbroken tokens are treated as secrets even without a known prefix (Mistral keys).
+const MIN_SECRET_TOKEN_LENGTH: usize = 32;
+
+/// Installs the global subscriber. `CORRECTIFY_LOG` accepts an `EnvFilter`
+/// directive such as `debug` or `correctify_lib=trace`.
+pub fn init(log_dir: &Path) -> io::Result<()> {
+    fs::create_dir_all(log_dir)?;
+    let file = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)
+        .map_err(io::Error::other)?;
+
+    let default_level = if cfg!(debug_assertions) { "debug" } else { "info" };
+    let filter = EnvFilter::try_from_env("CORRECTIFY_LOG").unwrap_or_else(|_| EnvFilter::new(default_level));
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_target(false)
+        .with_writer(RedactingMakeWriter(file));
+    let console_layer = cfg!(debug_assertions)
+        .then(|| tracing_subscriber::fmt::layer().with_writer(RedactingMakeWriter(io::stderr)));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(file_layer)
+        .with(console_layer)
+        .try_init()
+        .map_err(io::Error::other)
+}
+
+/// Log files currently on disk, oldest first.
+pub fn log_files(log_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(log_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(LOG_FILE_PREFIX))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// Writes a zip with every log file under `logs/` plus each named JSON
+/// document at the archive root. Documents are redacted like log lines.
+pub fn bundle_diagnostics(path: &Path, log_dir: &Path, documents: &[(&str, Value)]) -> io::Result<()> {
+    use zip::write::SimpleFileOptions;
+
+    let mut archive = zip::ZipWriter::new(fs::File::create(path)?);
+    let options = SimpleFileOptions::default();
+    for log_file in log_files(log_dir) {
+        let Some(name) = log_file.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        archive.start_file(format!("logs/{}", name), options)?;
+        archive.write_all(&fs::read(&log_file)?)?;
+    }
+    for (name, document) in documents {
+        let json = serde_json::to_string_pretty(document).map_err(io::Error::other)?;
+        archive.start_file(*name, options)?;
+        archive.write_all(redact(&json).as_bytes())?;
+    }
+    archive.finish()?;
+    Ok(())
+}
+
+/// Masks anything shaped like a provider credential.
+pub fn redact(line: &str) -> String {
+    let mut output = String::with_capacity(line.len());
+    let mut rest = line;
+    // Whatever follows "Bearer " is a credential, whatever its shape
+    let mut bearer = false;
+    while let Some(start) = rest.find(|c: char| is_token_char(c)) {
+        output.push_str(&rest[..start]);
+        let follows_bearer = bearer && start == 1 && rest.starts_with(' ');
+        rest = &rest[start..];
+        let end = rest.find(|c: char| !is_token_char(c)).unwrap_or(rest.len());
+        let token = &rest[..end];
+        if follows_bearer || looks_like_secret(token) {
+            output.push_str(REDACTED);
+        } else {
+            output.push_str(token);
+        }
+        bearer = token.eq_ignore_ascii_case("bearer");
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+fn looks_like_secret(token: &str) -> bool {
+    let mixed = token.chars().any(|c| c.is_ascii_digit()) && token.chars().any(|c| c.is_ascii_alphabetic());
+    (token.starts_with("sk-") && token.len() > 12) || (mixed && token.len() >= MIN_SECRET_TOKEN_LENGTH)
+}
+
+struct RedactingMakeWriter<M>(M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter(self.0.make_writer())
+    }
+}
+
+// The fmt layer hands over each formatted event in a single write, so tokens
+// are never split across calls.
+struct RedactingWriter<W>(W);
+
+impl<W: Write> Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let line = String::from_utf8_lossy(buf);
+        self.0.write_all(redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_provider_keys() {
+        let openai = "sk-proj-Ab3dEf6hIj9kLm2nOp5qRs8tUv";
+        let anthropic = "sk-ant-REDACTED";
+        let openrouter = "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";
+        let mistral = "Qm7tR2xV9kLp4Wn8Zs1Yb6Hc3Jd5Fg0A";
+        for key in [openai, anthropic, openrouter, mistral] {
+            let line = format!("Request failed for key {}: 401", key);
+            assert_eq!(redact(&line), "Request failed for key [REDACTED]: 401", "{}", key);
+        }
+    }
+
+    #[test]
+    fn masks_bearer_tokens_of_any_shape() {
+        assert_eq!(redact("authorization: Bearer abcdefghijklmnop"), "authorization: Bearer [REDACTED]");
+        assert_eq!(redact("\"Authorization\":\"bearer sk-test\""), "\"Authorization\":\"bearer [REDACTED]\"");
+        // Only the token right after the scheme
+        assert_eq!(redact("Bearer token expired"), "Bearer [REDACTED] expired");
+        assert_eq!(redact("Bearer: none"), "Bearer: none");
+    }
+
+    #[test]
+    fn keeps_ordinary_text() {
+        let lines = [
+            "Correcting 1200 characters with gpt-5.4-mini in 3 chunks",
+            "internationalization and counterrevolutionaries are long words",
+            "Donaudampfschifffahrtsgesellschaftskapitän",
+            "a_very_long_snake_case_identifier_without_digits",
+            "sk-short",
+            "v2.0.1-beta3 finished at 2026-10-18T12:00:00Z",
+        ];
+        for line in lines {
+            assert_eq!(redact(line), line);
+        }
+    }
+}
@@ -0,0 +1,788 @@
+//! `correctify lsp`: a language server over stdio.
+//!
+//! Editors start `correctify lsp` for Markdown and plain-text files. Each
+//! paragraph is corrected when a document is opened or saved, and every
+//! word-level edit between the paragraph and its correction becomes a
+//! diagnostic with a quick fix, plus one to accept the whole paragraph.
+//!
+//! Settings come from `initializationOptions` or from the `correctify` section
+//! of `workspace/didChangeConfiguration`:
+//!
+//! ```json
+//! { "model": "gpt-5.4-mini", "style": "formal", "language": "en", "customRules": "Use British spelling" }
+//! ```
+//!
+//! Nothing but LSP messages is written to stdout; logs go to the log directory.
+
+use crate::error::AppError;
+use crate::review::{self, ReviewEdit};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+const SOURCE: &str = "correctify";
+const SEVERITY_INFORMATION: u8 = 3;
+const TEXT_DOCUMENT_SYNC_FULL: u8 = 1;
+const MESSAGE_TYPE_ERROR: u8 = 1;
+
+// JSON-RPC error codes
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// Settings for the corrections. `None` leaves the choice to the corrector.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Options {
+    pub model: Option<String>,
+    pub style: Option<String>,
+    pub language: Option<String>,
+    pub custom_rules: Option<String>,
+}
+
+/// Corrects one paragraph, blocking until the model answers.
+pub trait Corrector: Send + Sync {
+    fn correct(&self, paragraph: &str, options: &Options) -> Result<String, AppError>;
+}
+
+enum Event {
+    Message(Value),
+    /// The editor closed stdin or sent something that is not LSP.
+    Closed,
+    Corrected { generation: u64, paragraph: String, result: Result<String, AppError> },
+}
+
+struct Job {
+    generation: u64,
+    paragraph: String,
+    options: Options,
+}
+
+struct Document {
+    text: String,
+    markdown: bool,
+}
+
+/// A paragraph with a cached correction that differs from it. Edit offsets are
+/// relative to `text`, which starts at byte `start` of the document.
+struct Suggestion<'a> {
+    start: usize,
+    text: &'a str,
+    corrected: &'a str,
+    edits: Vec<ReviewEdit>,
+}
+
+/// Serves one editor until it sends `exit` or closes stdin. Returns whether it
+/// asked for a shutdown first, which decides the exit status.
+pub fn serve(input: impl Read + Send + 'static, output: impl Write, corrector: Arc<dyn Corrector>) -> io::Result<bool> {
+    let (events, inbox) = mpsc::channel();
+    let (jobs, queue) = mpsc::channel();
+    let generation = Arc::new(AtomicU64::new(0));
+
+    let reader_events = events.clone();
+    thread::spawn(move || read_messages(input, reader_events));
+    let worker_generation = generation.clone();
+    thread::spawn(move || correct_paragraphs(corrector, queue, events, worker_generation));
+
+    let mut server = Server::new(output, generation, jobs);
+    for event in inbox {
+        match event {
+            Event::Message(message) => {
+                if server.handle(message)? {
+                    break;
+                }
+            }
+            Event::Closed => break,
+            Event::Corrected { generation, paragraph, result } => server.corrected(generation, paragraph, result)?,
+        }
+    }
+    Ok(server.shutdown)
+}
+
+fn read_messages(input: impl Read, events: Sender<Event>) {
+    let mut reader = BufReader::new(input);
+    loop {
+        let body = match read_message(&mut reader) {
+            Ok(Some(body)) => body,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("Failed to read an LSP message: {}", e);
+                break;
+            }
+        };
+        match serde_json::from_slice(&body) {
+            Ok(message) => {
+                if events.send(Event::Message(message)).is_err() {
+                    return;
+                }
+            }
+            Err(e) => tracing::warn!("Ignoring an LSP message that is not JSON: {}", e),
+        }
+    }
+    let _ = events.send(Event::Closed);
+}
+
+/// Reads one `Content-Length` framed body, or `None` at the end of the input.
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+// One paragraph at a time keeps a freshly opened document from flooding the provider
+fn correct_paragraphs(corrector: Arc<dyn Corrector>, queue: Receiver<Job>, events: Sender<Event>, generation: Arc<AtomicU64>) {
+    for job in queue {
+        // Settings changed since this was queued
+        if job.generation != generation.load(Ordering::SeqCst) {
+            continue;
+        }
+        let result = corrector.correct(&job.paragraph, &job.options);
+        if events.send(Event::Corrected { generation: job.generation, paragraph: job.paragraph, result }).is_err() {
+            return;
+        }
+    }
+}
+
+struct Server<W: Write> {
+    output: W,
+    options: Options,
+    /// Bumped when the settings change, so corrections made with the old ones are dropped.
+    generation: Arc<AtomicU64>,
+    documents: HashMap<String, Document>,
+    /// Corrected text by paragraph text, shared by all documents.
+    corrections: HashMap<String, String>,
+    /// Paragraphs queued or being corrected.
+    pending: HashSet<String>,
+    jobs: Sender<Job>,
+    shutdown: bool,
+    /// The last failure shown, so a missing key is reported once rather than once per paragraph.
+    last_error: Option<String>,
+}
+
+impl<W: Write> Server<W> {
+    fn new(output: W, generation: Arc<AtomicU64>, jobs: Sender<Job>) -> Self {
+        Server {
+            output,
+            options: Options::default(),
+            generation,
+            documents: HashMap::new(),
+            corrections: HashMap::new(),
+            pending: HashSet::new(),
+            jobs,
+            shutdown: false,
+            last_error: None,
+        }
+    }
+
+    /// Handles one message from the editor. Returns true on `exit`.
+    fn handle(&mut self, message: Value) -> io::Result<bool> {
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            // A response; this server sends no requests
+            return Ok(false);
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let Some(id) = message.get("id").cloned() else {
+            return self.notification(method, &params);
+        };
+
+        if self.shutdown {
+            return self.reply_error(id, INVALID_REQUEST, "The server is shutting down").map(|_| false);
+        }
+        let result = match method {
+            "initialize" => {
+                if let Some(options) = params.get("initializationOptions") {
+                    self.options = parse_options(options);
+                }
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": TEXT_DOCUMENT_SYNC_FULL, "save": { "includeText": true } },
+                        "codeActionProvider": { "codeActionKinds": ["quickfix"] },
+                    },
+                    "serverInfo": { "name": "correctify", "version": env!("CARGO_PKG_VERSION") },
+                })
+            }
+            "shutdown" => {
+                self.shutdown = true;
+                Value::Null
+            }
+            "textDocument/codeAction" => self.code_actions(&params),
+            _ => return self.reply_error(id, METHOD_NOT_FOUND, &format!("Unsupported method {}", method)).map(|_| false),
+        };
+        write_message(&mut self.output, &json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+        Ok(false)
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> io::Result<bool> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "exit" => return Ok(true),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                let text = document["text"].as_str().unwrap_or_default().to_string();
+                let markdown = document["languageId"].as_str() == Some("markdown");
+                self.documents.insert(uri.clone(), Document { text, markdown });
+                self.check(&uri);
+                self.publish(&uri)?;
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change holds the whole text
+                let text = params["contentChanges"].as_array().and_then(|changes| changes.last()).and_then(|change| change["text"].as_str());
+                if let (Some(document), Some(text)) = (self.documents.get_mut(&uri), text) {
+                    document.text = text.to_string();
+                }
+                self.forget_unused();
+                self.publish(&uri)?;
+            }
+            "textDocument/didSave" => {
+                if let (Some(document), Some(text)) = (self.documents.get_mut(&uri), params["text"].as_str()) {
+                    document.text = text.to_string();
+                }
+                self.forget_unused();
+                self.check(&uri);
+                self.publish(&uri)?;
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+                self.forget_unused();
+                self.notify("textDocument/publishDiagnostics", json!({ "uri": uri, "diagnostics": [] }))?;
+            }
+            "workspace/didChangeConfiguration" => {
+                let options = parse_options(&params["settings"][SOURCE]);
+                if params["settings"][SOURCE].is_object() && options != self.options {
+                    self.options = options;
+                    self.generation.fetch_add(1, Ordering::SeqCst);
+                    self.corrections.clear();
+                    self.pending.clear();
+                    let uris: Vec<String> = self.documents.keys().cloned().collect();
+                    for uri in &uris {
+                        self.check(uri);
+                        self.publish(uri)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn reply_error(&mut self, id: Value, code: i64, message: &str) -> io::Result<()> {
+        write_message(&mut self.output, &json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }))
+    }
+
+    fn notify(&mut self, method: &str, params: Value) -> io::Result<()> {
+        write_message(&mut self.output, &json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+    }
+
+    /// Queues the paragraphs of a document that have no correction yet.
+    fn check(&mut self, uri: &str) {
+        let Some(document) = self.documents.get(uri) else {
+            return;
+        };
+        let generation = self.generation.load(Ordering::SeqCst);
+        for (_, paragraph) in paragraphs(&document.text, document.markdown) {
+            if self.corrections.contains_key(paragraph) || !self.pending.insert(paragraph.to_string()) {
+                continue;
+            }
+            let job = Job { generation, paragraph: paragraph.to_string(), options: self.options.clone() };
+            if self.jobs.send(job).is_err() {
+                tracing::error!("The correction worker stopped");
+                return;
+            }
+        }
+    }
+
+    fn corrected(&mut self, generation: u64, paragraph: String, result: Result<String, AppError>) -> io::Result<()> {
+        if generation != self.generation.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.pending.remove(&paragraph);
+        match result {
+            Ok(corrected) => {
+                self.last_error = None;
+                // The paragraph may have been edited away while it was corrected
+                if self.documents.values().any(|document| document.text.contains(&paragraph)) {
+                    self.corrections.insert(paragraph.clone(), corrected);
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to correct a paragraph: {}", e.details());
+                let message = format!("Correctify could not check this text ({}): {}", e.code(), e.details());
+                if self.last_error.as_ref() != Some(&message) {
+                    self.last_error = Some(message.clone());
+                    self.notify("window/showMessage", json!({ "type": MESSAGE_TYPE_ERROR, "message": message }))?;
+                }
+                return Ok(());
+            }
+        }
+        let uris: Vec<String> =
+            self.documents.iter().filter(|(_, document)| document.text.contains(&paragraph)).map(|(uri, _)| uri.clone()).collect();
+        for uri in &uris {
+            self.publish(uri)?;
+        }
+        Ok(())
+    }
+
+    // Drops corrections of paragraphs no open document holds any more, so a long session keeps only what it shows.
+    // Fixes offered by code actions stay until the editor applies one or the text moves on.
+    fn forget_unused(&mut self) {
+        let shown: HashSet<&str> = self
+            .documents
+            .values()
+            .flat_map(|document| paragraphs(&document.text, document.markdown))
+            .map(|(_, paragraph)| paragraph)
+            .collect();
+        self.corrections.retain(|paragraph, _| shown.contains(paragraph.as_str()));
+    }
+
+    /// The paragraphs of a document whose cached correction suggests edits.
+    fn suggestions<'a>(&'a self, document: &'a Document) -> Vec<Suggestion<'a>> {
+        paragraphs(&document.text, document.markdown)
+            .into_iter()
+            .filter_map(|(start, text)| {
+                let corrected = self.corrections.get(text)?;
+                let edits = review::edits(text, corrected);
+                (!edits.is_empty()).then_some(Suggestion { start, text, corrected, edits })
+            })
+            .collect()
+    }
+
+    fn publish(&mut self, uri: &str) -> io::Result<()> {
+        let Some(document) = self.documents.get(uri) else {
+            return Ok(());
+        };
+        let lines = LineIndex::new(&document.text);
+        let diagnostics: Vec<Value> = self
+            .suggestions(document)
+            .iter()
+            .flat_map(|suggestion| suggestion.edits.iter().map(|edit| diagnostic(&lines, suggestion.start, edit)))
+            .collect();
+        self.notify("textDocument/publishDiagnostics", json!({ "uri": uri, "diagnostics": diagnostics }))
+    }
+
+    /// A quick fix for each edit in the requested range, and one per paragraph to accept all of its edits.
+    fn code_actions(&mut self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let Some(document) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        let lines = LineIndex::new(&document.text);
+        let (Some(from), Some(to)) = (lines.offset(&params["range"]["start"]), lines.offset(&params["range"]["end"])) else {
+            return json!([]);
+        };
+
+        let mut actions = Vec::new();
+        // What each fix turns its paragraph into, and the correction that still applies afterwards
+        let mut outcomes = Vec::new();
+        for suggestion in self.suggestions(document) {
+            let touched: Vec<&ReviewEdit> =
+                suggestion.edits.iter().filter(|edit| suggestion.start + edit.start <= to && suggestion.start + edit.end >= from).collect();
+            if touched.is_empty() {
+                continue;
+            }
+            for edit in touched {
+                actions.push(code_action(uri, &describe(edit), &lines, suggestion.start, &[edit], true));
+                outcomes.push((review::apply(suggestion.text, [edit]), suggestion.corrected.to_string()));
+            }
+            if suggestion.edits.len() > 1 {
+                let edits: Vec<&ReviewEdit> = suggestion.edits.iter().collect();
+                let title = format!("Accept all {} corrections in this paragraph", edits.len());
+                actions.push(code_action(uri, &title, &lines, suggestion.start, &edits, false));
+                outcomes.push((suggestion.corrected.to_string(), suggestion.corrected.to_string()));
+            }
+        }
+        // The rest of a paragraph keeps its diagnostics after a fix without asking the model again
+        self.corrections.extend(outcomes);
+        Value::Array(actions)
+    }
+}
+
+fn parse_options(value: &Value) -> Options {
+    if value.is_null() {
+        return Options::default();
+    }
+    serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring invalid Correctify settings: {}", e);
+        Options::default()
+    })
+}
+
+/// Blank-line separated paragraphs with their byte offsets. Fenced code blocks
+/// in Markdown are skipped.
+fn paragraphs(text: &str, markdown: bool) -> Vec<(usize, &str)> {
+    let mut paragraphs = Vec::new();
+    let mut current: Option<(usize, usize)> = None;
+    let mut fence: Option<&str> = None;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let content = line.trim_end_matches(['\n', '\r']);
+        let trimmed = content.trim_start();
+        let marker = if markdown { ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker)) } else { None };
+
+        if fence.is_some() || marker.is_some() || trimmed.is_empty() {
+            paragraphs.extend(current.take().map(|(start, end)| (start, &text[start..end])));
+            match (fence, marker) {
+                (Some(open), Some(close)) if open == close => fence = None,
+                (None, Some(open)) => fence = Some(open),
+                _ => {}
+            }
+            continue;
+        }
+        let end = start + content.len();
+        current = Some((current.map_or(start, |(start, _)| start), end));
+    }
+    paragraphs.extend(current.map(|(start, end)| (start, &text[start..end])));
+    paragraphs
+}
+
+fn describe(edit: &ReviewEdit) -> String {
+    if edit.original.is_empty() {
+        format!("Insert \"{}\"", edit.corrected)
+    } else if edit.corrected.is_empty() {
+        format!("Remove \"{}\"", edit.original)
+    } else {
+        format!("\"{}\" → \"{}\"", edit.original, edit.corrected)
+    }
+}
+
+fn diagnostic(lines: &LineIndex, start: usize, edit: &ReviewEdit) -> Value {
+    json!({
+        "range": lines.range(start + edit.start, start + edit.end),
+        "severity": SEVERITY_INFORMATION,
+        "source": SOURCE,
+        "message": describe(edit),
+    })
+}
+
+fn code_action(uri: &str, title: &str, lines: &LineIndex, start: usize, edits: &[&ReviewEdit], preferred: bool) -> Value {
+    let changes: Vec<Value> = edits
+        .iter()
+        .map(|edit| json!({ "range": lines.range(start + edit.start, start + edit.end), "newText": edit.corrected }))
+        .collect();
+    let diagnostics: Vec<Value> = edits.iter().map(|edit| diagnostic(lines, start, edit)).collect();
+    json!({
+        "title": title,
+        "kind": "quickfix",
+        "diagnostics": diagnostics,
+        "isPreferred": preferred,
+        "edit": { "changes": { uri: changes } },
+    })
+}
+
+/// Converts between byte offsets and LSP positions, which count UTF-16 code units.
+struct LineIndex<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        LineIndex { text, starts }
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let character = self.text[self.starts[line]..offset].encode_utf16().count();
+        json!({ "line": line, "character": character })
+    }
+
+    fn range(&self, start: usize, end: usize) -> Value {
+        json!({ "start": self.position(start), "end": self.position(end) })
+    }
+
+    fn offset(&self, position: &Value) -> Option<usize> {
+        let line = usize::try_from(position["line"].as_u64()?).ok()?;
+        let character = usize::try_from(position["character"].as_u64()?).ok()?;
+        let Some(&start) = self.starts.get(line) else {
+            return Some(self.text.len());
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= character || c == '\n' {
+                return Some(start + i);
+            }
+            units += c.len_utf16();
+        }
+        Some(self.text.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const URI: &str = "file:///notes.md";
+
+    struct Stub;
+
+    impl Corrector for Stub {
+        fn correct(&self, paragraph: &str, options: &Options) -> Result<String, AppError> {
+            if paragraph.contains("offline") {
+                return Err(AppError::ProviderUnavailable("The provider is down".to_string()));
+            }
+            let corrected = paragraph.replace("teh", "the").replace("recieve", "receive");
+            Ok(match options.style.as_deref() {
+                Some("formal") => corrected.replace("it.", "it, thank you."),
+                _ => corrected,
+            })
+        }
+    }
+
+    fn frame(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    fn server() -> (Server<Vec<u8>>, Receiver<Job>) {
+        let (jobs, queue) = mpsc::channel();
+        (Server::new(Vec::new(), Arc::new(AtomicU64::new(0)), jobs), queue)
+    }
+
+    fn notify(server: &mut Server<Vec<u8>>, method: &str, params: Value) {
+        assert!(!server.handle(json!({ "jsonrpc": "2.0", "method": method, "params": params })).unwrap());
+    }
+
+    fn open(server: &mut Server<Vec<u8>>, text: &str) {
+        let document = json!({ "uri": URI, "languageId": "markdown", "version": 1, "text": text });
+        notify(server, "textDocument/didOpen", json!({ "textDocument": document }));
+    }
+
+    fn change(server: &mut Server<Vec<u8>>, text: &str) {
+        let params = json!({ "textDocument": { "uri": URI, "version": 2 }, "contentChanges": [{ "text": text }] });
+        notify(server, "textDocument/didChange", params);
+    }
+
+    // Runs the queued paragraphs through the stub as the worker thread would
+    fn work(server: &mut Server<Vec<u8>>, queue: &Receiver<Job>) -> Vec<String> {
+        let mut corrected = Vec::new();
+        for job in queue.try_iter() {
+            let result = Stub.correct(&job.paragraph, &job.options);
+            corrected.push(job.paragraph.clone());
+            server.corrected(job.generation, job.paragraph, result).unwrap();
+        }
+        corrected
+    }
+
+    fn sent(server: &mut Server<Vec<u8>>) -> Vec<Value> {
+        let output = std::mem::take(&mut server.output);
+        let mut reader = &output[..];
+        std::iter::from_fn(|| read_message(&mut reader).unwrap()).map(|body| serde_json::from_slice(&body).unwrap()).collect()
+    }
+
+    fn diagnostics(server: &mut Server<Vec<u8>>) -> Vec<Value> {
+        let published = sent(server).into_iter().rfind(|message| message["method"] == "textDocument/publishDiagnostics");
+        published.unwrap()["params"]["diagnostics"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn reads_framed_messages() {
+        let input = format!("{}Content-Type: application/vscode-jsonrpc; charset=utf-8\r\ncontent-length:  2\r\n\r\n{{}}", frame(r#"{"id":1}"#));
+        let mut reader = input.as_bytes();
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), br#"{"id":1}"#);
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), b"{}");
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        let mut output = Vec::new();
+        write_message(&mut output, &json!({ "text": "é😀" })).unwrap();
+        assert_eq!(read_message(&mut &output[..]).unwrap().unwrap(), r#"{"text":"é😀"}"#.as_bytes());
+    }
+
+    #[test]
+    fn stops_at_incomplete_or_unframed_input() {
+        // The editor went away in the middle of a header
+        assert_eq!(read_message(&mut &b"Content-Len"[..]).unwrap(), None);
+        assert_eq!(read_message(&mut &b"Content-Length: 10\r\n"[..]).unwrap(), None);
+
+        let short = read_message(&mut &b"Content-Length: 10\r\n\r\n{}"[..]).unwrap_err();
+        assert_eq!(short.kind(), io::ErrorKind::UnexpectedEof);
+        for input in ["Content-Type: text/plain\r\n\r\n{}", "Content-Length: ten\r\n\r\n{}", "Content-Length: -2\r\n\r\n{}"] {
+            assert_eq!(read_message(&mut input.as_bytes()).unwrap_err().kind(), io::ErrorKind::InvalidData, "{}", input);
+        }
+    }
+
+    #[test]
+    fn counts_positions_in_utf16_code_units() {
+        let text = "a😀b\r\né😀 teh\nlast";
+        let lines = LineIndex::new(text);
+        assert_eq!(lines.position(text.find('b').unwrap()), json!({ "line": 0, "character": 3 }));
+        assert_eq!(lines.position(text.find("teh").unwrap()), json!({ "line": 1, "character": 4 }));
+        assert_eq!(lines.position(text.len()), json!({ "line": 2, "character": 4 }));
+
+        let offset = |line: u64, character: u64| lines.offset(&json!({ "line": line, "character": character }));
+        assert_eq!(offset(0, 3), text.find('b'));
+        assert_eq!(offset(1, 4), text.find("teh"));
+        // Inside a surrogate pair, past the end of a line and past the last line
+        assert_eq!(offset(0, 2), text.find('b'));
+        assert_eq!(offset(1, 99), text.rfind('\n'));
+        assert_eq!(offset(9, 0), Some(text.len()));
+        assert_eq!(lines.offset(&json!({ "line": 0 })), None);
+    }
+
+    #[test]
+    fn splits_paragraphs_around_code_fences() {
+        let text = "# Title\r\nIntro line\r\n\r\n```rust\nlet teh = 1;\n\n~~~\n```\n   \nAfter\n~~~\nteh\n~~~\nEnd";
+        let found: Vec<&str> = paragraphs(text, true).into_iter().map(|(_, paragraph)| paragraph).collect();
+        assert_eq!(found, ["# Title\r\nIntro line", "After", "End"]);
+        for (start, paragraph) in paragraphs(text, true) {
+            assert_eq!(&text[start..start + paragraph.len()], paragraph);
+        }
+
+        // Plain text has no fences
+        let plain: Vec<&str> = paragraphs("```\nteh\n```\n\nnext", false).into_iter().map(|(_, paragraph)| paragraph).collect();
+        assert_eq!(plain, ["```\nteh\n```", "next"]);
+        assert!(paragraphs("\n \n", true).is_empty());
+    }
+
+    #[test]
+    fn reports_corrections_as_diagnostics_with_fixes() {
+        let (mut server, queue) = server();
+        open(&mut server, "I will recieve it.\n\nAlready fine.\n\n```\nteh code\n```\n\nSee teh 😀 teh end.\n");
+        assert!(diagnostics(&mut server).is_empty());
+        assert_eq!(work(&mut server, &queue), ["I will recieve it.", "Already fine.", "See teh 😀 teh end."]);
+
+        let found = diagnostics(&mut server);
+        let ranges: Vec<(&Value, &Value)> = found.iter().map(|diagnostic| (&diagnostic["range"], &diagnostic["message"])).collect();
+        assert_eq!(
+            ranges,
+            [
+                (&json!({ "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 14 } }), &json!("\"recieve\" → \"receive\"")),
+                (&json!({ "start": { "line": 8, "character": 4 }, "end": { "line": 8, "character": 7 } }), &json!("\"teh\" → \"the\"")),
+                (&json!({ "start": { "line": 8, "character": 11 }, "end": { "line": 8, "character": 14 } }), &json!("\"teh\" → \"the\"")),
+            ]
+        );
+
+        let position = json!({ "line": 8, "character": 12 });
+        let params = json!({ "textDocument": { "uri": URI }, "range": { "start": position, "end": position }, "context": { "diagnostics": [] } });
+        assert!(!server.handle(json!({ "jsonrpc": "2.0", "id": 7, "method": "textDocument/codeAction", "params": params })).unwrap());
+        let reply = sent(&mut server).remove(0);
+        assert_eq!(reply["id"], 7);
+        let actions = reply["result"].as_array().unwrap();
+        let titles: Vec<&str> = actions.iter().map(|action| action["title"].as_str().unwrap()).collect();
+        assert_eq!(titles, ["\"teh\" → \"the\"", "Accept all 2 corrections in this paragraph"]);
+        assert_eq!(actions[0]["isPreferred"], true);
+        assert_eq!(
+            actions[0]["edit"]["changes"][URI],
+            json!([{ "range": found[2]["range"], "newText": "the" }])
+        );
+        assert_eq!(actions[1]["edit"]["changes"][URI].as_array().unwrap().len(), 2);
+
+        // Applying the fix keeps the other diagnostic without asking the model again
+        change(&mut server, "I will recieve it.\n\nAlready fine.\n\n```\nteh code\n```\n\nSee teh 😀 the end.\n");
+        let remaining = diagnostics(&mut server);
+        assert_eq!(remaining.len(), 2);
+        assert_eq!(remaining[1]["range"], found[1]["range"]);
+        assert!(queue.try_recv().is_err());
+    }
+
+    #[test]
+    fn forgets_corrections_no_document_shows() {
+        let (mut server, queue) = server();
+        open(&mut server, "First teh.\n\nSecond teh.");
+        work(&mut server, &queue);
+        assert_eq!(server.corrections.len(), 2);
+
+        change(&mut server, "First teh.\n\nSecond teh, edited.");
+        let mut kept: Vec<&str> = server.corrections.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(kept, ["First teh."]);
+
+        // A correction that arrives after its paragraph was edited away is not kept
+        notify(&mut server, "textDocument/didSave", json!({ "textDocument": { "uri": URI }, "text": "First teh.\n\nThird teh." }));
+        change(&mut server, "First teh.");
+        assert_eq!(work(&mut server, &queue), ["Third teh."]);
+        assert_eq!(server.corrections.len(), 1);
+
+        notify(&mut server, "textDocument/didClose", json!({ "textDocument": { "uri": URI } }));
+        assert!(server.corrections.is_empty());
+        assert_eq!(sent(&mut server).last().unwrap()["params"], json!({ "uri": URI, "diagnostics": [] }));
+    }
+
+    #[test]
+    fn shows_a_repeated_failure_once() {
+        let (mut server, queue) = server();
+        open(&mut server, "We are offline.\n\nStill offline.");
+        work(&mut server, &queue);
+        let messages: Vec<Value> = sent(&mut server).into_iter().filter(|message| message["method"] == "window/showMessage").collect();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["params"]["type"], MESSAGE_TYPE_ERROR);
+        assert!(messages[0]["params"]["message"].as_str().unwrap().contains("The provider is down"));
+        assert!(server.corrections.is_empty() && server.pending.is_empty());
+    }
+
+    #[test]
+    fn corrects_again_when_the_settings_change() {
+        let (mut server, queue) = server();
+        open(&mut server, "I recieve it.");
+        let stale: Vec<Job> = queue.try_iter().collect();
+
+        let settings = json!({ "settings": { "correctify": { "style": "formal" } } });
+        notify(&mut server, "workspace/didChangeConfiguration", settings.clone());
+        assert_eq!(server.options.style.as_deref(), Some("formal"));
+        // The answer to the old settings arrives late and is dropped
+        for job in stale {
+            server.corrected(job.generation, job.paragraph, Ok("I receive it.".to_string())).unwrap();
+        }
+        assert!(server.corrections.is_empty());
+        work(&mut server, &queue);
+        assert_eq!(server.corrections["I recieve it."], "I receive it, thank you.");
+
+        // The same settings again change nothing
+        notify(&mut server, "workspace/didChangeConfiguration", settings);
+        assert!(queue.try_recv().is_err());
+        assert_eq!(server.corrections.len(), 1);
+    }
+
+    #[test]
+    fn serves_until_exit() {
+        let input = [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": { "initializationOptions": { "model": "gpt-5.4-mini" } } }),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "textDocument/codeAction", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+        ];
+        let input: String = input.iter().map(|message| frame(&message.to_string())).collect();
+        let mut output = Vec::new();
+        assert!(serve(Cursor::new(input.into_bytes()), &mut output, Arc::new(Stub)).unwrap());
+
+        let mut reader = &output[..];
+        let replies: Vec<Value> =
+            std::iter::from_fn(|| read_message(&mut reader).unwrap()).map(|body| serde_json::from_slice(&body).unwrap()).collect();
+        assert_eq!(replies[0]["result"]["serverInfo"]["name"], "correctify");
+        assert_eq!(replies[1]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(replies[2]["result"], Value::Null);
+        assert_eq!(replies[3]["error"]["code"], INVALID_REQUEST);
+
+        // Closing stdin without a shutdown is an unclean exit
+        assert!(!serve(Cursor::new(Vec::new()), io::sink(), Arc::new(Stub)).unwrap());
+    }
+}
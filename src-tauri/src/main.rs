@@ -2,5 +2,17 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    // `correctify lsp` is a language server for editors and never opens a window
+    if args.get(1).map(String::as_str) == Some("lsp") {
+        return correctify_lib::run_lsp();
+    }
+    if args.get(1).map(String::as_str) == Some("native-host") {
+        return correctify_lib::run_native_host_setup(&args[2..]);
+    }
+    // Browsers start the native messaging host for their extensions
+    if correctify_lib::launched_by_browser(&args) {
+        return correctify_lib::run_native_host();
+    }
     correctify_lib::run()
 }
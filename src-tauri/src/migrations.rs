@@ -0,0 +1,563 @@
+//! Versioned migrations of stored data.
+//!
+//! Each release that changes how keys, settings, presets or usage history
+//! are stored appends a step to [`MIGRATIONS`]. The schema version is the
+//! number of steps applied, recorded in the app data directory and in the
+//! webview's localStorage; the lower of the two wins, so clearing either
+//! side replays steps instead of skipping them. Every step must therefore
+//! be idempotent.
+//!
+//! A step runs as a transaction. Credential writes are journaled and undone
+//! if the step fails, localStorage edits are buffered and handed to the
+//! webview only once the step commits, and legacy sources (old key files,
+//! per-provider vault entries) are deleted last. A source that cannot be
+//! deleted leaves the version where it was, so the next start retries it.
+
+use crate::api_keys;
+use crate::credentials::CredentialStore;
+use crate::error::AppError;
+use crate::llm::Provider;
+use crate::prompts::WRITING_STYLES;
+use base64::{engine::general_purpose, Engine as _};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The localStorage key holding the webview's copy of the schema version.
+pub const LOCAL_VERSION_KEY: &str = "correctify_schema_version";
+const VERSION_FILE: &str = "schema-version";
+const LEGACY_KEY_DIRECTORY: &str = ".keys";
+const LEGACY_MIGRATION_FLAG: &str = "MIGRATION_COMPLETE_V1_1_0";
+const PRESETS_KEY: &str = "correctify_presets_v1";
+const USAGE_HISTORY_KEY: &str = "correctify_usage_history_v2";
+const BOOLEAN_SETTINGS: [&str; 3] = ["autostart-enabled", "sound-enabled", "auto-paste-enabled"];
+
+struct Migration {
+    name: &'static str,
+    apply: fn(&mut Transaction) -> Result<(), AppError>,
+}
+
+/// Append only: a step's position is its version.
+const MIGRATIONS: [Migration; 5] = [
+    Migration { name: "consolidate-legacy-keys", apply: consolidate_legacy_keys },
+    Migration { name: "key-profiles", apply: key_profiles },
+    Migration { name: "settings", apply: normalize_settings },
+    Migration { name: "presets", apply: normalize_presets },
+    Migration { name: "usage-history", apply: normalize_usage_history },
+];
+
+/// The schema version of this build.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedMigration {
+    pub name: &'static str,
+    pub error: AppError,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationReport {
+    /// The schema version reached. The webview stores it after applying `local_changes`.
+    pub version: u32,
+    pub applied: Vec<&'static str>,
+    /// localStorage writes for the webview to make; None removes the key.
+    pub local_changes: BTreeMap<String, Option<String>>,
+    /// The step that was rolled back, retried on the next start.
+    pub failed: Option<FailedMigration>,
+}
+
+/// Wraps a credential store and remembers the first value each record had,
+/// so a failed step can put everything back.
+struct Journal<'a> {
+    store: &'a mut dyn CredentialStore,
+    originals: Vec<(String, Option<String>)>,
+}
+
+impl Journal<'_> {
+    fn remember(&mut self, key: &str) -> Result<(), AppError> {
+        if self.originals.iter().any(|(name, _)| name == key) {
+            return Ok(());
+        }
+        let original = match self.store.get(key) {
+            Ok(value) => Some(value),
+            Err(AppError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        self.originals.push((key.to_string(), original));
+        Ok(())
+    }
+
+    fn rollback(self) {
+        for (key, original) in self.originals.into_iter().rev() {
+            let restored = match original {
+                Some(value) => self.store.set(&key, &value),
+                None => match self.store.remove(&key) {
+                    Err(AppError::NotFound(_)) => Ok(()),
+                    result => result,
+                },
+            };
+            if let Err(e) = restored {
+                tracing::error!("Could not roll back credential '{}': {}", key, e);
+            }
+        }
+    }
+}
+
+impl CredentialStore for Journal<'_> {
+    fn get(&self, key: &str) -> Result<String, AppError> {
+        self.store.get(key)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+        self.remember(key)?;
+        self.store.set(key, value)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), AppError> {
+        self.remember(key)?;
+        self.store.remove(key)
+    }
+}
+
+/// A legacy source to delete once the step that read it has committed.
+enum Cleanup {
+    VaultEntry(String),
+    File(PathBuf),
+}
+
+struct Transaction<'a> {
+    credentials: Journal<'a>,
+    legacy: &'a mut dyn CredentialStore,
+    data_dir: &'a Path,
+    local: BTreeMap<String, String>,
+    cleanup: Vec<Cleanup>,
+}
+
+impl Transaction<'_> {
+    fn get(&self, key: &str) -> Result<Option<String>, AppError> {
+        match self.credentials.get(key) {
+            Ok(value) => Ok(Some(value)),
+            Err(AppError::NotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Rewrites a JSON array in localStorage, dropping entries `update` returns None for
+    fn update_local_list(&mut self, key: &str, update: impl Fn(Map<String, Value>) -> Option<Map<String, Value>>) {
+        let Some(raw) = self.local.get(key) else { return };
+        let Ok(Value::Array(items)) = serde_json::from_str::<Value>(raw) else {
+            tracing::warn!("Leaving unreadable '{}' in place", key);
+            return;
+        };
+        let items: Vec<Value> = items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::Object(fields) => update(fields).map(Value::Object),
+                _ => None,
+            })
+            .collect();
+        self.local.insert(key.to_string(), Value::Array(items).to_string());
+    }
+}
+
+fn legacy_provider(name: &str) -> Option<Provider> {
+    let id = name.strip_prefix("correctify_").unwrap_or(name).strip_suffix("-api-key")?;
+    Provider::ALL.into_iter().find(|provider| provider.id() == id)
+}
+
+/// Gathers keys from the three places older releases kept them: base64 files
+/// under `.keys`, one vault entry per provider, and plain localStorage.
+fn consolidate_legacy_keys(tx: &mut Transaction) -> Result<(), AppError> {
+    let mut found = BTreeMap::new();
+
+    let directory = tx.data_dir.join(LEGACY_KEY_DIRECTORY);
+    if directory.is_dir() {
+        let entries =
+            fs::read_dir(&directory).map_err(|e| AppError::StorageFailed(format!("Failed to read legacy keys: {}", e)))?;
+        for entry in entries {
+            let path = entry
+                .map_err(|e| AppError::StorageFailed(format!("Failed to read legacy key entry: {}", e)))?
+                .path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("dat") {
+                continue;
+            }
+            let Some(provider) = path.file_stem().and_then(|stem| stem.to_str()).and_then(legacy_provider) else {
+                tracing::warn!("Skipping unrecognized legacy key file {}", path.display());
+                continue;
+            };
+            let encoded = fs::read_to_string(&path)
+                .map_err(|e| AppError::StorageFailed(format!("Failed to read legacy credential: {}", e)))?;
+            let value = general_purpose::STANDARD
+                .decode(encoded.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .ok_or_else(|| AppError::StorageFailed(format!("Legacy credential {} is damaged", path.display())))?;
+            found.entry(provider).or_insert(value);
+            tx.cleanup.push(Cleanup::File(path));
+        }
+    }
+
+    for provider in Provider::ALL {
+        let name = format!("correctify_{}-api-key", provider.id());
+        match tx.legacy.get(&name) {
+            Ok(value) => {
+                found.entry(provider).or_insert(value);
+                tx.cleanup.push(Cleanup::VaultEntry(name));
+            }
+            // A vault that does not answer never held anything for this install
+            Err(AppError::NotFound(_)) | Err(AppError::KeyringUnavailable(_)) => {}
+            Err(e) => return Err(e),
+        }
+
+        let local_name = format!("{}-api-key", provider.id());
+        if let Some(value) = tx.local.remove(&local_name) {
+            found.entry(provider).or_insert(value);
+        }
+    }
+
+    if found.is_empty() {
+        return Ok(());
+    }
+    let current = tx.get(api_keys::API_KEYS_RECORD)?;
+    if let Some(record) = api_keys::adopt_legacy(current.as_deref(), &found)? {
+        tx.credentials.set(api_keys::API_KEYS_RECORD, &record)?;
+    }
+    Ok(())
+}
+
+/// Stores a flat `{provider: key}` record as "Default" profiles.
+fn key_profiles(tx: &mut Transaction) -> Result<(), AppError> {
+    if let Some(current) = tx.get(api_keys::API_KEYS_RECORD)? {
+        if let Some(record) = api_keys::upgrade(&current)? {
+            tx.credentials.set(api_keys::API_KEYS_RECORD, &record)?;
+        }
+    }
+    Ok(())
+}
+
+/// Drops the pre-registry migration flag and settings no release would have
+/// written, so the app falls back to their defaults.
+fn normalize_settings(tx: &mut Transaction) -> Result<(), AppError> {
+    tx.local.remove(LEGACY_MIGRATION_FLAG);
+    if tx.local.get("writing-style").is_some_and(|style| !WRITING_STYLES.contains(&style.as_str())) {
+        tx.local.remove("writing-style");
+    }
+    for key in BOOLEAN_SETTINGS {
+        if tx.local.get(key).is_some_and(|value| value != "true" && value != "false") {
+            tx.local.remove(key);
+        }
+    }
+    Ok(())
+}
+
+/// Fills fields added to presets after their first release and drops
+/// presets without an id or name.
+fn normalize_presets(tx: &mut Transaction) -> Result<(), AppError> {
+    tx.update_local_list(PRESETS_KEY, |mut preset| {
+        let named = ["id", "name"].iter().all(|field| preset.get(*field).is_some_and(Value::is_string));
+        if !named {
+            return None;
+        }
+        let style = preset.get("writingStyle").and_then(Value::as_str);
+        if !style.is_some_and(|style| WRITING_STYLES.contains(&style)) {
+            preset.insert("writingStyle".to_string(), Value::from("grammar"));
+        }
+        preset.entry("customRules").or_insert_with(|| Value::from(""));
+        preset.entry("language").or_insert_with(|| Value::from("auto"));
+        Some(preset)
+    });
+    Ok(())
+}
+
+/// Stores the defaults that entries recorded before style, language and edit
+/// tracking were read with.
+fn normalize_usage_history(tx: &mut Transaction) -> Result<(), AppError> {
+    tx.update_local_list(USAGE_HISTORY_KEY, |mut entry| {
+        entry.entry("writingStyle").or_insert_with(|| Value::from("grammar"));
+        entry.entry("language").or_insert_with(|| Value::from("unknown"));
+        entry.entry("detectedEdits").or_insert_with(|| Value::from(0));
+        entry.entry("acceptedEdits").or_insert_with(|| Value::from(0));
+        Some(entry)
+    });
+    Ok(())
+}
+
+fn recorded_version(data_dir: &Path) -> u32 {
+    fs::read_to_string(data_dir.join(VERSION_FILE))
+        .ok()
+        .and_then(|version| version.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn record_version(data_dir: &Path, version: u32) -> Result<(), AppError> {
+    fs::create_dir_all(data_dir)
+        .and_then(|_| fs::write(data_dir.join(VERSION_FILE), version.to_string()))
+        .map_err(|e| AppError::StorageFailed(format!("Failed to record schema version: {}", e)))
+}
+
+fn clean_up(legacy: &mut dyn CredentialStore, cleanup: Vec<Cleanup>) -> Result<(), AppError> {
+    for item in cleanup {
+        match item {
+            Cleanup::VaultEntry(name) => match legacy.remove(&name) {
+                Ok(()) | Err(AppError::NotFound(_)) => {}
+                Err(e) => return Err(e),
+            },
+            Cleanup::File(path) => {
+                if let Err(e) = fs::remove_file(&path) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        return Err(AppError::StorageFailed(format!(
+                            "Failed to remove legacy credential {}: {}",
+                            path.display(),
+                            e
+                        )));
+                    }
+                }
+                // Removed once empty; a failure only leaves an empty directory behind
+                if let Some(directory) = path.parent() {
+                    let _ = fs::remove_dir(directory);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Brings stored data up to [`SCHEMA_VERSION`]. `store` is the active
+/// credential store, `legacy` the OS vault and `local` the webview's
+/// localStorage. Stops at the first step that fails, after rolling it back.
+pub fn run(
+    store: &mut dyn CredentialStore,
+    legacy: &mut dyn CredentialStore,
+    data_dir: &Path,
+    local: BTreeMap<String, String>,
+) -> MigrationReport {
+    let local_version = local.get(LOCAL_VERSION_KEY).and_then(|version| version.parse().ok()).unwrap_or(0);
+    let mut version = recorded_version(data_dir).min(local_version).min(SCHEMA_VERSION);
+    let mut current = local.clone();
+    let mut applied = Vec::new();
+    let mut failed = None;
+
+    for migration in &MIGRATIONS[version as usize..] {
+        let mut tx = Transaction {
+            credentials: Journal { store: &mut *store, originals: Vec::new() },
+            legacy: &mut *legacy,
+            data_dir,
+            local: current.clone(),
+            cleanup: Vec::new(),
+        };
+        let outcome = (migration.apply)(&mut tx);
+        let Transaction { credentials, legacy: vault, local: changed, cleanup, .. } = tx;
+        if let Err(error) = outcome {
+            credentials.rollback();
+            tracing::warn!(migration = migration.name, "Migration rolled back: {}", error);
+            failed = Some(FailedMigration { name: migration.name, error });
+            break;
+        }
+        // Committed: keep the step's changes even if cleanup has to be retried
+        current = changed;
+        if let Err(error) = clean_up(vault, cleanup).and_then(|_| record_version(data_dir, version + 1)) {
+            tracing::warn!(migration = migration.name, "Migration will be retried: {}", error);
+            failed = Some(FailedMigration { name: migration.name, error });
+            break;
+        }
+        version += 1;
+        applied.push(migration.name);
+        tracing::info!(migration = migration.name, version, "Migration applied");
+    }
+
+    let mut local_changes = BTreeMap::new();
+    for (key, value) in &current {
+        if local.get(key) != Some(value) {
+            local_changes.insert(key.clone(), Some(value.clone()));
+        }
+    }
+    for key in local.keys().filter(|key| !current.contains_key(*key)) {
+        local_changes.insert(key.clone(), None);
+    }
+    local_changes.remove(LOCAL_VERSION_KEY);
+    MigrationReport { version, applied, local_changes, failed }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use std::collections::HashMap;
+
+    #[derive(Default)]
+    struct FakeStore {
+        records: HashMap<String, String>,
+        /// Writes to this record fail, to exercise rollback.
+        failing: Option<String>,
+    }
+
+    impl CredentialStore for FakeStore {
+        fn get(&self, key: &str) -> Result<String, AppError> {
+            self.records.get(key).cloned().ok_or_else(|| AppError::NotFound(key.to_string()))
+        }
+
+        fn set(&mut self, key: &str, value: &str) -> Result<(), AppError> {
+            if self.failing.as_deref() == Some(key) {
+                return Err(AppError::StorageFailed(format!("refusing to write {}", key)));
+            }
+            self.records.insert(key.to_string(), value.to_string());
+            Ok(())
+        }
+
+        fn remove(&mut self, key: &str) -> Result<(), AppError> {
+            if self.failing.as_deref() == Some(key) {
+                return Err(AppError::StorageFailed(format!("refusing to remove {}", key)));
+            }
+            self.records.remove(key).map(|_| ()).ok_or_else(|| AppError::NotFound(key.to_string()))
+        }
+    }
+
+    fn local(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn stored_keys(store: &FakeStore) -> Value {
+        serde_json::from_str(&store.records[api_keys::API_KEYS_RECORD]).unwrap()
+    }
+
+    #[test]
+    fn consolidates_every_legacy_source() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        fs::create_dir_all(directory.join(LEGACY_KEY_DIRECTORY)).unwrap();
+        let file = directory.join(LEGACY_KEY_DIRECTORY).join("correctify_mistral-api-key.dat");
+        fs::write(&file, general_purpose::STANDARD.encode("mistral-key")).unwrap();
+        let mut store = FakeStore::default();
+        let mut legacy = FakeStore::default();
+        legacy.records.insert("correctify_openai-api-key".to_string(), "openai-key".to_string());
+
+        let report = run(&mut store, &mut legacy, directory, local(&[("anthropic-api-key", "anthropic-key")]));
+
+        assert!(report.failed.is_none());
+        assert_eq!(report.version, SCHEMA_VERSION);
+        let keys = stored_keys(&store);
+        let profiles = keys["profiles"].as_array().unwrap();
+        assert_eq!(profiles.len(), 3);
+        assert!(profiles.iter().all(|profile| profile["name"] == api_keys::DEFAULT_PROFILE));
+        assert!(legacy.records.is_empty());
+        assert!(!file.exists());
+        assert_eq!(report.local_changes.get("anthropic-api-key"), Some(&None));
+        assert_eq!(recorded_version(directory), SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn keeps_keys_already_in_the_record() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        let mut store = FakeStore::default();
+        store.records.insert(api_keys::API_KEYS_RECORD.to_string(), r#"{"openai":"current-key"}"#.to_string());
+        let mut legacy = FakeStore::default();
+        legacy.records.insert("correctify_openai-api-key".to_string(), "stale-key".to_string());
+
+        run(&mut store, &mut legacy, directory, BTreeMap::new());
+
+        let keys = stored_keys(&store);
+        assert_eq!(keys["profiles"][0]["key"], "current-key");
+        assert_eq!(keys["active"]["openai"], api_keys::DEFAULT_PROFILE);
+        assert!(legacy.records.is_empty());
+    }
+
+    #[test]
+    fn rolls_back_a_failed_step() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        let mut store = FakeStore { failing: Some(api_keys::API_KEYS_RECORD.to_string()), ..FakeStore::default() };
+        let mut legacy = FakeStore::default();
+        legacy.records.insert("correctify_openai-api-key".to_string(), "openai-key".to_string());
+
+        let report = run(&mut store, &mut legacy, directory, local(&[("openai-api-key", "local-key")]));
+
+        assert_eq!(report.version, 0);
+        assert_eq!(report.failed.map(|failed| failed.name), Some("consolidate-legacy-keys"));
+        assert!(report.local_changes.is_empty());
+        assert!(store.records.is_empty());
+        assert_eq!(legacy.records.len(), 1);
+        assert_eq!(recorded_version(directory), 0);
+    }
+
+    #[test]
+    fn restores_records_written_before_the_failure() {
+        let mut store = FakeStore { failing: Some("second".to_string()), ..FakeStore::default() };
+        store.records.insert("first".to_string(), "original".to_string());
+        let mut journal = Journal { store: &mut store, originals: Vec::new() };
+        journal.set("first", "changed").unwrap();
+        journal.set("new", "value").unwrap();
+        assert!(journal.set("second", "value").is_err());
+        journal.rollback();
+
+        assert_eq!(store.records.len(), 1);
+        assert_eq!(store.records["first"], "original");
+    }
+
+    #[test]
+    fn retries_when_a_legacy_source_cannot_be_removed() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        let mut store = FakeStore::default();
+        let legacy_name = "correctify_openai-api-key".to_string();
+        let mut legacy = FakeStore { failing: Some(legacy_name.clone()), ..FakeStore::default() };
+        legacy.records.insert(legacy_name.clone(), "openai-key".to_string());
+
+        let report = run(&mut store, &mut legacy, directory, BTreeMap::new());
+        assert_eq!(report.version, 0);
+        assert!(store.records.contains_key(api_keys::API_KEYS_RECORD));
+
+        legacy.failing = None;
+        let report = run(&mut store, &mut legacy, directory, BTreeMap::new());
+        assert_eq!(report.version, SCHEMA_VERSION);
+        assert!(legacy.records.is_empty());
+        assert_eq!(stored_keys(&store)["profiles"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn replays_from_the_lower_recorded_version() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        record_version(directory, SCHEMA_VERSION).unwrap();
+        let mut store = FakeStore::default();
+        let mut legacy = FakeStore::default();
+        let history = r#"[{"timestamp":1,"provider":"openai","model":"gpt","duration":5,"success":true}]"#;
+
+        let report = run(&mut store, &mut legacy, directory, local(&[(USAGE_HISTORY_KEY, history)]));
+        assert_eq!(report.applied.len(), MIGRATIONS.len());
+        let entries: Value = serde_json::from_str(report.local_changes[USAGE_HISTORY_KEY].as_deref().unwrap()).unwrap();
+        assert_eq!(entries[0]["writingStyle"], "grammar");
+        assert_eq!(entries[0]["acceptedEdits"], 0);
+
+        let up_to_date = local(&[(LOCAL_VERSION_KEY, &SCHEMA_VERSION.to_string())]);
+        let report = run(&mut store, &mut legacy, directory, up_to_date);
+        assert!(report.applied.is_empty());
+        assert!(report.local_changes.is_empty());
+    }
+
+    #[test]
+    fn normalizes_settings_and_presets() {
+        let data = testing::data_dir();
+        let directory = data.path();
+        let presets = r#"[{"id":"a","name":"Work","writingStyle":"shouty","customRules":"x"},{"name":"orphan"}]"#;
+        let report = run(
+            &mut FakeStore::default(),
+            &mut FakeStore::default(),
+            directory,
+            local(&[(LEGACY_MIGRATION_FLAG, "true"), ("sound-enabled", "yes"), ("auto-paste-enabled", "true"), (PRESETS_KEY, presets)]),
+        );
+
+        assert_eq!(report.local_changes[LEGACY_MIGRATION_FLAG], None);
+        assert_eq!(report.local_changes["sound-enabled"], None);
+        assert!(!report.local_changes.contains_key("auto-paste-enabled"));
+        let presets: Value = serde_json::from_str(report.local_changes[PRESETS_KEY].as_deref().unwrap()).unwrap();
+        assert_eq!(presets.as_array().unwrap().len(), 1);
+        assert_eq!(presets[0]["writingStyle"], "grammar");
+        assert_eq!(presets[0]["language"], "auto");
+        assert_eq!(presets[0]["customRules"], "x");
+    }
+}
@@ -0,0 +1,484 @@
+//! Native messaging host for browser extensions.
+//!
+//! A browser extension sends the selected text of a textarea and gets the
+//! corrected text and the edit list back, instead of Correctify simulating
+//! copy and paste in the page. Browsers start the executable themselves with
+//! the calling extension's origin (Chromium) or the manifest path and the
+//! extension id (Firefox), and exchange JSON messages over stdio, each
+//! preceded by its length as a native-endian `u32`:
+//!
+//! ```json
+//! { "id": 1, "type": "correct", "text": "teh text", "style": "formal" }
+//! { "id": 1, "type": "corrected", "text": "the text", "edits": [{ "original": "teh", "corrected": "the", "start": 0, "end": 3 }] }
+//! { "id": 1, "type": "error", "error": { "code": "missing-api-key", "messageKey": "errors.missingApiKey", "details": "..." } }
+//! ```
+//!
+//! Edit offsets count UTF-16 code units, as JavaScript strings do. `model`,
+//! `style`, `language` and `customRules` are optional and default to the
+//! model and style last selected in the app.
+//!
+//! `correctify native-host install <extension-id>...` registers the host with
+//! the installed browsers, and `correctify native-host uninstall` removes it.
+
+use crate::error::AppError;
+use crate::lsp::{Corrector, Options};
+use crate::review;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The host name extensions pass to `runtime.connectNative`.
+pub const NAME: &str = "com.correctify.correctify";
+const DESCRIPTION: &str = "Correctify grammar correction";
+
+// Chromium refuses messages from a host larger than this
+const MAX_OUTGOING: usize = 1024 * 1024;
+// Browsers send at most 64 MiB
+const MAX_INCOMING: usize = 64 * 1024 * 1024;
+
+const SELECTED_SETTINGS_FILE: &str = "selected-settings.json";
+
+/// The model and style last selected in the app, for corrections made without it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SelectedSettings {
+    pub model: Option<String>,
+    pub style: Option<String>,
+}
+
+impl SelectedSettings {
+    pub fn load(data_dir: &Path) -> Self {
+        fs::read_to_string(data_dir.join(SELECTED_SETTINGS_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, data_dir: &Path) -> Result<(), AppError> {
+        let contents = serde_json::to_string_pretty(self).map_err(|e| AppError::StorageFailed(e.to_string()))?;
+        fs::create_dir_all(data_dir)
+            .and_then(|_| fs::write(data_dir.join(SELECTED_SETTINGS_FILE), contents))
+            .map_err(|e| AppError::StorageFailed(format!("Failed to save the selected settings: {}", e)))
+    }
+}
+
+/// Whether a browser started this process as a native messaging host.
+pub fn launched_by_browser(argv: &[String]) -> bool {
+    match argv.get(1) {
+        Some(origin) if origin.starts_with("chrome-extension://") => true,
+        Some(manifest) => argv.len() >= 3 && manifest.ends_with(".json") && Path::new(manifest).is_file(),
+        None => false,
+    }
+}
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    text: String,
+    #[serde(flatten)]
+    options: Options,
+}
+
+/// One change in UTF-16 code units of the sent text.
+#[derive(Serialize)]
+struct Edit {
+    original: String,
+    corrected: String,
+    start: usize,
+    end: usize,
+}
+
+/// Answers messages one at a time until the browser closes stdin.
+pub fn serve(mut input: impl Read, mut output: impl Write, corrector: &dyn Corrector) -> io::Result<()> {
+    while let Some(body) = read_message(&mut input)? {
+        let response = match serde_json::from_slice::<Request>(&body) {
+            Ok(request) => respond(request, corrector),
+            Err(e) => error_response(Value::Null, AppError::InvalidArgument(format!("Malformed message: {}", e))),
+        };
+        write_message(&mut output, &response)?;
+    }
+    Ok(())
+}
+
+fn respond(request: Request, corrector: &dyn Corrector) -> Value {
+    if request.kind != "correct" {
+        return error_response(request.id, AppError::InvalidArgument(format!("Unknown message type '{}'", request.kind)));
+    }
+    let corrected = match corrector.correct(&request.text, &request.options) {
+        Ok(corrected) => corrected,
+        Err(e) => return error_response(request.id, e),
+    };
+    let edits: Vec<Edit> = review::edits(&request.text, &corrected)
+        .into_iter()
+        .map(|edit| Edit {
+            start: utf16_offset(&request.text, edit.start),
+            end: utf16_offset(&request.text, edit.end),
+            original: edit.original,
+            corrected: edit.corrected,
+        })
+        .collect();
+    let response = json!({ "id": request.id, "type": "corrected", "text": corrected, "edits": edits });
+    if response.to_string().len() > MAX_OUTGOING {
+        return error_response(request.id, AppError::InvalidArgument("The correction is too long to send to the browser".to_string()));
+    }
+    response
+}
+
+fn error_response(id: Value, error: AppError) -> Value {
+    tracing::warn!(code = error.code(), "Native messaging request failed: {}", error.details());
+    json!({ "id": id, "type": "error", "error": error })
+}
+
+fn utf16_offset(text: &str, offset: usize) -> usize {
+    text[..offset].encode_utf16().count()
+}
+
+/// Reads one length-prefixed message, or `None` once the browser disconnects.
+fn read_message(input: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_INCOMING {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Message of {} bytes is too large", length)));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    output.write_all(&(body.len() as u32).to_ne_bytes())?;
+    output.write_all(body.as_bytes())?;
+    output.flush()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    Chromium,
+    Firefox,
+}
+
+enum Location {
+    /// The browser looks for `<NAME>.json` in this directory.
+    #[cfg_attr(target_os = "windows", allow(dead_code))]
+    Directory(PathBuf),
+    /// The default value of this key under `HKEY_CURRENT_USER` holds the manifest path.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    Registry(&'static str),
+}
+
+struct Browser {
+    family: Family,
+    location: Location,
+}
+
+#[cfg(target_os = "linux")]
+fn browsers() -> Vec<Browser> {
+    let Some(home) = dirs::home_dir() else {
+        return Vec::new();
+    };
+    let config = dirs::config_dir().unwrap_or_else(|| home.join(".config"));
+    let chromium = ["google-chrome", "chromium", "microsoft-edge", "BraveSoftware/Brave-Browser", "vivaldi"];
+    let mut browsers: Vec<Browser> = chromium
+        .iter()
+        .map(|profile| Browser { family: Family::Chromium, location: Location::Directory(config.join(profile).join("NativeMessagingHosts")) })
+        .collect();
+    browsers.push(Browser { family: Family::Firefox, location: Location::Directory(home.join(".mozilla").join("native-messaging-hosts")) });
+    browsers
+}
+
+#[cfg(target_os = "macos")]
+fn browsers() -> Vec<Browser> {
+    let Some(support) = dirs::data_dir() else {
+        return Vec::new();
+    };
+    let chromium = ["Google/Chrome", "Chromium", "Microsoft Edge", "BraveSoftware/Brave-Browser", "Vivaldi"];
+    let mut browsers: Vec<Browser> = chromium
+        .iter()
+        .map(|profile| Browser { family: Family::Chromium, location: Location::Directory(support.join(profile).join("NativeMessagingHosts")) })
+        .collect();
+    browsers.push(Browser { family: Family::Firefox, location: Location::Directory(support.join("Mozilla").join("NativeMessagingHosts")) });
+    browsers
+}
+
+#[cfg(target_os = "windows")]
+fn browsers() -> Vec<Browser> {
+    vec![
+        // Chromium, Brave and Vivaldi read Chrome's key too
+        Browser { family: Family::Chromium, location: Location::Registry(r"Software\Google\Chrome\NativeMessagingHosts") },
+        Browser { family: Family::Chromium, location: Location::Registry(r"Software\Microsoft\Edge\NativeMessagingHosts") },
+        Browser { family: Family::Firefox, location: Location::Registry(r"Software\Mozilla\NativeMessagingHosts") },
+    ]
+}
+
+// Chromium extension ids are 32 letters from a to p; Firefox ids look like e-mail addresses or GUIDs
+fn family_of(extension_id: &str) -> Result<Family, AppError> {
+    if extension_id.len() == 32 && extension_id.bytes().all(|b| (b'a'..=b'p').contains(&b)) {
+        Ok(Family::Chromium)
+    } else if extension_id.contains('@') || (extension_id.starts_with('{') && extension_id.ends_with('}')) {
+        Ok(Family::Firefox)
+    } else {
+        Err(AppError::InvalidArgument(format!("Not a browser extension id: {}", extension_id)))
+    }
+}
+
+fn manifest(executable: &Path, family: Family, extension_ids: &[&str]) -> Value {
+    let mut manifest = json!({ "name": NAME, "description": DESCRIPTION, "path": executable, "type": "stdio" });
+    match family {
+        Family::Chromium => {
+            let origins: Vec<String> = extension_ids.iter().map(|id| format!("chrome-extension://{}/", id)).collect();
+            manifest["allowed_origins"] = json!(origins);
+        }
+        Family::Firefox => manifest["allowed_extensions"] = json!(extension_ids),
+    }
+    manifest
+}
+
+fn storage_error(action: &str, target: &Path, error: io::Error) -> AppError {
+    AppError::StorageFailed(format!("Failed to {} {}: {}", action, target.display(), error))
+}
+
+/// Registers the host for the given extensions with every installed browser
+/// of their kind. `data_dir` keeps the manifests the Windows registry points
+/// to. Returns where the manifests were written.
+pub fn install(executable: &Path, data_dir: &Path, extension_ids: &[String]) -> Result<Vec<String>, AppError> {
+    let mut chromium = Vec::new();
+    let mut firefox = Vec::new();
+    for id in extension_ids {
+        match family_of(id)? {
+            Family::Chromium => chromium.push(id.as_str()),
+            Family::Firefox => firefox.push(id.as_str()),
+        }
+    }
+    if chromium.is_empty() && firefox.is_empty() {
+        return Err(AppError::InvalidArgument("Name at least one extension id".to_string()));
+    }
+
+    let mut installed = Vec::new();
+    for browser in browsers() {
+        let ids = match browser.family {
+            Family::Chromium => &chromium,
+            Family::Firefox => &firefox,
+        };
+        if ids.is_empty() {
+            continue;
+        }
+        let contents = serde_json::to_string_pretty(&manifest(executable, browser.family, ids)).map_err(|e| AppError::StorageFailed(e.to_string()))?;
+        match browser.location {
+            Location::Directory(dir) => {
+                // Only browsers with a profile on this machine
+                if !dir.parent().is_some_and(Path::is_dir) {
+                    continue;
+                }
+                let path = dir.join(format!("{}.json", NAME));
+                fs::create_dir_all(&dir).and_then(|_| fs::write(&path, &contents)).map_err(|e| storage_error("write", &path, e))?;
+                installed.push(path.display().to_string());
+            }
+            Location::Registry(key) => {
+                let family = if browser.family == Family::Chromium { "chromium" } else { "firefox" };
+                let path = data_dir.join("native-messaging").join(format!("{}.json", family));
+                fs::create_dir_all(data_dir.join("native-messaging"))
+                    .and_then(|_| fs::write(&path, &contents))
+                    .map_err(|e| storage_error("write", &path, e))?;
+                let key = format!(r"HKCU\{}\{}", key, NAME);
+                let path_value = path.display().to_string();
+                reg(&["add", &key, "/ve", "/t", "REG_SZ", "/d", &path_value, "/f"])?;
+                installed.push(key);
+            }
+        }
+    }
+    Ok(installed)
+}
+
+/// Removes the host from every browser. Returns what was removed.
+pub fn uninstall(data_dir: &Path) -> Result<Vec<String>, AppError> {
+    let mut removed = Vec::new();
+    for browser in browsers() {
+        match browser.location {
+            Location::Directory(dir) => {
+                let path = dir.join(format!("{}.json", NAME));
+                match fs::remove_file(&path) {
+                    Ok(()) => removed.push(path.display().to_string()),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(storage_error("remove", &path, e)),
+                }
+            }
+            Location::Registry(key) => {
+                let key = format!(r"HKCU\{}\{}", key, NAME);
+                if reg(&["delete", &key, "/f"]).is_ok() {
+                    removed.push(key);
+                }
+            }
+        }
+    }
+    let _ = fs::remove_dir_all(data_dir.join("native-messaging"));
+    Ok(removed)
+}
+
+// `reg.exe` ships with every Windows, so no registry crate is needed
+fn reg(args: &[&str]) -> Result<(), AppError> {
+    let status = std::process::Command::new("reg")
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| AppError::StorageFailed(format!("Failed to run reg: {}", e)))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::StorageFailed(format!("reg {} failed with {}", args.join(" "), status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    struct Stub;
+
+    impl Corrector for Stub {
+        fn correct(&self, text: &str, options: &Options) -> Result<String, AppError> {
+            match options.model.as_deref() {
+                Some("missing") => Err(AppError::MissingApiKey("No OpenAI API key is configured".to_string())),
+                Some("verbose") => Ok(text.repeat(MAX_OUTGOING)),
+                _ => Ok(text.replace("teh", "the")),
+            }
+        }
+    }
+
+    fn frame(body: &[u8]) -> Vec<u8> {
+        let mut framed = (body.len() as u32).to_ne_bytes().to_vec();
+        framed.extend_from_slice(body);
+        framed
+    }
+
+    fn exchange(requests: &[Value]) -> Vec<Value> {
+        let input: Vec<u8> = requests.iter().flat_map(|request| frame(request.to_string().as_bytes())).collect();
+        let mut output = Vec::new();
+        serve(&input[..], &mut output, &Stub).unwrap();
+        let mut reader = &output[..];
+        std::iter::from_fn(|| read_message(&mut reader).unwrap()).map(|body| serde_json::from_slice(&body).unwrap()).collect()
+    }
+
+    #[test]
+    fn reads_length_prefixed_messages() {
+        let mut input = frame(b"{\"id\":1}");
+        input.extend(frame(b""));
+        let mut reader = &input[..];
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), b"{\"id\":1}");
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), b"");
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        let mut output = Vec::new();
+        write_message(&mut output, &json!({ "text": "é😀" })).unwrap();
+        assert_eq!(&output[..4], &(output.len() as u32 - 4).to_ne_bytes());
+        assert_eq!(read_message(&mut &output[..]).unwrap().unwrap(), "{\"text\":\"é😀\"}".as_bytes());
+    }
+
+    #[test]
+    fn refuses_truncated_and_oversized_messages() {
+        // The browser went away in the middle of a length
+        assert_eq!(read_message(&mut &[7u8, 0][..]).unwrap(), None);
+
+        let truncated = [&10u32.to_ne_bytes()[..], b"{}"].concat();
+        assert_eq!(read_message(&mut &truncated[..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        let oversized = (MAX_INCOMING as u32 + 1).to_ne_bytes();
+        assert_eq!(read_message(&mut &oversized[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn counts_offsets_in_utf16_code_units() {
+        let text = "é😀 teh";
+        assert_eq!(utf16_offset(text, 0), 0);
+        assert_eq!(utf16_offset(text, "é".len()), 1);
+        assert_eq!(utf16_offset(text, text.find(' ').unwrap()), 3);
+        assert_eq!(utf16_offset(text, text.len()), 7);
+    }
+
+    #[test]
+    fn answers_each_message() {
+        let replies = exchange(&[
+            json!({ "id": 1, "type": "correct", "text": "😀 teh text", "style": "formal" }),
+            json!({ "id": "two", "type": "correct", "text": "teh", "model": "missing" }),
+            json!({ "id": 3, "type": "explain", "text": "teh" }),
+            json!({ "id": 4, "type": "correct", "text": "teh", "model": "verbose" }),
+            json!(["not", "a", "request"]),
+        ]);
+        assert_eq!(
+            replies[0],
+            json!({
+                "id": 1,
+                "type": "corrected",
+                "text": "😀 the text",
+                "edits": [{ "original": "teh", "corrected": "the", "start": 3, "end": 6 }],
+            })
+        );
+        assert_eq!((&replies[1]["id"], &replies[1]["error"]["code"]), (&json!("two"), &json!("missing-api-key")));
+        assert_eq!((&replies[2]["type"], &replies[2]["error"]["code"]), (&json!("error"), &json!("invalid-argument")));
+        assert!(replies[3]["error"]["details"].as_str().unwrap().contains("too long"));
+        assert_eq!((&replies[4]["id"], &replies[4]["type"]), (&Value::Null, &json!("error")));
+    }
+
+    #[test]
+    fn tells_extension_ids_apart() {
+        assert_eq!(family_of("abcdefghijklmnopabcdefghijklmnop").unwrap(), Family::Chromium);
+        assert_eq!(family_of("correctify@example.org").unwrap(), Family::Firefox);
+        assert_eq!(family_of("{8f4c2c2e-5c67-4d2f-9a57-0c1b2f3e4d5a}").unwrap(), Family::Firefox);
+        let refused = ["", "abcdefghijklmnopabcdefghijklmnoq", "ABCDEFGHIJKLMNOPABCDEFGHIJKLMNOP", "abcdefghijklmnop", "{unclosed"];
+        for id in refused {
+            assert!(matches!(family_of(id), Err(AppError::InvalidArgument(_))), "{}", id);
+        }
+    }
+
+    #[test]
+    fn recognizes_a_browser_launch() {
+        let argv = |arguments: &[&str]| -> Vec<String> {
+            std::iter::once("correctify").chain(arguments.iter().copied()).map(str::to_string).collect()
+        };
+        assert!(launched_by_browser(&argv(&["chrome-extension://abcdefghijklmnopabcdefghijklmnop/"])));
+
+        let data = testing::data_dir();
+        let manifest = data.path().join(format!("{}.json", NAME));
+        fs::write(&manifest, "{}").unwrap();
+        let manifest = manifest.display().to_string();
+        assert!(launched_by_browser(&argv(&[&manifest, "correctify@example.org"])));
+        // Firefox always names the extension too
+        assert!(!launched_by_browser(&argv(&[&manifest])));
+        let missing = data.path().join("missing.json").display().to_string();
+        assert!(!launched_by_browser(&argv(&[&missing, "correctify@example.org"])));
+        assert!(!launched_by_browser(&argv(&["--show"])));
+        assert!(!launched_by_browser(&argv(&[])));
+    }
+
+    #[test]
+    fn writes_a_manifest_per_browser_family() {
+        let executable = Path::new("/opt/correctify/correctify");
+        let chromium = manifest(executable, Family::Chromium, &["abcdefghijklmnopabcdefghijklmnop"]);
+        assert_eq!(chromium["path"], "/opt/correctify/correctify");
+        assert_eq!(chromium["allowed_origins"], json!(["chrome-extension://abcdefghijklmnopabcdefghijklmnop/"]));
+        let firefox = manifest(executable, Family::Firefox, &["correctify@example.org"]);
+        assert_eq!((&firefox["name"], &firefox["type"]), (&json!(NAME), &json!("stdio")));
+        assert_eq!(firefox["allowed_extensions"], json!(["correctify@example.org"]));
+        assert!(firefox.get("allowed_origins").is_none());
+    }
+
+    #[test]
+    fn remembers_the_selected_settings() {
+        let data = testing::data_dir();
+        assert_eq!(SelectedSettings::load(data.path()), SelectedSettings::default());
+        let selected = SelectedSettings { model: Some("gpt-5.4-mini".to_string()), style: Some("formal".to_string()) };
+        selected.save(data.path()).unwrap();
+        assert_eq!(SelectedSettings::load(data.path()), selected);
+    }
+}
@@ -0,0 +1,65 @@
+//! Where Correctify keeps its data.
+//!
+//! Installed copies use the platform's app data and log directories. A copy
+//! with a `correctify.portable` marker file next to its executable is
+//! portable: data, logs and the webview profile live in a `CorrectifyData`
+//! directory beside it, keys go to the encrypted file instead of the OS
+//! vault, and nothing is registered with the system.
+
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+
+pub const PORTABLE_MARKER: &str = "correctify.portable";
+/// The bundle identifier from `tauri.conf.json`, which names the app's directories.
+const IDENTIFIER: &str = "com.correctify";
+const PORTABLE_DATA_DIRECTORY: &str = "CorrectifyData";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppPaths {
+    pub portable: bool,
+    /// The directory holding everything a portable copy writes.
+    pub root: Option<PathBuf>,
+    pub data_dir: PathBuf,
+    pub log_dir: PathBuf,
+    /// The webview profile, holding localStorage, when it is not the platform default.
+    #[serde(skip)]
+    pub webview_dir: Option<PathBuf>,
+}
+
+impl AppPaths {
+    pub fn installed(data_dir: PathBuf, log_dir: PathBuf) -> Self {
+        AppPaths { portable: false, root: None, data_dir, log_dir, webview_dir: None }
+    }
+
+    pub fn portable(root: &Path) -> Self {
+        AppPaths {
+            portable: true,
+            root: Some(root.to_path_buf()),
+            data_dir: root.join("data"),
+            log_dir: root.join("logs"),
+            webview_dir: Some(root.join("webview")),
+        }
+    }
+
+    /// The directories Tauri would pick, for modes that run without the app such as `correctify lsp`.
+    pub fn resolve() -> Option<Self> {
+        if let Some(root) = Self::portable_root() {
+            return Some(Self::portable(&root));
+        }
+        let data_dir = dirs::data_dir()?.join(IDENTIFIER);
+        #[cfg(target_os = "macos")]
+        let log_dir = dirs::home_dir()?.join("Library").join("Logs").join(IDENTIFIER);
+        #[cfg(not(target_os = "macos"))]
+        let log_dir = dirs::data_local_dir()?.join(IDENTIFIER).join("logs");
+        Some(Self::installed(data_dir, log_dir))
+    }
+
+    /// The data directory beside the executable, when the portable marker sits next to it.
+    pub fn portable_root() -> Option<PathBuf> {
+        let executable = env::current_exe().ok()?;
+        let directory = executable.parent()?;
+        directory.join(PORTABLE_MARKER).is_file().then(|| directory.join(PORTABLE_DATA_DIRECTORY))
+    }
+}
@@ -0,0 +1,283 @@
+//! Administrator policy.
+//!
+//! Managed installs can place a JSON policy file at a system-wide path that
+//! users cannot write to. The backend enforces it on every command that
+//! stores a key, picks a model or changes a locked setting; the webview only
+//! learns what is locked so it can disable the matching controls.
+//!
+//! ```json
+//! {
+//!   "allowedProviders": ["openai"],
+//!   "endpoints": { "openai": "https://contoso.openai.azure.com/openai/v1" },
+//!   "blockedModels": ["openrouter/*"],
+//!   "locked": { "autoPasteEnabled": false },
+//!   "defaults": { "writingStyle": "formal" }
+//! }
+//! ```
+
+use crate::error::AppError;
+use crate::llm::Provider;
+use crate::prompts::WRITING_STYLES;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Settings forced to a value. The interface shows them as managed.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LockedSettings {
+    pub auto_paste_enabled: Option<bool>,
+    pub sound_enabled: Option<bool>,
+    pub writing_style: Option<String>,
+}
+
+/// Starting values that users may still change.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Defaults {
+    pub writing_style: Option<String>,
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Policy {
+    /// Providers that keys may be stored for and requests sent to. Absent allows all.
+    pub allowed_providers: Option<Vec<Provider>>,
+    /// Model ids that may be used. Absent allows every model of an allowed provider.
+    pub allowed_models: Option<Vec<String>>,
+    /// Model ids that may not be used. A trailing `*` matches any suffix.
+    #[serde(default)]
+    pub blocked_models: Vec<String>,
+    /// Base URLs that replace a provider's public API and speak the same
+    /// protocol, such as a company Azure OpenAI deployment for `openai`.
+    #[serde(default)]
+    pub endpoints: BTreeMap<Provider, String>,
+    #[serde(default)]
+    pub locked: LockedSettings,
+    #[serde(default)]
+    pub defaults: Defaults,
+    /// Where the policy was read from.
+    #[serde(skip_deserializing)]
+    pub source: Option<PathBuf>,
+    /// Why the policy file could not be used. Everything is blocked until it is fixed.
+    #[serde(skip_deserializing)]
+    pub error: Option<String>,
+}
+
+#[cfg(target_os = "linux")]
+fn policy_path() -> Option<PathBuf> {
+    Some(PathBuf::from("/etc/correctify/policy.json"))
+}
+
+#[cfg(target_os = "macos")]
+fn policy_path() -> Option<PathBuf> {
+    Some(PathBuf::from("/Library/Application Support/Correctify/policy.json"))
+}
+
+#[cfg(target_os = "windows")]
+fn policy_path() -> Option<PathBuf> {
+    let program_data = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+    Some(PathBuf::from(program_data).join("Correctify").join("policy.json"))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn policy_path() -> Option<PathBuf> {
+    None
+}
+
+fn matches(pattern: &str, model: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => model.starts_with(prefix),
+        None => pattern == model,
+    }
+}
+
+impl Policy {
+    /// Reads the system policy. No file means no restrictions.
+    pub fn load() -> Policy {
+        match policy_path() {
+            Some(path) => Self::read(&path),
+            None => Policy::default(),
+        }
+    }
+
+    fn read(path: &Path) -> Policy {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Policy::default(),
+            Err(e) => return Self::unusable(path, format!("Failed to read the policy file: {}", e)),
+        };
+        let policy = serde_json::from_str::<Policy>(&contents)
+            .map_err(|e| format!("The policy file is invalid: {}", e))
+            .and_then(Policy::validated);
+        match policy {
+            Ok(policy) => {
+                tracing::info!(path = %path.display(), "Administrator policy applied");
+                Policy { source: Some(path.to_path_buf()), ..policy }
+            }
+            Err(error) => Self::unusable(path, error),
+        }
+    }
+
+    // A broken policy fails closed: no provider is allowed until it is fixed
+    fn unusable(path: &Path, error: String) -> Policy {
+        tracing::error!(path = %path.display(), "{}", error);
+        Policy {
+            allowed_providers: Some(Vec::new()),
+            source: Some(path.to_path_buf()),
+            error: Some(error),
+            ..Policy::default()
+        }
+    }
+
+    fn validated(self) -> Result<Policy, String> {
+        let styles = [&self.locked.writing_style, &self.defaults.writing_style];
+        if let Some(style) = styles.into_iter().flatten().find(|style| !WRITING_STYLES.contains(&style.as_str())) {
+            return Err(format!("Unknown writing style '{}' in the policy file", style));
+        }
+        if let Some((provider, _)) = self.endpoints.iter().find(|(_, url)| !url.starts_with("https://")) {
+            return Err(format!("The {} endpoint in the policy file must use https", provider.label()));
+        }
+        Ok(self)
+    }
+
+    pub fn is_managed(&self) -> bool {
+        self.source.is_some()
+    }
+
+    pub fn allows_provider(&self, provider: Provider) -> bool {
+        self.allowed_providers.as_ref().is_none_or(|allowed| allowed.contains(&provider))
+    }
+
+    pub fn check_provider(&self, provider: Provider) -> Result<(), AppError> {
+        if self.allows_provider(provider) {
+            return Ok(());
+        }
+        Err(AppError::BlockedByPolicy(match &self.error {
+            Some(error) => error.clone(),
+            None => format!("{} is not allowed by your organization's policy", provider.label()),
+        }))
+    }
+
+    pub fn check_model(&self, model: &str, provider: Provider) -> Result<(), AppError> {
+        self.check_provider(provider)?;
+        let listed = self.allowed_models.as_ref().is_none_or(|allowed| allowed.iter().any(|id| id == model));
+        if !listed || self.blocked_models.iter().any(|pattern| matches(pattern, model)) {
+            return Err(AppError::BlockedByPolicy(format!(
+                "The model '{}' is not allowed by your organization's policy",
+                model
+            )));
+        }
+        Ok(())
+    }
+
+    /// Fails when `value` differs from the value the policy locks the setting to.
+    pub fn check_locked<T: PartialEq + ?Sized>(&self, setting: &str, locked: Option<&T>, value: &T) -> Result<(), AppError> {
+        match locked {
+            Some(locked) if locked != value => Err(AppError::BlockedByPolicy(format!(
+                "The setting '{}' is managed by your organization's policy",
+                setting
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use tempfile::TempDir;
+
+    fn read(contents: &str) -> (TempDir, Policy) {
+        let data = testing::data_dir();
+        let path = data.path().join("policy.json");
+        fs::write(&path, contents).unwrap();
+        let policy = Policy::read(&path);
+        (data, policy)
+    }
+
+    #[test]
+    fn reads_a_policy_file() {
+        let (data, policy) = read(
+            r#"{
+                "allowedProviders": ["openai"],
+                "endpoints": { "openai": "https://contoso.openai.azure.com/openai/v1" },
+                "blockedModels": ["openrouter/*"],
+                "locked": { "autoPasteEnabled": false },
+                "defaults": { "writingStyle": "formal" }
+            }"#,
+        );
+        assert!(policy.is_managed());
+        assert_eq!(policy.source.as_deref(), Some(data.path().join("policy.json").as_path()));
+        assert_eq!(policy.error, None);
+        assert_eq!(policy.allowed_providers, Some(vec![Provider::OpenAI]));
+        assert_eq!(policy.endpoints[&Provider::OpenAI], "https://contoso.openai.azure.com/openai/v1");
+        assert_eq!(policy.locked.auto_paste_enabled, Some(false));
+        assert_eq!(policy.defaults.writing_style.as_deref(), Some("formal"));
+    }
+
+    #[test]
+    fn no_policy_file_means_no_restrictions() {
+        let data = testing::data_dir();
+        let policy = Policy::read(&data.path().join("policy.json"));
+        assert!(!policy.is_managed());
+        assert!(Provider::ALL.into_iter().all(|provider| policy.allows_provider(provider)));
+        assert!(policy.check_model("any-model", Provider::OpenRouter).is_ok());
+    }
+
+    #[test]
+    fn a_broken_policy_file_blocks_every_provider() {
+        let broken = [
+            "{ not json",
+            r#"{ "allowedProviders": ["openai"], "lockedSettings": {} }"#,
+            r#"{ "locked": { "writingStyle": "poetic" } }"#,
+            r#"{ "defaults": { "writingStyle": "pirate" } }"#,
+            r#"{ "endpoints": { "openai": "http://proxy.internal/v1" } }"#,
+        ];
+        for contents in broken {
+            let (_data, policy) = read(contents);
+            assert!(policy.is_managed(), "{}", contents);
+            assert_eq!(policy.allowed_providers, Some(Vec::new()), "{}", contents);
+            let error = policy.error.clone().unwrap();
+            let blocked = policy.check_provider(Provider::OpenAI).err().unwrap();
+            assert!(matches!(blocked, AppError::BlockedByPolicy(details) if details == error), "{}", contents);
+        }
+    }
+
+    #[test]
+    fn allows_only_the_listed_providers_and_models() {
+        let policy = Policy {
+            allowed_providers: Some(vec![Provider::OpenAI, Provider::OpenRouter]),
+            allowed_models: Some(vec!["gpt-4o-mini".to_string(), "openai/gpt-4o".to_string()]),
+            blocked_models: vec!["openai/*".to_string()],
+            ..Policy::default()
+        };
+        assert!(policy.allows_provider(Provider::OpenRouter));
+        assert!(!policy.allows_provider(Provider::Anthropic));
+        assert!(policy.check_model("gpt-4o-mini", Provider::OpenAI).is_ok());
+        let refused = [
+            ("gpt-4o", Provider::OpenAI),
+            ("openai/gpt-4o", Provider::OpenRouter),
+            ("gpt-4o-mini", Provider::Anthropic),
+        ];
+        for (model, provider) in refused {
+            assert!(matches!(policy.check_model(model, provider), Err(AppError::BlockedByPolicy(_))), "{}", model);
+        }
+
+        let blocked = Policy { blocked_models: vec!["claude-3-opus".to_string()], ..Policy::default() };
+        assert!(blocked.check_model("claude-3-opus", Provider::Anthropic).is_err());
+        assert!(blocked.check_model("claude-3-opus-latest", Provider::Anthropic).is_ok());
+    }
+
+    #[test]
+    fn refuses_values_other_than_the_locked_one() {
+        let policy = Policy::default();
+        assert!(policy.check_locked("writingStyle", Some("formal"), "formal").is_ok());
+        assert!(policy.check_locked("writingStyle", None, "informal").is_ok());
+        let refused = policy.check_locked("autoPasteEnabled", Some(&false), &true).err().unwrap();
+        assert!(matches!(refused, AppError::BlockedByPolicy(details) if details.contains("'autoPasteEnabled'")));
+    }
+}
@@ -0,0 +1,125 @@
+//! Word-level review of a correction, matching `lib/review.ts`.
+//!
+//! Whitespace runs, words and single punctuation marks are tokens, so an edit
+//! never rebuilds the Markdown around it.
+
+/// One change between the original and the corrected text. `start` and `end`
+/// are byte offsets of `original` in the original text; an insertion has
+/// `start == end`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewEdit {
+    pub original: String,
+    pub corrected: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Larger inputs are reported as a single edit instead of building the table
+const MAX_TABLE_CELLS: usize = 2_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Space,
+    Word,
+    Other,
+}
+
+fn class(c: char) -> Class {
+    if c.is_whitespace() {
+        Class::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        Class::Word
+    } else {
+        Class::Other
+    }
+}
+
+struct Token<'a> {
+    value: &'a str,
+    start: usize,
+    end: usize,
+}
+
+fn tokens(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let kind = class(c);
+        let mut end = start + c.len_utf8();
+        if kind != Class::Other {
+            while let Some(&(next_start, next)) = chars.peek() {
+                if class(next) != kind {
+                    break;
+                }
+                end = next_start + next.len_utf8();
+                chars.next();
+            }
+        }
+        tokens.push(Token { value: &text[start..end], start, end });
+    }
+    tokens
+}
+
+/// The edits that turn `original` into `corrected`, in order.
+pub fn edits(original: &str, corrected: &str) -> Vec<ReviewEdit> {
+    let before = tokens(original);
+    let after = tokens(corrected);
+    let (rows, cols) = (before.len() + 1, after.len() + 1);
+    if rows.saturating_mul(cols) > MAX_TABLE_CELLS {
+        return vec![ReviewEdit { original: original.to_string(), corrected: corrected.to_string(), start: 0, end: original.len() }];
+    }
+
+    // table[i][j] is the longest common subsequence of before[i..] and after[j..]
+    let mut table = vec![0u32; rows * cols];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            table[i * cols + j] = if before[i].value == after[j].value {
+                table[(i + 1) * cols + j + 1] + 1
+            } else {
+                table[(i + 1) * cols + j].max(table[i * cols + j + 1])
+            };
+        }
+    }
+
+    let mut edits = Vec::new();
+    let mut pending: Option<ReviewEdit> = None;
+    let (mut i, mut j) = (0, 0);
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i].value == after[j].value {
+            edits.extend(pending.take());
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let position = before.get(i).map_or(original.len(), |token| token.start);
+        let edit = pending.get_or_insert_with(|| ReviewEdit {
+            original: String::new(),
+            corrected: String::new(),
+            start: position,
+            end: position,
+        });
+        if j < after.len() && (i == before.len() || table[i * cols + j + 1] >= table[(i + 1) * cols + j]) {
+            edit.corrected.push_str(after[j].value);
+            j += 1;
+        } else {
+            edit.original.push_str(before[i].value);
+            edit.end = before[i].end;
+            i += 1;
+        }
+    }
+    edits.extend(pending);
+    edits
+}
+
+/// `original` with `edits` applied. Edits must be in order and must not overlap.
+pub fn apply<'a>(original: &str, edits: impl IntoIterator<Item = &'a ReviewEdit>) -> String {
+    let mut result = String::with_capacity(original.len());
+    let mut position = 0;
+    for edit in edits {
+        result.push_str(&original[position..edit.start]);
+        result.push_str(&edit.corrected);
+        position = edit.end;
+    }
+    result.push_str(&original[position..]);
+    result
+}
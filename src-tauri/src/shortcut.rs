@@ -0,0 +1,416 @@
+//! Parsing and validation for the global correction shortcut.
+//!
+//! Shortcuts are stored as a modifier string from the settings dropdown
+//! (`CmdOrCtrl+Shift`, `CmdOrCtrl+Alt`, `AltOrOption+Shift`,
+//! `CmdOrCtrl+Alt+Shift`) plus one key name that the global-shortcut plugin
+//! understands. Keys are physical keys, so a captured combination is
+//! independent of the active keyboard layout.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+
+const NAMED_KEYS: [&str; 13] = [
+    "Space", "Tab", "Escape", "Insert", "Delete", "Home", "End", "PageUp", "PageDown", "Up", "Down", "Left", "Right",
+];
+const PUNCTUATION_KEYS: &str = "`-=[]\\;',./";
+
+/// A validated shortcut in the form the frontend stores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Combination {
+    pub modifier: String,
+    pub key: String,
+}
+
+/// Whether a valid combination can be registered right now.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Availability {
+    /// Correctify already uses this combination.
+    Current,
+    Available,
+    /// Another application or the OS holds the combination.
+    Taken,
+}
+
+/// Result of checking a combination before it is saved.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutCheck {
+    #[serde(flatten)]
+    pub combination: Combination,
+    pub availability: Availability,
+}
+
+/// The shortcut Correctify holds, and whether it is registered at all.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    #[serde(flatten)]
+    pub combination: Combination,
+    /// False when neither the chosen combination nor any fallback could be registered.
+    pub registered: bool,
+}
+
+/// A `keydown` event forwarded from the webview while capturing.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEvent {
+    /// `KeyboardEvent.code`, the physical key.
+    pub code: String,
+    pub ctrl_key: bool,
+    pub alt_key: bool,
+    pub shift_key: bool,
+    pub meta_key: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Modifiers {
+    primary: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Modifiers {
+    // Single-modifier combinations shadow ordinary app shortcuts everywhere, so
+    // only the dropdown's two- and three-modifier sets are accepted
+    fn canonical(self) -> Option<&'static str> {
+        match (self.primary, self.alt, self.shift) {
+            (true, false, true) => Some("CmdOrCtrl+Shift"),
+            (true, true, false) => Some("CmdOrCtrl+Alt"),
+            (false, true, true) => Some("AltOrOption+Shift"),
+            (true, true, true) => Some("CmdOrCtrl+Alt+Shift"),
+            _ => None,
+        }
+    }
+}
+
+impl Combination {
+    /// Parses and validates a modifier string and key, rejecting unknown
+    /// tokens and combinations reserved by the OS or by common editing shortcuts.
+    pub fn parse(modifier: &str, key: &str) -> Result<Self, AppError> {
+        let modifiers = parse_modifiers(modifier)?;
+        let key = normalize_key(key)?;
+        Self::validate(modifiers, key)
+    }
+
+    /// Builds a combination from a captured key press.
+    pub fn from_key_event(event: &KeyEvent) -> Result<Self, AppError> {
+        // Cmd is the primary modifier on macOS; elsewhere it is Ctrl and the
+        // Windows/Super key cannot be registered reliably
+        let (primary, unsupported) = if cfg!(target_os = "macos") {
+            (event.meta_key, event.ctrl_key.then_some("Control"))
+        } else {
+            (event.ctrl_key, event.meta_key.then_some("Super"))
+        };
+        if let Some(name) = unsupported {
+            return Err(AppError::InvalidShortcut(format!("The {} modifier is not supported", name)));
+        }
+        let modifiers = Modifiers { primary, alt: event.alt_key, shift: event.shift_key };
+        let key = key_from_code(&event.code)?;
+        Self::validate(modifiers, key)
+    }
+
+    /// The accelerator string the global-shortcut plugin registers.
+    pub fn accelerator(&self) -> String {
+        format!("{}+{}", platform_modifier(&self.modifier), self.key)
+    }
+
+    fn validate(modifiers: Modifiers, key: String) -> Result<Self, AppError> {
+        let Some(modifier) = modifiers.canonical() else {
+            return Err(AppError::InvalidShortcut(
+                "Use Cmd/Ctrl or Alt/Option together with at least one other modifier".to_string(),
+            ));
+        };
+        if let Some(reason) = reserved_reason(modifiers, &key) {
+            return Err(AppError::ShortcutReserved(format!("{}+{} is {}", modifier, key, reason)));
+        }
+        Ok(Combination { modifier: modifier.to_string(), key })
+    }
+}
+
+/// The default shortcut for a UI locale. `]` needs AltGr on German, French
+/// and Turkish keyboards, so those locales default to a letter key that is
+/// labelled the same on every layout they use.
+pub fn default_for_locale(locale: &str) -> Combination {
+    let key = match locale {
+        "de" | "fr" | "tr" => "K",
+        _ => "]",
+    };
+    Combination { modifier: "CmdOrCtrl+Shift".to_string(), key: key.to_string() }
+}
+
+/// Combinations to try in turn when `preferred` cannot be registered at
+/// startup: the locale default, then `K` with each modifier set. Ones this
+/// platform reserves are left out.
+pub fn fallbacks(preferred: &Combination, locale: &str) -> Vec<Combination> {
+    let mut candidates = vec![preferred.clone(), default_for_locale(locale)];
+    for modifier in ["CmdOrCtrl+Shift", "CmdOrCtrl+Alt", "AltOrOption+Shift", "CmdOrCtrl+Alt+Shift"] {
+        if let Ok(combination) = Combination::parse(modifier, "K") {
+            candidates.push(combination);
+        }
+    }
+    let mut unique: Vec<Combination> = Vec::new();
+    for candidate in candidates {
+        if !unique.contains(&candidate) {
+            unique.push(candidate);
+        }
+    }
+    unique
+}
+
+// Converts modifier string to platform-specific format
+// Tauri uses "Alt" for Option key on macOS, and "Alt" for Alt key on Windows/Linux
+pub fn platform_modifier(modifier: &str) -> String {
+    #[cfg(target_os = "macos")]
+    {
+        modifier
+            .replace("CmdOrCtrl", "Cmd")
+            .replace("AltOrOption", "Alt")
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        modifier
+            .replace("CmdOrCtrl", "Ctrl")
+            .replace("AltOrOption", "Alt")
+    }
+}
+
+fn parse_modifiers(modifier: &str) -> Result<Modifiers, AppError> {
+    let mut modifiers = Modifiers { primary: false, alt: false, shift: false };
+    for token in modifier.split('+').map(str::trim) {
+        match token.to_ascii_lowercase().as_str() {
+            "cmdorctrl" | "commandorcontrol" => modifiers.primary = true,
+            "alt" | "option" | "altoroption" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            _ => return Err(AppError::InvalidShortcut(format!("Unknown modifier '{}'", token))),
+        }
+    }
+    Ok(modifiers)
+}
+
+fn normalize_key(key: &str) -> Result<String, AppError> {
+    let key = key.trim();
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        if c.is_ascii_alphanumeric() {
+            return Ok(c.to_ascii_uppercase().to_string());
+        }
+        if PUNCTUATION_KEYS.contains(c) {
+            return Ok(c.to_string());
+        }
+        // Layout-specific characters (ö, ç, ğ) have no stable physical key
+        return Err(AppError::InvalidShortcut(format!("'{}' cannot be used as a shortcut key; record the shortcut instead", c)));
+    }
+
+    if let Some(number) = function_key_number(key) {
+        return Ok(format!("F{}", number));
+    }
+    let name = match key.strip_prefix("Arrow").unwrap_or(key) {
+        "Esc" | "esc" => "Escape",
+        name => name,
+    };
+    NAMED_KEYS
+        .iter()
+        .find(|named| named.eq_ignore_ascii_case(name))
+        .map(|named| named.to_string())
+        .ok_or_else(|| AppError::InvalidShortcut(format!("Unknown key '{}'", key)))
+}
+
+fn function_key_number(key: &str) -> Option<u8> {
+    let number: u8 = key.strip_prefix(['F', 'f'])?.parse().ok()?;
+    (1..=24).contains(&number).then_some(number)
+}
+
+// Maps a `KeyboardEvent.code` to the key name the plugin parses
+fn key_from_code(code: &str) -> Result<String, AppError> {
+    if let Some(letter) = code.strip_prefix("Key") {
+        return normalize_key(letter);
+    }
+    if let Some(digit) = code.strip_prefix("Digit") {
+        return normalize_key(digit);
+    }
+    let punctuation = match code {
+        "Backquote" => Some("`"),
+        "Minus" => Some("-"),
+        "Equal" => Some("="),
+        "BracketLeft" => Some("["),
+        "BracketRight" => Some("]"),
+        "Backslash" => Some("\\"),
+        "Semicolon" => Some(";"),
+        "Quote" => Some("'"),
+        "Comma" => Some(","),
+        "Period" => Some("."),
+        "Slash" => Some("/"),
+        _ => None,
+    };
+    match punctuation {
+        Some(key) => Ok(key.to_string()),
+        None => normalize_key(code),
+    }
+}
+
+// Explains why a combination is off limits, or None when it is usable
+fn reserved_reason(modifiers: Modifiers, key: &str) -> Option<&'static str> {
+    let Modifiers { primary, alt, shift } = modifiers;
+
+    if key == "Tab" || (key == "Space" && (primary || alt)) {
+        return Some("reserved for switching apps or input sources");
+    }
+    if primary && shift && !alt && key == "Z" {
+        return Some("the standard Redo shortcut");
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        if primary && shift && !alt && ["3", "4", "5", "6"].contains(&key) {
+            return Some("reserved for macOS screenshots");
+        }
+        if primary && alt && !shift && ["D", "Escape"].contains(&key) {
+            return Some("reserved by macOS");
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        if primary && alt && key == "Delete" {
+            return Some("reserved by Windows");
+        }
+        if primary && shift && key == "Escape" {
+            return Some("reserved for Task Manager");
+        }
+        if primary && alt && !shift && ["Left", "Right", "Up", "Down"].contains(&key) {
+            return Some("reserved for display rotation on many Windows drivers");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if primary && alt && !shift {
+            if key == "T" || key == "Delete" {
+                return Some("reserved by most Linux desktops");
+            }
+            if ["Left", "Right", "Up", "Down"].contains(&key) {
+                return Some("reserved for switching workspaces");
+            }
+            if function_key_number(key).is_some_and(|number| number <= 12) {
+                return Some("reserved for switching virtual terminals");
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modifiers(modifier: &str) -> Modifiers {
+        parse_modifiers(modifier).unwrap()
+    }
+
+    #[test]
+    fn modifiers_are_canonical_in_any_order_and_spelling() {
+        for modifier in ["CmdOrCtrl+Shift", "Shift+CmdOrCtrl", " shift + commandorcontrol "] {
+            assert_eq!(Combination::parse(modifier, "k").unwrap().modifier, "CmdOrCtrl+Shift");
+        }
+        assert_eq!(Combination::parse("Option+Shift", "K").unwrap().modifier, "AltOrOption+Shift");
+        assert_eq!(Combination::parse("Shift+Alt+CmdOrCtrl", "K").unwrap().modifier, "CmdOrCtrl+Alt+Shift");
+        assert_eq!(Combination::parse("Alt+CmdOrCtrl", "K").unwrap().modifier, "CmdOrCtrl+Alt");
+    }
+
+    #[test]
+    fn rejects_unknown_and_single_modifiers() {
+        assert!(matches!(Combination::parse("Hyper+Shift", "K"), Err(AppError::InvalidShortcut(_))));
+        assert!(matches!(Combination::parse("CmdOrCtrl", "K"), Err(AppError::InvalidShortcut(_))));
+        assert!(matches!(Combination::parse("Shift", "K"), Err(AppError::InvalidShortcut(_))));
+        assert!(matches!(Combination::parse("", "K"), Err(AppError::InvalidShortcut(_))));
+    }
+
+    #[test]
+    fn normalizes_bare_and_named_keys() {
+        let key = |key: &str| Combination::parse("CmdOrCtrl+Shift", key).unwrap().key;
+        assert_eq!(key("k"), "K");
+        assert_eq!(key("7"), "7");
+        assert_eq!(key("]"), "]");
+        assert_eq!(key("f5"), "F5");
+        assert_eq!(key("F24"), "F24");
+        assert_eq!(key("esc"), "Escape");
+        assert_eq!(key("ArrowLeft"), "Left");
+        assert_eq!(key("pagedown"), "PageDown");
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        for key in ["", "F0", "F25", "Enter", "ö", "KK"] {
+            assert!(
+                matches!(Combination::parse("CmdOrCtrl+Shift", key), Err(AppError::InvalidShortcut(_))),
+                "{:?} was accepted",
+                key
+            );
+        }
+    }
+
+    #[test]
+    fn reserves_combinations_on_every_platform() {
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "Tab").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Space").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "Z").is_some());
+        assert!(reserved_reason(modifiers("AltOrOption+Shift"), "Space").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt+Shift"), "Z").is_none());
+        assert!(matches!(Combination::parse("CmdOrCtrl+Shift", "z"), Err(AppError::ShortcutReserved(_))));
+        assert!(Combination::parse("CmdOrCtrl+Shift", "]").is_ok());
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn reserves_macos_combinations() {
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "4").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "D").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Escape").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "T").is_none());
+    }
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn reserves_windows_combinations() {
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Delete").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "Escape").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Left").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "4").is_none());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reserves_linux_combinations() {
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "T").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Delete").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "Up").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "F2").is_some());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt"), "F13").is_none());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Alt+Shift"), "T").is_none());
+        assert!(reserved_reason(modifiers("CmdOrCtrl+Shift"), "4").is_none());
+    }
+
+    #[test]
+    fn locale_defaults_avoid_altgr_keys() {
+        for locale in ["de", "fr", "tr"] {
+            assert_eq!(default_for_locale(locale), Combination::parse("CmdOrCtrl+Shift", "K").unwrap());
+        }
+        for locale in ["en", "es", "ja", ""] {
+            assert_eq!(default_for_locale(locale), Combination::parse("CmdOrCtrl+Shift", "]").unwrap());
+        }
+    }
+
+    #[test]
+    fn fallbacks_start_with_the_preferred_combination_without_repeats() {
+        let preferred = Combination::parse("CmdOrCtrl+Alt", "J").unwrap();
+        let candidates = fallbacks(&preferred, "de");
+        assert_eq!(candidates[0], preferred);
+        assert_eq!(candidates[1], default_for_locale("de"));
+        for (index, candidate) in candidates.iter().enumerate() {
+            assert!(!candidates[index + 1..].contains(candidate));
+        }
+    }
+}
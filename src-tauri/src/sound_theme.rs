@@ -0,0 +1,228 @@
+//! Freedesktop sound theme lookup for Linux desktops.
+//!
+//! Implements the lookup order of the XDG sound theme specification: the
+//! user's theme, its parents, then the `freedesktop` theme. Each event name is
+//! tried from most to least specific (`dialog-warning`, then `dialog`).
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FALLBACK_THEME: &str = "freedesktop";
+const SOUND_EXTENSIONS: [&str; 3] = ["oga", "ogg", "wav"];
+
+pub enum ThemeSound {
+    /// Event sounds are switched off globally, or the theme disables this event.
+    Muted,
+    /// A themed file to play instead of the bundled sound.
+    File(PathBuf),
+    /// No theme provides the event; play the bundled sound.
+    Missing,
+}
+
+struct ThemeIndex {
+    inherits: Vec<String>,
+    directories: Vec<String>,
+}
+
+// Maps Correctify's sound names to freedesktop event names
+fn event_name(sound_type: &str) -> Option<&'static str> {
+    match sound_type {
+        "completed" => Some("complete"),
+        "empty" => Some("dialog-warning"),
+        "processing" => Some("dialog-information"),
+        _ => None,
+    }
+}
+
+/// Resolves a Correctify sound through the desktop sound theme. Blocks briefly
+/// on `gsettings`, so call it from the playback thread.
+pub fn resolve(sound_type: &str) -> ThemeSound {
+    let Some(event) = event_name(sound_type) else {
+        return ThemeSound::Missing;
+    };
+    if gsetting("event-sounds").as_deref() == Some("false") {
+        return ThemeSound::Muted;
+    }
+    let theme = gsetting("theme-name")
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| FALLBACK_THEME.to_string());
+    lookup(event, &theme, &sound_roots())
+}
+
+// Reads a key from the desktop sound schema; None when gsettings is unavailable
+fn gsetting(key: &str) -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.sound", key])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().trim_matches('\'').to_string())
+}
+
+// $XDG_DATA_HOME/sounds followed by each $XDG_DATA_DIRS entry, as the spec requires
+fn sound_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    match env::var_os("XDG_DATA_HOME").filter(|value| !value.is_empty()) {
+        Some(data_home) => roots.push(PathBuf::from(data_home)),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                roots.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    roots.extend(data_dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from));
+    roots.into_iter().map(|root| root.join("sounds")).collect()
+}
+
+fn lookup(event: &str, theme: &str, roots: &[PathBuf]) -> ThemeSound {
+    let mut themes = Vec::new();
+    collect_themes(theme, roots, &mut themes);
+    collect_themes(FALLBACK_THEME, roots, &mut themes);
+
+    let mut name = event;
+    loop {
+        for (theme, index) in &themes {
+            for root in roots {
+                let theme_dir = root.join(theme);
+                for directory in &index.directories {
+                    if let Some(found) = find_in_dir(&theme_dir.join(directory), name) {
+                        return found;
+                    }
+                }
+            }
+        }
+        match name.rfind('-') {
+            Some(position) => name = &name[..position],
+            None => break,
+        }
+    }
+
+    // Unthemed sounds may live directly in a sounds directory
+    roots
+        .iter()
+        .find_map(|root| find_in_dir(root, event))
+        .unwrap_or(ThemeSound::Missing)
+}
+
+fn find_in_dir(dir: &Path, name: &str) -> Option<ThemeSound> {
+    if dir.join(format!("{}.disabled", name)).exists() {
+        return Some(ThemeSound::Muted);
+    }
+    SOUND_EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(format!("{}.{}", name, extension)))
+        .find(|path| path.is_file())
+        .map(ThemeSound::File)
+}
+
+// Depth-first over Inherits=, skipping themes already visited to survive cycles
+fn collect_themes(theme: &str, roots: &[PathBuf], themes: &mut Vec<(String, ThemeIndex)>) {
+    if themes.iter().any(|(name, _)| name == theme) {
+        return;
+    }
+    let Some(index) = roots
+        .iter()
+        .find_map(|root| fs::read_to_string(root.join(theme).join("index.theme")).ok())
+        .map(|contents| parse_index(&contents))
+    else {
+        return;
+    };
+    let parents = index.inherits.clone();
+    themes.push((theme.to_string(), index));
+    for parent in parents {
+        collect_themes(&parent, roots, themes);
+    }
+}
+
+fn parse_index(contents: &str) -> ThemeIndex {
+    let mut section = String::new();
+    let mut inherits = Vec::new();
+    let mut directories = Vec::new();
+    let mut other_profiles = Vec::new();
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            section = line[1..line.len() - 1].to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let list = || value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty());
+        match (section.as_str(), key.trim()) {
+            ("Sound Theme", "Inherits") => inherits.extend(list()),
+            ("Sound Theme", "Directories") => directories.extend(list()),
+            (_, "OutputProfile") if value.trim() != "stereo" => other_profiles.push(section.clone()),
+            _ => {}
+        }
+    }
+
+    // Only stereo output is supported; keep the conventional layout if none is declared
+    directories.retain(|directory| !other_profiles.contains(directory));
+    if directories.is_empty() {
+        directories.push("stereo".to_string());
+    }
+    ThemeIndex { inherits, directories }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use tempfile::TempDir;
+
+    fn write(path: PathBuf, contents: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn found(sound: ThemeSound) -> Option<PathBuf> {
+        match sound {
+            ThemeSound::File(path) => Some(path),
+            ThemeSound::Muted | ThemeSound::Missing => None,
+        }
+    }
+
+    // A user theme in the first root inheriting a system theme in the second, plus freedesktop
+    fn themes() -> (TempDir, Vec<PathBuf>) {
+        let directory = testing::data_dir();
+        let (user, system) = (directory.path().join("user"), directory.path().join("system"));
+        write(user.join("custom/index.theme"), "[Sound Theme]\nName=Custom\nInherits=parent\nDirectories=stereo\n");
+        write(user.join("custom/stereo/complete.oga"), "");
+        write(system.join("parent/index.theme"), "[Sound Theme]\nInherits=custom\nDirectories=stereo,5.1\n\n[5.1]\nOutputProfile=5.1\n");
+        write(system.join("parent/stereo/dialog.ogg"), "");
+        write(system.join("parent/5.1/message.ogg"), "");
+        write(system.join("freedesktop/index.theme"), "[Sound Theme]\nDirectories=stereo\n");
+        write(system.join("freedesktop/stereo/message.oga"), "");
+        write(system.join("freedesktop/stereo/bell.wav"), "");
+        write(system.join("freedesktop/stereo/bell.disabled"), "");
+        (directory, vec![user, system])
+    }
+
+    #[test]
+    fn follows_the_theme_then_its_parents_then_freedesktop() {
+        let (_directory, roots) = themes();
+        assert_eq!(found(lookup("complete", "custom", &roots)), Some(roots[0].join("custom/stereo/complete.oga")));
+        // Only the parent has `dialog`, found by dropping the most specific part of the name
+        assert_eq!(found(lookup("dialog-warning", "custom", &roots)), Some(roots[1].join("parent/stereo/dialog.ogg")));
+        // The parent's surround directory is skipped, so freedesktop answers
+        assert_eq!(found(lookup("message-new-email", "custom", &roots)), Some(roots[1].join("freedesktop/stereo/message.oga")));
+    }
+
+    #[test]
+    fn disabled_and_unknown_events() {
+        let (_directory, roots) = themes();
+        assert!(matches!(lookup("bell", "custom", &roots), ThemeSound::Muted));
+        assert!(matches!(lookup("suspend-error", "custom", &roots), ThemeSound::Missing));
+        // A theme that is not installed falls back to freedesktop alone
+        assert_eq!(found(lookup("message", "missing", &roots)), Some(roots[1].join("freedesktop/stereo/message.oga")));
+    }
+}
@@ -0,0 +1,17 @@
+//! Helpers shared by the unit tests.
+
+use crate::credentials::Credentials;
+use tempfile::TempDir;
+
+/// An empty data directory, deleted with its contents when dropped.
+pub fn data_dir() -> TempDir {
+    tempfile::Builder::new().prefix("correctify-").tempdir().unwrap()
+}
+
+/// An unlocked, empty encrypted-file store in its own data directory.
+pub fn credentials() -> (TempDir, Credentials) {
+    let data = data_dir();
+    let credentials = Credentials::open(data.path().to_path_buf(), true);
+    credentials.unlock("test passphrase").unwrap();
+    (data, credentials)
+}